@@ -0,0 +1,59 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::Mutex;
+
+use super::{ProcessBackend, ProcessStats};
+
+/// In-memory backend for tests: processes and listening ports are whatever was declared,
+/// and a kill just forgets the process
+#[derive(Default)]
+pub struct MockBackend {
+    running: Mutex<HashSet<u32>>,
+    listeners: Mutex<HashMap<u16, Vec<u32>>>,
+}
+
+impl MockBackend {
+    /// Declare a running process, optionally listening on `port`
+    pub fn with_process(self, pid: u32, port: Option<u16>) -> Self {
+        self.running.lock().unwrap().insert(pid);
+        if let Some(port) = port {
+            self.listeners.lock().unwrap().entry(port).or_default().push(pid);
+        }
+        self
+    }
+}
+
+impl ProcessBackend for MockBackend {
+    fn pids_on_port(&self, port: u16) -> Vec<u32> {
+        self.listeners.lock().unwrap().get(&port).cloned().unwrap_or_default()
+    }
+
+    fn is_pid_running(&self, pid: u32) -> bool {
+        self.running.lock().unwrap().contains(&pid)
+    }
+
+    fn kill(&self, pid: u32) -> bool {
+        let was_running = self.running.lock().unwrap().remove(&pid);
+        for pids in self.listeners.lock().unwrap().values_mut() {
+            pids.retain(|&p| p != pid);
+        }
+        was_running
+    }
+
+    fn kill_tree(&self, pid: u32) {
+        self.kill(pid);
+    }
+
+    fn signal(&self, pid: u32, _signal: &str) -> bool {
+        self.is_pid_running(pid)
+    }
+
+    fn signal_tree(&self, _pid: u32, _signal: &str) {}
+
+    fn process_tree(&self, pid: u32) -> Vec<u32> {
+        vec![pid]
+    }
+
+    fn process_stats(&self, pid: u32) -> Option<ProcessStats> {
+        self.is_pid_running(pid).then(ProcessStats::default)
+    }
+}
@@ -0,0 +1,73 @@
+#[cfg(test)]
+mod mock;
+#[cfg(unix)]
+mod unix;
+#[cfg(not(unix))]
+mod windows;
+
+use std::time::Duration;
+
+#[cfg(test)]
+pub use mock::MockBackend;
+
+/// Resource usage of a single process, as far as the platform reports it
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ProcessStats {
+    pub uptime: Option<Duration>,
+    /// Resident memory in bytes
+    pub memory: Option<u64>,
+}
+
+/// How groo finds, probes and stops local processes.
+///
+/// Commands go through [`backend()`] instead of calling `lsof`/`kill`/`netstat` directly,
+/// so platform differences live in one place and tests can swap in a `MockBackend`.
+pub trait ProcessBackend: Sync {
+    /// PIDs of processes listening on `port`
+    fn pids_on_port(&self, port: u16) -> Vec<u32>;
+
+    /// Whether anything is listening on `port`
+    fn is_port_in_use(&self, port: u16) -> bool {
+        !self.pids_on_port(port).is_empty()
+    }
+
+    fn is_pid_running(&self, pid: u32) -> bool;
+
+    /// Ask a process to exit, forcing it if it doesn't. Returns whether it was stopped.
+    fn kill(&self, pid: u32) -> bool;
+
+    /// Terminate a process and everything it spawned (`sh` → `npm` → dev server)
+    fn kill_tree(&self, pid: u32);
+
+    /// Send a named signal such as `HUP`. Returns whether it was delivered.
+    fn signal(&self, pid: u32, signal: &str) -> bool;
+
+    /// Send a named signal to a process and everything it spawned
+    fn signal_tree(&self, pid: u32, signal: &str);
+
+    /// A process and everything it spawned, descendants first
+    fn process_tree(&self, pid: u32) -> Vec<u32>;
+
+    /// Uptime and memory of a running process, `None` if it isn't running
+    fn process_stats(&self, pid: u32) -> Option<ProcessStats>;
+
+    /// Check if a service is running by port (preferred) or PID fallback
+    fn is_service_running(&self, port: Option<u16>, pid: u32) -> bool {
+        match port {
+            Some(port) => self.is_port_in_use(port),
+            None => self.is_pid_running(pid),
+        }
+    }
+}
+
+/// The backend for the current platform
+pub fn backend() -> &'static dyn ProcessBackend {
+    #[cfg(unix)]
+    {
+        &unix::UnixBackend
+    }
+    #[cfg(not(unix))]
+    {
+        &windows::WindowsBackend
+    }
+}
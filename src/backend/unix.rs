@@ -0,0 +1,147 @@
+use std::process::Command;
+use std::time::Duration;
+
+use super::{ProcessBackend, ProcessStats};
+
+/// `lsof`, `pgrep` and `kill`
+pub struct UnixBackend;
+
+impl ProcessBackend for UnixBackend {
+    fn pids_on_port(&self, port: u16) -> Vec<u32> {
+        let Ok(output) = tool("lsof")
+            .args(["-ti", &format!(":{}", port)])
+            .output()
+        else {
+            return vec![];
+        };
+        if !output.status.success() {
+            return vec![];
+        }
+        String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .filter_map(|line| line.trim().parse().ok())
+            .collect()
+    }
+
+    fn is_pid_running(&self, pid: u32) -> bool {
+        // A zombie still takes signals, but it has exited and is only waiting to be reaped
+        signal(pid, "-0") && !is_zombie(pid)
+    }
+
+    fn kill(&self, pid: u32) -> bool {
+        // Try SIGTERM first
+        signal(pid, "-15");
+
+        // Brief wait for graceful shutdown
+        std::thread::sleep(std::time::Duration::from_millis(100));
+
+        // Check if still running, if so use SIGKILL
+        if self.is_pid_running(pid) {
+            signal(pid, "-9")
+        } else {
+            true
+        }
+    }
+
+    fn kill_tree(&self, pid: u32) {
+        if let Ok(output) = tool("pgrep").args(["-P", &pid.to_string()]).output() {
+            for child in String::from_utf8_lossy(&output.stdout).lines() {
+                if let Ok(child) = child.trim().parse() {
+                    self.kill_tree(child);
+                }
+            }
+        }
+        signal(pid, "-15");
+    }
+
+    fn signal(&self, pid: u32, name: &str) -> bool {
+        signal(pid, &format!("-{}", name.trim_start_matches("SIG")))
+    }
+
+    fn signal_tree(&self, pid: u32, name: &str) {
+        for pid in self.process_tree(pid) {
+            self.signal(pid, name);
+        }
+    }
+
+    fn process_tree(&self, pid: u32) -> Vec<u32> {
+        let mut tree = Vec::new();
+        if let Ok(output) = tool("pgrep").args(["-P", &pid.to_string()]).output() {
+            for child in String::from_utf8_lossy(&output.stdout).lines() {
+                if let Ok(child) = child.trim().parse() {
+                    tree.extend(self.process_tree(child));
+                }
+            }
+        }
+        tree.push(pid);
+        tree
+    }
+
+    fn process_stats(&self, pid: u32) -> Option<ProcessStats> {
+        // `etime` rather than `etimes`, which macOS doesn't have
+        let output = tool("ps")
+            .args(["-o", "etime=,rss=", "-p", &pid.to_string()])
+            .output()
+            .ok()?;
+        if !output.status.success() {
+            return None;
+        }
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let mut fields = stdout.split_whitespace();
+        let uptime = fields.next().and_then(parse_etime);
+        let memory = fields.next().and_then(|kb| kb.parse::<u64>().ok()).map(|kb| kb * 1024);
+        Some(ProcessStats { uptime, memory })
+    }
+}
+
+/// Parse `ps` elapsed time: `[[dd-]hh:]mm:ss`
+fn parse_etime(etime: &str) -> Option<Duration> {
+    let (days, clock) = match etime.split_once('-') {
+        Some((days, clock)) => (days.parse::<u64>().ok()?, clock),
+        None => (0, etime),
+    };
+    let mut secs = 0;
+    for part in clock.split(':') {
+        secs = secs * 60 + part.parse::<u64>().ok()?;
+    }
+    Some(Duration::from_secs(days * 86400 + secs))
+}
+
+#[cfg(target_os = "linux")]
+fn is_zombie(pid: u32) -> bool {
+    // `<pid> (<comm>) <state> ...`; comm may contain spaces and parentheses
+    std::fs::read_to_string(format!("/proc/{}/stat", pid))
+        .ok()
+        .and_then(|stat| stat.rsplit_once(')').map(|(_, rest)| rest.trim_start().starts_with('Z')))
+        .unwrap_or(false)
+}
+
+#[cfg(not(target_os = "linux"))]
+fn is_zombie(pid: u32) -> bool {
+    tool("ps")
+        .args(["-o", "stat=", "-p", &pid.to_string()])
+        .output()
+        .map(|o| String::from_utf8_lossy(&o.stdout).trim_start().starts_with('Z'))
+        .unwrap_or(false)
+}
+
+/// A tool whose output is parsed, run in the C locale so it isn't translated or
+/// formatted for the user's language
+fn tool(program: &str) -> Command {
+    let mut cmd = Command::new(program);
+    cmd.env("LC_ALL", "C");
+    cmd
+}
+
+fn signal(pid: u32, signal: &str) -> bool {
+    let sent = tool("kill")
+        .args([signal, &pid.to_string()])
+        .output()
+        .map(|o| o.status.success())
+        .unwrap_or(false);
+    // Liveness probes run constantly and would drown out the signals that matter
+    if signal != "-0" {
+        crate::diag::record("signal", format!("kill {} {}: {}", signal, pid, if sent { "sent" } else { "failed" }));
+    }
+    sent
+}
@@ -0,0 +1,99 @@
+use std::net::TcpListener;
+use std::process::Command;
+
+use super::{ProcessBackend, ProcessStats};
+
+/// `netstat`, `tasklist` and `taskkill`
+pub struct WindowsBackend;
+
+impl ProcessBackend for WindowsBackend {
+    fn pids_on_port(&self, port: u16) -> Vec<u32> {
+        let Ok(output) = Command::new("netstat").args(["-ano", "-p", "TCP"]).output() else {
+            return vec![];
+        };
+        if !output.status.success() {
+            return vec![];
+        }
+        // The state column is translated ("LISTENING", "ABHÖREN", ...), so listening
+        // sockets are told apart by having no remote end instead
+        let needle = format!(":{}", port);
+        let mut pids: Vec<u32> = String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .filter_map(|line| {
+                let fields: Vec<&str> = line.split_whitespace().collect();
+                let [_, local, remote, .., pid] = fields.as_slice() else {
+                    return None;
+                };
+                if !remote.ends_with(":0") || !local.ends_with(&needle) {
+                    return None;
+                }
+                pid.parse().ok()
+            })
+            .collect();
+        // Listening on both IPv4 and IPv6 shows the process twice
+        pids.sort_unstable();
+        pids.dedup();
+        pids
+    }
+
+    fn is_port_in_use(&self, port: u16) -> bool {
+        TcpListener::bind(("127.0.0.1", port)).is_err()
+    }
+
+    fn is_pid_running(&self, pid: u32) -> bool {
+        // With no match tasklist prints a translated notice, so look for the PID column itself
+        Command::new("tasklist")
+            .args(["/NH", "/FO", "CSV", "/FI", &format!("PID eq {}", pid)])
+            .output()
+            .map(|o| String::from_utf8_lossy(&o.stdout).contains(&format!("\",\"{}\",", pid)))
+            .unwrap_or(false)
+    }
+
+    fn kill(&self, pid: u32) -> bool {
+        taskkill(&["/F", "/PID", &pid.to_string()])
+    }
+
+    fn kill_tree(&self, pid: u32) {
+        taskkill(&["/T", "/F", "/PID", &pid.to_string()]);
+    }
+
+    fn signal(&self, _pid: u32, _signal: &str) -> bool {
+        // Windows has no POSIX signals to forward
+        false
+    }
+
+    fn signal_tree(&self, _pid: u32, _signal: &str) {}
+
+    fn process_tree(&self, pid: u32) -> Vec<u32> {
+        // `taskkill /T` finds the descendants itself
+        vec![pid]
+    }
+
+    fn process_stats(&self, pid: u32) -> Option<ProcessStats> {
+        // tasklist has no start time, so only memory is reported
+        let output = Command::new("tasklist")
+            .args(["/NH", "/FO", "CSV", "/FI", &format!("PID eq {}", pid)])
+            .output()
+            .ok()?;
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let line = stdout.lines().find(|line| line.contains(&format!("\"{}\"", pid)))?;
+        // "node.exe","1234","Console","1","123,456 K"
+        let memory = line
+            .rsplit("\",\"")
+            .next()
+            .map(|mem| mem.chars().filter(char::is_ascii_digit).collect::<String>())
+            .and_then(|kb| kb.parse::<u64>().ok())
+            .map(|kb| kb * 1024);
+        Some(ProcessStats { uptime: None, memory })
+    }
+}
+
+fn taskkill(args: &[&str]) -> bool {
+    let sent = Command::new("taskkill")
+        .args(args)
+        .output()
+        .map(|o| o.status.success())
+        .unwrap_or(false);
+    crate::diag::record("signal", format!("taskkill {}: {}", args.join(" "), if sent { "ok" } else { "failed" }));
+    sent
+}
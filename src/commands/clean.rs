@@ -0,0 +1,133 @@
+use anyhow::Result;
+use console::style;
+use std::time::SystemTime;
+
+use crate::backend::backend;
+use crate::config::{self, get_logs_dir};
+use crate::format;
+use crate::output::{self, Level};
+use crate::state::{LogManifest, State};
+
+pub fn run(logs: bool, older_than: Option<String>, dry_run: bool) -> Result<()> {
+    if !logs {
+        anyhow::bail!("Nothing to clean. Pass --logs to remove log files.");
+    }
+
+    let max_age = older_than.as_deref().map(format::parse_duration).transpose()?;
+
+    let mut state = State::load().unwrap_or_default();
+    state.clean_stale_pids();
+
+    let logs_dir = get_logs_dir();
+    let mut manifest = LogManifest::load();
+
+    // Never delete logs that running services are still writing to
+    let in_use: Vec<std::path::PathBuf> = manifest
+        .entries
+        .iter()
+        .filter(|(key, entry)| {
+            entry.service_name(key).is_some_and(|service| {
+                state
+                    .projects
+                    .values()
+                    .any(|p| p.path == entry.project_path && p.services.contains_key(service))
+            })
+        })
+        .map(|(_, entry)| logs_dir.join(&entry.file))
+        .collect();
+
+    let now = SystemTime::now();
+    let mut removed = 0;
+    let mut freed = 0;
+
+    for entry in walkdir::WalkDir::new(&logs_dir)
+        .into_iter()
+        .filter_map(|e| e.ok())
+        .filter(|e| e.file_type().is_file())
+        .filter(|e| e.path().extension().is_some_and(|ext| ext == "log"))
+    {
+        let path = entry.path();
+        if in_use.iter().any(|p| p == path) {
+            continue;
+        }
+        let Ok(metadata) = entry.metadata() else {
+            continue;
+        };
+        if let Some(max_age) = max_age {
+            let age = metadata
+                .modified()
+                .ok()
+                .and_then(|modified| now.duration_since(modified).ok())
+                .unwrap_or_default();
+            if age < max_age {
+                continue;
+            }
+        }
+
+        let display = path.strip_prefix(&logs_dir).unwrap_or(path).display();
+        if dry_run {
+            output::line(format!("  {} Would remove {}", style("-").dim(), display));
+        } else {
+            if let Err(e) = std::fs::remove_file(path) {
+                output::item(Level::Error, format!("Failed to remove {}: {}", display, e));
+                continue;
+            }
+            manifest.entries.retain(|_, e| logs_dir.join(&e.file) != path);
+        }
+        removed += 1;
+        freed += metadata.len();
+    }
+
+    if !dry_run {
+        manifest.save()?;
+        remove_empty_dirs(&logs_dir);
+    }
+
+    let verb = if dry_run { "Would remove" } else { "Removed" };
+    output::success(format!("{} {} log file(s), {}", verb, removed, format::bytes(freed)));
+
+    let stacks = prune_ephemeral_stacks(dry_run);
+    if stacks > 0 {
+        output::success(format!("{} {} leftover ephemeral stack(s)", verb, stacks));
+    }
+
+    Ok(())
+}
+
+/// Remove what `groo dev --ephemeral` runs that didn't exit cleanly left behind in the
+/// namespaces they made up. Returns how many stacks there were.
+fn prune_ephemeral_stacks(dry_run: bool) -> usize {
+    let mut pruned = 0;
+    for name in config::namespaces() {
+        let Some(pid) = config::ephemeral_namespace_pid(&name) else {
+            continue;
+        };
+        if backend().is_pid_running(pid) {
+            continue;
+        }
+        if dry_run {
+            output::line(format!("  {} Would remove stack {}", style("-").dim(), name));
+        } else {
+            for dir in config::namespace_dirs(&name) {
+                if let Err(e) = std::fs::remove_dir_all(&dir)
+                    && e.kind() != std::io::ErrorKind::NotFound
+                {
+                    output::item(Level::Error, format!("Failed to remove {}: {}", dir.display(), e));
+                }
+            }
+        }
+        pruned += 1;
+    }
+    pruned
+}
+
+fn remove_empty_dirs(logs_dir: &std::path::Path) {
+    if let Ok(entries) = std::fs::read_dir(logs_dir) {
+        for entry in entries.filter_map(|e| e.ok()) {
+            if entry.path().is_dir() {
+                // Only succeeds when the directory is empty
+                let _ = std::fs::remove_dir(entry.path());
+            }
+        }
+    }
+}
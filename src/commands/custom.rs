@@ -0,0 +1,144 @@
+use anyhow::Result;
+use clap::CommandFactory;
+use console::style;
+
+use crate::backend::backend;
+use crate::config::{CustomCommand, ProjectConfig};
+use crate::discovery::{discover_services, find_git_root, FrameworkType, Scheme, Service};
+use crate::output;
+use crate::runner::{get_color_for_index, spawn_script, Overrides};
+
+/// Run a project command from `[commands]` in groo.toml, passing on any extra arguments
+pub async fn run(name: &str, args: &[String]) -> Result<()> {
+    let git_root = find_git_root()?;
+    let config = ProjectConfig::load(&git_root)?;
+
+    let Some(custom) = config.commands.get(name) else {
+        if config.commands.is_empty() {
+            anyhow::bail!("Unknown command '{}'. See `groo --help`", name);
+        }
+        list(&config);
+        anyhow::bail!("Unknown command '{}'", name);
+    };
+
+    let dir = git_root.join(custom.run.as_deref().unwrap_or(".".as_ref()));
+    if !dir.is_dir() {
+        anyhow::bail!("Command '{}' runs in {}, which doesn't exist", name, dir.display());
+    }
+    let args: String = args.iter().map(|a| format!(" {}", shell_quote(a))).collect();
+
+    // A service living there lends its groo.toml env; output is prefixed with the command name
+    let services = discover_services(&git_root)?;
+    let mut service = services
+        .into_iter()
+        .find(|s| s.path == dir)
+        .unwrap_or_else(|| Service {
+            name: String::new(),
+            path: dir.clone(),
+            framework: FrameworkType::Unknown,
+            port: None,
+            scheme: Scheme::default(),
+            base_path: None,
+            tags: Vec::new(),
+            command: None,
+            env: Default::default(),
+            framework_env: Default::default(),
+            package_name: None,
+            priority: Default::default(),
+        });
+    service.name = name.to_string();
+    // `spawn_script` runs a service's `command` in place of its `dev` script
+    let script = match (&custom.script, &custom.command) {
+        (Some(script), None) => {
+            service.command = None;
+            if args.is_empty() { script.clone() } else { format!("{} --{}", script, args) }
+        }
+        (None, Some(command)) => {
+            service.command = Some(format!("{}{}", command, args));
+            "dev".to_string()
+        }
+        _ => anyhow::bail!("Command '{}' needs either `script` or `command` in groo.toml", name),
+    };
+
+    let color = get_color_for_index(0);
+    let mut child = spawn_script(&service, &script, &color, None, &Overrides::default()).await?;
+    let status = tokio::select! {
+        status = child.wait() => status?,
+        _ = tokio::signal::ctrl_c() => {
+            // The shell's children too, which killing the shell alone would leave running
+            if let Some(pid) = child.id() {
+                backend().kill_tree(pid);
+            }
+            let _ = child.wait().await;
+            anyhow::bail!("{} cancelled", name);
+        }
+    };
+    if !status.success() {
+        let code = status.code().map(|c| format!(" with exit code {}", c)).unwrap_or_default();
+        anyhow::bail!("{} failed{}", name, code);
+    }
+    Ok(())
+}
+
+/// Print the project's commands with their descriptions
+fn list(config: &ProjectConfig) {
+    output::blank();
+    output::line(style("Project commands:").bold());
+    for line in command_lines(config) {
+        output::line(line);
+    }
+}
+
+/// `groo --help` section listing the current project's commands, if it has any
+pub fn help_text() -> Option<String> {
+    let config = ProjectConfig::load(&find_git_root().ok()?).ok()?;
+    let lines = command_lines(&config);
+    (!lines.is_empty()).then(|| format!("Project commands:\n{}", lines.join("\n")))
+}
+
+fn command_lines(config: &ProjectConfig) -> Vec<String> {
+    let builtins = builtin_names();
+    let mut commands: Vec<(&String, &CustomCommand)> = config.commands.iter().collect();
+    commands.sort_by_key(|(name, _)| *name);
+    let max_name_len = commands.iter().map(|(name, _)| name.len()).max().unwrap_or(0);
+
+    commands
+        .into_iter()
+        .map(|(name, command)| {
+            let description = command
+                .description
+                .clone()
+                .or_else(|| command.script.as_ref().map(|s| format!("npm run {}", s)))
+                .or_else(|| command.command.clone())
+                .unwrap_or_default();
+            let description = if builtins.iter().any(|b| b == name) {
+                format!("{} (unreachable: `groo {}` is a built-in command; rename it)", description, name)
+            } else {
+                description
+            };
+            // Pad before styling so ANSI codes don't throw off the alignment
+            format!(
+                "  {}  {}",
+                style(format!("{:<width$}", name, width = max_name_len)).cyan(),
+                style(description).dim()
+            )
+        })
+        .collect()
+}
+
+/// Names and aliases of groo's own subcommands, which win over project commands
+fn builtin_names() -> Vec<String> {
+    crate::Cli::command()
+        .get_subcommands()
+        .flat_map(|c| std::iter::once(c.get_name()).chain(c.get_all_aliases()))
+        .map(String::from)
+        .collect()
+}
+
+/// Quote an argument for `sh`
+fn shell_quote(arg: &str) -> String {
+    if !arg.is_empty() && arg.chars().all(|c| c.is_ascii_alphanumeric() || "-_./=:@,+".contains(c)) {
+        return arg.to_string();
+    }
+    format!("'{}'", arg.replace('\'', r"'\''"))
+}
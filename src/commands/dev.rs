@@ -1,218 +1,300 @@
 use anyhow::Result;
-use console::{style, Style, Term};
-use dialoguer::{theme::ColorfulTheme, Confirm, MultiSelect};
+use console::{style, Term};
+use dialoguer::Confirm;
+use std::collections::BTreeMap;
+use std::path::Path;
 use tokio::sync::broadcast;
 
-use crate::commands::stop::{get_pids_by_port, kill_process};
-use crate::config::get_service_log_file;
-use crate::discovery::{discover_services, find_git_root, get_project_name, Service};
-use crate::runner::{get_color_for_index, spawn_service, wait_for_processes, ProcessHandle};
-use crate::state::{is_port_in_use, State};
-
-fn create_theme() -> ColorfulTheme {
-    ColorfulTheme {
-        defaults_style: Style::new().dim(),
-        prompt_style: Style::new().bold(),
-        prompt_prefix: style("?".to_string()).green().bold(),
-        success_prefix: style("✓".to_string()).green().bold(),
-        error_prefix: style("✗".to_string()).red().bold(),
-        checked_item_prefix: style("  ◉".to_string()).green(),
-        unchecked_item_prefix: style("  ○".to_string()).dim(),
-        active_item_style: Style::new().cyan().bold(),
-        inactive_item_style: Style::new().dim(),
-        active_item_prefix: style("❯".to_string()).cyan().bold(),
-        ..ColorfulTheme::default()
+use crate::backend::backend;
+use crate::commands::init;
+use crate::commands::reconcile::reconcile;
+use crate::config::{self, Preselect, ProjectConfig};
+use crate::discovery::{
+    discover_services_with, find_git_root, free_port, get_project_name, DiscoveryOptions, Scope, Service,
+};
+use crate::output::{self, Level};
+use crate::runner::{
+    get_color_for_index, set_keep_alive, set_timestamps, shutdown_requested, spawn_service, wait_for_processes,
+    Deferred, Overrides, ProcessHandle,
+};
+use crate::select::{theme, Defaults, Selector};
+use crate::state::{service_log_file, ServiceState, Session, State};
+
+pub async fn run(
+    deep: bool,
+    ephemeral: bool,
+    timestamps: bool,
+    env: Vec<String>,
+    service_queries: Vec<String>,
+    scope: Vec<String>,
+    keep_alive: bool,
+) -> Result<()> {
+    // Closing the console takes groo down with it on Windows, so nothing would keep the
+    // services' logs going
+    #[cfg(not(unix))]
+    if keep_alive {
+        anyhow::bail!("--keep-alive isn't supported on Windows");
+    }
+    set_timestamps(timestamps);
+    set_keep_alive(keep_alive);
+    let env = parse_env(&env)?;
+    if ephemeral {
+        // Keep this instance's state, logs and sessions apart from any other stack
+        let namespace = config::namespace().unwrap_or_else(|| config::ephemeral_namespace(std::process::id()));
+        config::set_namespace(&namespace);
+        output::step(format!(
+            "Ephemeral stack {}. Inspect it with {}",
+            style(&namespace).bold(),
+            style(format!("GROO_NAMESPACE={} groo status", namespace)).cyan()
+        ));
+        output::blank();
     }
-}
 
-pub async fn run() -> Result<()> {
     let git_root = find_git_root()?;
     let project_name = get_project_name(&git_root);
-    let services = discover_services(&git_root)?;
+    // Services picked on the command line say the user knows what they're doing
+    if !ephemeral && service_queries.is_empty() && scope.is_empty() {
+        init::offer(&git_root)?;
+    }
+    let config = ProjectConfig::load(&git_root)?;
+    let options = DiscoveryOptions {
+        deep,
+        scope: Scope::new(&scope),
+    };
+    let services = discover_services_with(&git_root, &options)?;
 
     if services.is_empty() {
-        println!("{}", style("No services with dev scripts found.").yellow());
+        if scope.is_empty() {
+            output::warn("No services with dev scripts found.");
+        } else {
+            output::warn(format!("No services with dev scripts found in {}.", scope.join(", ")));
+        }
         return Ok(());
     }
 
-    // Load state
+    // Load state; services running outside groo are offered for stopping below instead
     let mut state = State::load().unwrap_or_default();
+    reconcile(&mut state, &project_name, &git_root, &services, false)?;
     state.clean_stale_pids();
     state.save()?;
 
-    // Check which services are already running (port-based detection)
-    let mut is_running: Vec<bool> = services
+    // Collect running services (port-based detection)
+    let running_services: Vec<&Service> = services
         .iter()
-        .map(|s| s.port.map(is_port_in_use).unwrap_or(false))
+        .filter(|s| s.port.is_some_and(|p| backend().is_port_in_use(p)))
         .collect();
 
-    // Collect running services
-    let running_services: Vec<(&Service, usize)> = services
-        .iter()
-        .enumerate()
-        .filter(|(i, _)| is_running[*i])
-        .map(|(i, s)| (s, i))
-        .collect();
-
-    // Prompt to stop if any are running
-    if !running_services.is_empty() {
-        println!("{}", style("Running services:").yellow().bold());
-        for (service, _) in &running_services {
+    // Prompt to stop if any are running; ephemeral stacks get their own ports instead
+    if !ephemeral && !running_services.is_empty() && Term::stderr().is_term() {
+        output::heading(style("Running services:").yellow());
+        for service in &running_services {
             let port_str = service
                 .port
                 .map(|p| format!(":{}", p))
                 .unwrap_or_default();
-            println!(
+            output::line(format!(
                 "  {} {}",
                 style(&service.name).cyan(),
                 style(port_str).dim()
-            );
+            ));
         }
-        println!();
+        output::blank();
 
-        let stop_them = Confirm::new()
+        let stop_them = Confirm::with_theme(&theme())
             .with_prompt("Stop running services?")
             .default(true)
             .interact()?;
 
         if stop_them {
-            for (service, _) in &running_services {
+            for service in &running_services {
                 if let Some(port) = service.port {
-                    for pid in get_pids_by_port(port) {
-                        kill_process(pid);
+                    for pid in backend().pids_on_port(port) {
+                        backend().kill(pid);
                     }
-                    println!("  {} Stopped {}", style("✓").green(), service.name);
+                    output::item(Level::Success, format!("Stopped {}", service.name));
                 }
             }
             // Brief wait for ports to be released
             std::thread::sleep(std::time::Duration::from_millis(300));
-
-            // Refresh running status
-            is_running = services
-                .iter()
-                .map(|s| s.port.map(is_port_in_use).unwrap_or(false))
-                .collect();
-            println!();
+            output::blank();
         }
     }
 
-    // Find max name length for alignment
-    let max_name_len = services.iter().map(|s| s.name.len()).max().unwrap_or(0);
-
-    // Display services for selection
-    let items: Vec<String> = services
-        .iter()
-        .zip(is_running.iter())
-        .map(|(s, &running)| {
-            let port_str = s.port
-                .map(|p| format!("{}", p))
-                .unwrap_or_else(|| "-".to_string());
-            if running {
-                format!(
-                    "{:<width$}  {}  {}",
-                    style(&s.name).dim(),
-                    style(port_str).dim(),
-                    style("(running)").dim().italic(),
-                    width = max_name_len
-                )
-            } else {
-                format!(
-                    "{:<width$}  {}",
-                    s.name,
-                    style(port_str).dim(),
-                    width = max_name_len
-                )
-            }
-        })
-        .collect();
-
-    // Auto-select only services with detected ports that are not running
-    let defaults: Vec<bool> = services
-        .iter()
-        .zip(is_running.iter())
-        .map(|(s, &running)| s.port.is_some() && !running)
-        .collect();
-
-    let theme = create_theme();
-    let selections = MultiSelect::with_theme(&theme)
-        .with_prompt("Select services to run")
-        .items(&items)
-        .defaults(&defaults)
-        .interact_on(&Term::stderr())?;
+    let selected_services: Vec<&Service> = if ephemeral && service_queries.is_empty() {
+        // No prompt so CI jobs can run unattended
+        let with_ports: Vec<&Service> = services.iter().filter(|s| s.port.is_some()).collect();
+        if with_ports.is_empty() {
+            output::warn("No services with detected ports to run.");
+        }
+        with_ports
+    } else {
+        Selector::new(&services, "Select services to run")
+            .defaults(preselected(&config, &project_name, &services))
+            .queries(&service_queries)
+            .select()?
+    };
 
-    if selections.is_empty() {
-        println!("{}", style("No services selected.").yellow());
+    if selected_services.is_empty() {
         return Ok(());
     }
 
-    let selected_services: Vec<&Service> = selections.iter().map(|&i| &services[i]).collect();
+    let selected = selected_services
+        .into_iter()
+        .map(|service| {
+            let overrides = Overrides {
+                port: if ephemeral { free_port() } else { None },
+                env: env.clone(),
+            };
+            (service, overrides)
+        })
+        .collect();
+    launch(&git_root, &project_name, &config, state, selected, Cleanup::Project).await
+}
+
+/// What [`launch`] forgets in state once its services are done
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Cleanup {
+    /// The whole project: this run started everything it tracks
+    Project,
+    /// Only the services this run started, as other runs may still track the rest
+    Services,
+}
 
-    println!(
-        "\n{} Starting {} service(s)...\n",
-        style("→").green().bold(),
-        selected_services.len()
-    );
+/// Start services with their overrides, stream their output and track them in state and
+/// the session until Ctrl+C or they all exit. Shared by `groo dev`, `groo restart` and
+/// `groo plan run`.
+pub async fn launch(
+    git_root: &Path,
+    project_name: &str,
+    config: &ProjectConfig,
+    mut state: State,
+    selected: Vec<(&Service, Overrides)>,
+    cleanup: Cleanup,
+) -> Result<()> {
+    let names: Vec<String> = selected.iter().map(|(service, _)| service.name.clone()).collect();
+    output::blank();
+    output::step(format!("Starting {} service(s)...", selected.len()));
+    output::blank();
 
     // Set up shutdown signal
     let (shutdown_tx, _) = broadcast::channel::<()>(1);
 
-    // Set up Ctrl+C handler
+    // Set up Ctrl+C and terminal hangup handler
     let shutdown_tx_clone = shutdown_tx.clone();
     tokio::spawn(async move {
-        tokio::signal::ctrl_c().await.ok();
-        println!("\n{} Shutting down...", style("→").yellow().bold());
+        shutdown_requested().await;
         let _ = shutdown_tx_clone.send(());
     });
 
-    // Spawn all selected services
+    // Record session metadata for `groo report`
+    let mut session = Session::new(project_name, git_root.to_path_buf());
+
+    // Spawn the selected services, holding back those whose external dependencies aren't up yet
     let mut handles: Vec<ProcessHandle> = Vec::new();
-    for (idx, service) in selected_services.iter().enumerate() {
+    let mut deferred: Vec<Deferred> = Vec::new();
+    for (idx, (service, overrides)) in selected.into_iter().enumerate() {
         let color = get_color_for_index(idx);
-        let log_file = get_service_log_file(&service.path);
-
-        match spawn_service(
-            &service.name,
-            &service.path,
-            &service.dev_command,
-            color.clone(),
-            log_file,
-        )
-        .await
+        let log_file = service_log_file(project_name, git_root, service);
+        let dependencies = config.external_dependencies([service.name.as_str()]);
+        if !dependencies.is_empty() {
+            deferred.push(Deferred {
+                service: service.clone(),
+                color,
+                log_file,
+                overrides,
+                dependencies,
+            });
+            continue;
+        }
+        let env = overrides.env.clone();
+
+        match spawn_service(service, color.clone(), log_file, overrides).await
         {
             Ok(handle) => {
+                session.add_service(&service.name, &service.path, handle.port, handle.pid(), &env);
                 if let Some(pid) = handle.pid() {
                     state.add_service(
-                        &project_name,
-                        git_root.clone(),
+                        project_name,
+                        git_root.to_path_buf(),
                         &service.name,
-                        pid,
-                        service.port,
+                        ServiceState {
+                            pid,
+                            port: handle.port,
+                            scheme: service.scheme,
+                            base_path: service.base_path.clone(),
+                        },
                     );
                 }
                 handles.push(handle);
             }
             Err(e) => {
-                eprintln!(
-                    "{} Failed to start {}: {}",
-                    style("✗").red().bold(),
-                    service.name,
-                    e
-                );
+                output::error(format!("Failed to start {}: {}", service.name, e));
             }
         }
     }
 
     // Save state
+    state.touch_recent(project_name, git_root.to_path_buf());
     state.save()?;
+    let _ = session.start();
 
     // Wait for all processes or shutdown
     let shutdown_rx = shutdown_tx.subscribe();
-    wait_for_processes(handles, shutdown_rx).await;
+    let forced = wait_for_processes(handles, deferred, shutdown_rx, &mut session, config).await;
 
     // Clean up state on exit
     let mut state = State::load().unwrap_or_default();
-    state.remove_project(&project_name);
+    match cleanup {
+        Cleanup::Project => state.remove_project(project_name),
+        Cleanup::Services => {
+            for name in &names {
+                state.remove_service(project_name, name);
+            }
+        }
+    }
     state.save()?;
 
+    // A namespace made up for this run has nothing left worth keeping
+    let own = config::ephemeral_namespace(std::process::id());
+    if config::namespace().as_ref() == Some(&own) {
+        for dir in config::namespace_dirs(&own) {
+            let _ = std::fs::remove_dir_all(dir);
+        }
+    }
+
+    if forced {
+        std::process::exit(130);
+    }
     Ok(())
 }
+
+/// Parse `--env KEY=VALUE` flags
+fn parse_env(vars: &[String]) -> Result<BTreeMap<String, String>> {
+    vars.iter()
+        .map(|var| match var.split_once('=') {
+            Some((key, value)) if !key.is_empty() => Ok((key.to_string(), value.to_string())),
+            _ => anyhow::bail!("Invalid --env '{}', expected KEY=VALUE", var),
+        })
+        .collect()
+}
+
+/// Which services the selector checks up front, per `[dev] preselect` in groo.toml
+fn preselected(config: &ProjectConfig, project_name: &str, services: &[Service]) -> Defaults {
+    match config.dev.preselect {
+        Preselect::None => Defaults::None,
+        Preselect::NotRunning => Defaults::NotRunning,
+        Preselect::AutoStart => Defaults::Named(config.dev.auto_start.clone()),
+        Preselect::LastUsed => Defaults::Named(
+            Session::latest_for_project(project_name)
+                .map(|session| session.services.into_iter().map(|run| run.name).collect())
+                .unwrap_or_default(),
+        ),
+        Preselect::Tags => Defaults::Named(
+            services
+                .iter()
+                .filter(|s| s.tags.iter().any(|t| config.dev.preselect_tags.contains(t)))
+                .map(|s| s.name.clone())
+                .collect(),
+        ),
+    }
+}
@@ -0,0 +1,75 @@
+use anyhow::Result;
+use console::style;
+
+use crate::discovery::{discover_candidates, find_git_root, DiscoveryOptions, Scope};
+use crate::output::{self, Level};
+
+/// Run only the discovery phase and show every package.json that was considered
+pub fn run(json: bool, deep: bool, scope: Vec<String>) -> Result<()> {
+    let git_root = find_git_root()?;
+    let options = DiscoveryOptions {
+        deep,
+        scope: Scope::new(&scope),
+    };
+    let candidates = discover_candidates(&git_root, &options)?;
+
+    if json {
+        output::document(&format!("{}\n", serde_json::to_string_pretty(&candidates)?));
+        return Ok(());
+    }
+
+    if output::is_json() {
+        for candidate in &candidates {
+            output::data(candidate);
+        }
+        return Ok(());
+    }
+
+    if candidates.is_empty() {
+        output::warn("No package.json files found.");
+        return Ok(());
+    }
+
+    let max_name_len = candidates.iter().map(|c| c.name.len()).max().unwrap_or(0);
+
+    for candidate in &candidates {
+        if candidate.accepted {
+            let port_str = candidate
+                .port
+                .map(|p| p.to_string())
+                .unwrap_or_else(|| "-".to_string());
+            let framework = candidate
+                .framework
+                .as_ref()
+                .map(|f| f.to_string())
+                .unwrap_or_default();
+            let source = if candidate.deep_port.is_some() { " (deep)" } else { "" };
+            output::item(
+                Level::Success,
+                format!(
+                    "{:<width$}  {:<6} {}{}",
+                    candidate.name,
+                    port_str,
+                    style(framework).dim(),
+                    style(source).dim(),
+                    width = max_name_len
+                ),
+            );
+        } else {
+            let reason = candidate
+                .skip_reason
+                .as_ref()
+                .map(|r| r.to_string())
+                .unwrap_or_default();
+            output::line(format!(
+                "  {} {:<width$}  {}",
+                style("-").dim(),
+                style(&candidate.name).dim(),
+                style(format!("skipped: {}", reason)).dim(),
+                width = max_name_len
+            ));
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,190 @@
+use anyhow::Result;
+use console::style;
+use std::process::Command;
+
+use crate::config::{get_config_dir, get_global_config_file, GlobalConfig};
+#[cfg(unix)]
+use crate::config::get_logs_dir;
+use crate::diag;
+use crate::format;
+use crate::output::{self, Level};
+use crate::state::{logs_disk_usage, State};
+
+/// Lines of the diagnostic log `--show-log` prints
+const SHOWN_LOG_LINES: usize = 200;
+
+/// Diagnose the local groo installation: required tools, state and disk usage
+pub fn run(show_log: bool) -> Result<()> {
+    if show_log {
+        return print_log();
+    }
+    let mut problems = 0;
+
+    output::heading("groo doctor");
+    output::blank();
+    output::line(format!("  {} {}", style("Config dir").dim(), get_config_dir().display()));
+    output::line(format!("  {} {}", style("Diagnostic log").dim(), diag::log_file().display()));
+    output::blank();
+
+    for (tool, args) in [("git", "--version"), ("node", "--version"), ("npm", "--version")] {
+        if !report(tool_available(tool, args), &format!("{} available", tool), "not found in PATH") {
+            problems += 1;
+        }
+    }
+    #[cfg(unix)]
+    if !report(
+        tool_available("lsof", "-v"),
+        "lsof available",
+        "needed to detect running services",
+    ) {
+        problems += 1;
+    }
+
+    let config = match GlobalConfig::load() {
+        Ok(config) => {
+            report(true, "config readable", "");
+            config
+        }
+        Err(e) => {
+            problems += 1;
+            report(false, &get_global_config_file().display().to_string(), &e.to_string());
+            GlobalConfig::default()
+        }
+    };
+
+    if let Err(e) = State::load() {
+        problems += 1;
+        report(false, "state readable", &e.to_string());
+    } else {
+        report(true, "state readable", "");
+    }
+
+    #[cfg(unix)]
+    if !check_permissions() {
+        problems += 1;
+    }
+
+    let usage = logs_disk_usage();
+    let threshold = config.logs.warn_size_mb.saturating_mul(1024 * 1024);
+    if usage > threshold {
+        problems += 1;
+        output::item(
+            Level::Warning,
+            format!(
+                "logs use {} (over {} MB). Run {} to trim them.",
+                format::bytes(usage),
+                config.logs.warn_size_mb,
+                style("groo clean --logs --older-than 7d").cyan()
+            ),
+        );
+    } else {
+        output::item(Level::Success, format!("logs use {}", format::bytes(usage)));
+    }
+
+    output::blank();
+    if problems == 0 {
+        output::success("No problems found.");
+    } else {
+        output::warn(format!("{} problem(s) found.", problems));
+    }
+
+    Ok(())
+}
+
+/// The end of groo's diagnostic log, across its rotated files
+fn print_log() -> Result<()> {
+    let mut lines: Vec<String> = Vec::new();
+    for file in diag::log_files() {
+        if let Ok(content) = std::fs::read_to_string(&file) {
+            lines.extend(content.lines().map(str::to_string));
+        }
+    }
+    if lines.is_empty() {
+        output::line(format!("Nothing logged yet at {}", diag::log_file().display()));
+        return Ok(());
+    }
+    output::line(style(diag::log_file().display()).dim());
+    for line in &lines[lines.len().saturating_sub(SHOWN_LOG_LINES)..] {
+        output::line(line);
+    }
+    Ok(())
+}
+
+/// Flag groo files other users can access, or that belong to someone else (say, left behind
+/// by `sudo groo`) and so can't be updated
+#[cfg(unix)]
+fn check_permissions() -> bool {
+    use std::os::unix::fs::MetadataExt;
+
+    // SAFETY: geteuid can't fail
+    let uid = unsafe { libc::geteuid() };
+    let mut dirs = vec![get_config_dir()];
+    if GlobalConfig::cached().logs.local {
+        dirs.push(get_logs_dir());
+    }
+    let mut exposed = Vec::new();
+    let mut foreign = Vec::new();
+    for dir in &dirs {
+        for entry in walkdir::WalkDir::new(dir).into_iter().filter_map(|e| e.ok()) {
+            let Ok(metadata) = entry.metadata() else {
+                continue;
+            };
+            if metadata.uid() != uid {
+                foreign.push(entry.into_path());
+            } else if metadata.mode() & 0o077 != 0 {
+                exposed.push(entry.into_path());
+            }
+        }
+    }
+
+    let mut ok = true;
+    if !exposed.is_empty() {
+        ok = false;
+        output::item(
+            Level::Warning,
+            format!(
+                "{} file(s) accessible to other users, e.g. {}. Run {} to fix.",
+                exposed.len(),
+                exposed[0].display(),
+                style(format!(
+                    "chmod -R go-rwx {}",
+                    dirs.iter().map(|d| d.display().to_string()).collect::<Vec<_>>().join(" ")
+                ))
+                .cyan()
+            ),
+        );
+    }
+    if !foreign.is_empty() {
+        ok = false;
+        output::item(
+            Level::Warning,
+            format!(
+                "{} file(s) owned by another user, e.g. {}. groo can't update them; was it run with sudo?",
+                foreign.len(),
+                foreign[0].display()
+            ),
+        );
+    }
+    if ok {
+        output::item(Level::Success, "files private to you");
+    }
+    ok
+}
+
+/// Print a check line and return whether it passed
+fn report(ok: bool, label: &str, detail: &str) -> bool {
+    if ok {
+        output::item(Level::Success, label);
+    } else {
+        output::item(Level::Error, format!("{} {}", label, style(format!("({})", detail)).dim()));
+    }
+    ok
+}
+
+fn tool_available(tool: &str, arg: &str) -> bool {
+    Command::new(tool)
+        .arg(arg)
+        .output()
+        .map(|o| o.status.success())
+        .unwrap_or(false)
+}
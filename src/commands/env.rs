@@ -0,0 +1,194 @@
+use anyhow::Result;
+use console::style;
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::discovery::{discover_services, find_git_root, find_service, get_project_name, load_env_files};
+use crate::output;
+use crate::state::{env_fingerprint, ServiceRun, Session};
+
+/// One differing variable in `groo env diff --output json`. Values are only included when
+/// known: sessions keep fingerprints of values, not the values themselves.
+#[derive(Serialize)]
+struct EnvDifference<'a> {
+    key: &'a str,
+    change: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    left: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    right: Option<&'a str>,
+}
+
+/// A variable's fingerprint for comparing, and its value when it was just read from an
+/// env file rather than recorded by a session
+struct EnvValue {
+    fingerprint: String,
+    value: Option<String>,
+}
+
+type Env = BTreeMap<String, EnvValue>;
+
+/// Show which env vars differ between two services, or for one service between sessions.
+///
+/// A service's env is what its env files define plus groo's overrides (`--env`, plans).
+/// The shell's environment is the same for every service and left out.
+pub fn diff(query: &str, other: Option<&str>, session: Option<&str>) -> Result<()> {
+    let git_root = find_git_root()?;
+    let project_name = get_project_name(&git_root);
+    let services = discover_services(&git_root)?;
+    let service = find_service(&services, query)?;
+
+    let ((left_label, left), (right_label, right)) = match (other, session) {
+        (Some(other), _) => {
+            let other = find_service(&services, other)?;
+            let latest = Session::latest_for_project(&project_name);
+            let current = |name: &str, dir| {
+                let mut env: Env = load_env_files(dir)
+                    .into_iter()
+                    .map(|(key, value)| {
+                        let fingerprint = env_fingerprint(&value);
+                        (key, EnvValue { fingerprint, value: Some(value) })
+                    })
+                    .collect();
+                if let Some(run) = latest.as_ref().and_then(|s| last_run(s, name)) {
+                    env.extend(recorded(&run.env_hashes));
+                }
+                env
+            };
+            (
+                (service.name.clone(), current(&service.name, &service.path)),
+                (other.name.clone(), current(&other.name, &other.path)),
+            )
+        }
+        (None, Some(which)) => {
+            let back = sessions_back(which)?;
+            // Sessions this service ran in, newest first
+            let runs: Vec<ServiceRun> = Session::load_all()
+                .iter()
+                .rev()
+                .filter(|s| s.project == project_name)
+                .filter_map(|s| last_run(s, &service.name).cloned())
+                .collect();
+            let (Some(latest), Some(earlier)) = (runs.first(), runs.get(back)) else {
+                anyhow::bail!(
+                    "{} ran in {} recorded session(s); nothing to compare {} back",
+                    service.name,
+                    runs.len(),
+                    which
+                );
+            };
+            let earlier_label = if back == 1 {
+                "previous session".to_string()
+            } else {
+                format!("{} sessions back", back)
+            };
+            (
+                (earlier_label, recorded_env(earlier)),
+                ("last session".to_string(), recorded_env(latest)),
+            )
+        }
+        (None, None) => anyhow::bail!("Name a second service, or compare sessions with --session previous"),
+    };
+
+    let keys: BTreeSet<&String> = left.keys().chain(right.keys()).collect();
+    let differences: Vec<EnvDifference> = keys
+        .iter()
+        .filter_map(|key| {
+            let (l, r) = (left.get(*key), right.get(*key));
+            let change = match (l, r) {
+                (Some(l), Some(r)) if l.fingerprint == r.fingerprint => return None,
+                (Some(_), Some(_)) => "changed",
+                (Some(_), None) => "removed",
+                _ => "added",
+            };
+            Some(EnvDifference {
+                key,
+                change,
+                left: l.and_then(|v| v.value.as_deref()),
+                right: r.and_then(|v| v.value.as_deref()),
+            })
+        })
+        .collect();
+
+    if output::is_json() {
+        for difference in &differences {
+            output::data(difference);
+        }
+        return Ok(());
+    }
+
+    if differences.is_empty() {
+        output::success(format!(
+            "{} and {} agree on all {} variable(s)",
+            style(&left_label).cyan(),
+            style(&right_label).cyan(),
+            keys.len()
+        ));
+        return Ok(());
+    }
+
+    output::line(style(format!("--- {}", left_label)).red());
+    output::line(style(format!("+++ {}", right_label)).green());
+    output::blank();
+    for difference in &differences {
+        let key = difference.key;
+        match difference.change {
+            "changed" if difference.left.is_none() && difference.right.is_none() => {
+                output::line(format!("{} {} {}", style("~").yellow(), style(key).bold(), style("(changed)").dim()));
+            }
+            "changed" => {
+                output::line(format!("{} {}", style("~").yellow(), style(key).bold()));
+                output::line(style(format!("    - {}", shown(difference.left))).red());
+                output::line(style(format!("    + {}", shown(difference.right))).green());
+            }
+            "removed" => output::line(style(format!("- {}{}", key, assigned(difference.left))).red()),
+            _ => output::line(style(format!("+ {}{}", key, assigned(difference.right))).green()),
+        }
+    }
+    output::blank();
+    output::line(style(format!(
+        "{} variable(s) differ, {} identical",
+        differences.len(),
+        keys.len() - differences.len()
+    )).dim());
+
+    Ok(())
+}
+
+/// A value for display; sessions only record fingerprints
+fn shown(value: Option<&str>) -> &str {
+    value.unwrap_or("(value not recorded)")
+}
+
+fn assigned(value: Option<&str>) -> String {
+    value.map(|v| format!("={}", v)).unwrap_or_default()
+}
+
+/// `previous` or a number of sessions back, e.g. `2`
+fn sessions_back(which: &str) -> Result<usize> {
+    match which {
+        "previous" => Ok(1),
+        n => match n.parse::<usize>() {
+            Ok(back) if back > 0 => Ok(back),
+            _ => anyhow::bail!("Invalid --session '{}': use 'previous' or a number of sessions back", which),
+        },
+    }
+}
+
+/// A service's latest run in a session
+fn last_run<'a>(session: &'a Session, service_name: &str) -> Option<&'a ServiceRun> {
+    session.services.iter().rev().find(|r| r.name == service_name)
+}
+
+fn recorded_env(run: &ServiceRun) -> Env {
+    let mut env = recorded(&run.env_file_hashes);
+    env.extend(recorded(&run.env_hashes));
+    env
+}
+
+fn recorded(hashes: &BTreeMap<String, String>) -> Env {
+    hashes
+        .iter()
+        .map(|(key, fingerprint)| (key.clone(), EnvValue { fingerprint: fingerprint.clone(), value: None }))
+        .collect()
+}
@@ -0,0 +1,158 @@
+use anyhow::{Context, Result};
+use clap::ValueEnum;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use crate::config::{ExternalDependency, ProjectConfig};
+use crate::discovery::{discover_services, find_git_root, Service, ENV_FILES};
+use crate::output;
+
+/// Formats `groo export` writes
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+pub enum ExportFormat {
+    /// A docker-compose file
+    Compose,
+}
+
+#[derive(Serialize)]
+struct ComposeFile {
+    services: BTreeMap<String, ComposeService>,
+}
+
+#[derive(Serialize)]
+struct ComposeService {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    build: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    image: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    working_dir: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    volumes: Vec<String>,
+    command: String,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    ports: Vec<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    env_file: Vec<String>,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    environment: BTreeMap<String, String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    depends_on: Vec<String>,
+}
+
+pub fn run(format: ExportFormat, file: Option<PathBuf>) -> Result<()> {
+    match format {
+        ExportFormat::Compose => export_compose(file),
+    }
+}
+
+/// Write the discovered services as a compose file, to stdout or `file`.
+///
+/// Packages with a Dockerfile are built from it; the rest run their dev script in a Node
+/// image with the repository mounted. This approximates the stack; it isn't a production setup.
+fn export_compose(file: Option<PathBuf>) -> Result<()> {
+    let git_root = find_git_root()?;
+    let config = ProjectConfig::load(&git_root)?;
+    let services = discover_services(&git_root)?;
+
+    let mut compose = ComposeFile {
+        services: BTreeMap::new(),
+    };
+    for service in &services {
+        // Imported from a compose file in the first place; its definition lives there
+        if service.command.as_deref().is_some_and(|c| c.starts_with("docker compose")) {
+            output::warn(format!("Skipped {}: it already runs through docker compose", service.name));
+            continue;
+        }
+        compose
+            .services
+            .insert(compose_name(&service.name), to_compose(service, &services, &config, &git_root));
+    }
+
+    let yaml = serde_yaml_ng::to_string(&compose)?;
+    match file {
+        Some(file) => {
+            std::fs::write(&file, yaml).with_context(|| format!("Failed to write {}", file.display()))?;
+            output::success(format!("Wrote {} service(s) to {}", compose.services.len(), file.display()));
+        }
+        None => output::document(&yaml),
+    }
+    Ok(())
+}
+
+fn to_compose(service: &Service, services: &[Service], config: &ProjectConfig, git_root: &Path) -> ComposeService {
+    let relative = service
+        .path
+        .strip_prefix(git_root)
+        .map(|p| format!("./{}", p.display()))
+        .unwrap_or_else(|_| service.path.display().to_string());
+    let relative = if relative == "./" { ".".to_string() } else { relative };
+    let service_config = config.service(&service.name);
+
+    // Dev servers usually bind localhost only, which isn't reachable from outside a container
+    let command = match (&service.command, service.framework.host_flag()) {
+        (Some(command), _) => command.clone(),
+        (None, Some(flag)) => format!("npm run dev -- {} 0.0.0.0", flag),
+        (None, None) => "npm run dev".to_string(),
+    };
+    let mut compose = ComposeService {
+        build: None,
+        image: None,
+        working_dir: None,
+        volumes: Vec::new(),
+        command,
+        ports: service.port.map(|p| format!("{}:{}", p, p)).into_iter().collect(),
+        // Passed to the container in the order dev servers load them
+        env_file: ENV_FILES
+            .iter()
+            .filter(|f| service.path.join(f).exists())
+            .map(|f| format!("{}/{}", relative, f))
+            .collect(),
+        environment: service.env.clone(),
+        depends_on: service_config
+            .map(|c| {
+                c.depends_on
+                    .iter()
+                    .filter(|d| ExternalDependency::parse(d).is_none())
+                    .filter(|d| services.iter().any(|s| &s.name == *d))
+                    .map(|d| compose_name(d))
+                    .collect()
+            })
+            .unwrap_or_default(),
+    };
+
+    if service.path.join("Dockerfile").exists() {
+        compose.build = Some(relative);
+    } else {
+        // No image to build: run the dev script or command against the checked-out repository
+        let node = service_config
+            .and_then(|c| c.requires.node.as_deref())
+            .and_then(node_major)
+            .unwrap_or_else(|| "lts".to_string());
+        compose.image = Some(format!("node:{}", node));
+        let dir = relative.trim_start_matches('.').trim_start_matches('/');
+        compose.working_dir = Some(if dir.is_empty() { "/app".to_string() } else { format!("/app/{}", dir) });
+        compose.volumes = vec![".:/app".to_string()];
+    }
+    if let Some(port) = service.port {
+        compose.environment.insert("HOST".to_string(), "0.0.0.0".to_string());
+        compose.environment.insert("PORT".to_string(), port.to_string());
+    }
+    compose
+}
+
+/// Compose service names can't contain `:`
+fn compose_name(name: &str) -> String {
+    name.replace(':', "-")
+}
+
+/// `20` from requirements like `>=20.11` or `20`
+fn node_major(requirement: &str) -> Option<String> {
+    let digits: String = requirement
+        .trim_start_matches(['>', '=', '^', '~', 'v', ' '])
+        .chars()
+        .take_while(|c| c.is_ascii_digit())
+        .collect();
+    (!digits.is_empty()).then_some(digits)
+}
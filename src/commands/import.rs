@@ -0,0 +1,305 @@
+use anyhow::{Context, Result};
+use clap::ValueEnum;
+use console::style;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use crate::config::{ProjectConfig, PROJECT_CONFIG_FILE};
+use crate::discovery::find_git_root;
+use crate::output::{self, Level};
+
+/// Formats `groo import` reads
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+pub enum ImportSource {
+    /// docker-compose.yml / compose.yaml
+    Compose,
+    /// A foreman-style Procfile
+    Procfile,
+}
+
+impl ImportSource {
+    /// Files looked for in the repository root when `--file` isn't given
+    fn default_files(self) -> &'static [&'static str] {
+        match self {
+            ImportSource::Compose => &["compose.yaml", "compose.yml", "docker-compose.yml", "docker-compose.yaml"],
+            ImportSource::Procfile => &["Procfile.dev", "Procfile"],
+        }
+    }
+}
+
+/// A service entry as written to groo.toml
+#[derive(Debug, Serialize)]
+struct ImportedService {
+    command: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    path: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    port: Option<u16>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    depends_on: Vec<String>,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    env: BTreeMap<String, String>,
+}
+
+/// Port foreman gives the first Procfile process; each next one gets 100 more
+const FOREMAN_BASE_PORT: u16 = 5000;
+
+/// Convert Procfile or compose definitions into `[services]` entries in groo.toml
+pub fn run(source: ImportSource, file: Option<PathBuf>) -> Result<()> {
+    let git_root = find_git_root()?;
+    let file = match file {
+        Some(file) => std::env::current_dir()?.join(file),
+        None => source
+            .default_files()
+            .iter()
+            .map(|f| git_root.join(f))
+            .find(|f| f.exists())
+            .with_context(|| format!("No {} found in {}", source.default_files().join(" or "), git_root.display()))?,
+    };
+    let content = std::fs::read_to_string(&file)
+        .with_context(|| format!("Failed to read {}", file.display()))?;
+
+    let imported = match source {
+        ImportSource::Procfile => from_procfile(&content)?,
+        ImportSource::Compose => from_compose(&content, &file, &git_root)?,
+    };
+    if imported.is_empty() {
+        output::warn(format!("No services found in {}", file.display()));
+        return Ok(());
+    }
+
+    // Never overwrite what's already configured
+    let config = ProjectConfig::load(&git_root)?;
+    let mut added: BTreeMap<String, ImportedService> = BTreeMap::new();
+    for (name, service) in imported {
+        if config.service(&name).is_some() {
+            output::item(Level::Warning, format!("Skipped {}: already in {}", name, PROJECT_CONFIG_FILE));
+        } else {
+            added.insert(name, service);
+        }
+    }
+    if added.is_empty() {
+        return Ok(());
+    }
+
+    #[derive(Serialize)]
+    struct Services<'a> {
+        services: &'a BTreeMap<String, ImportedService>,
+    }
+    let config_path = git_root.join(PROJECT_CONFIG_FILE);
+    let existing = std::fs::read_to_string(&config_path).unwrap_or_default();
+    let mut out = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&config_path)
+        .with_context(|| format!("Failed to open {}", config_path.display()))?;
+    let separator = if existing.is_empty() || existing.ends_with("\n\n") { "" } else { "\n" };
+    write!(out, "{}{}", separator, toml::to_string(&Services { services: &added })?)?;
+
+    for (name, service) in &added {
+        let port = service.port.map(|p| format!(" on port {}", p)).unwrap_or_default();
+        output::item(Level::Success, format!("{}{}", style(name).cyan(), port));
+    }
+    output::success(format!(
+        "Imported {} service(s) from {} into {}",
+        added.len(),
+        file.file_name().and_then(|n| n.to_str()).unwrap_or_default(),
+        PROJECT_CONFIG_FILE
+    ));
+    output::line(format!(
+        "  Check the commands and ports, then run {}",
+        style("groo dev").cyan()
+    ));
+    Ok(())
+}
+
+/// `name: command` lines. Processes that read `$PORT` get foreman's port for them.
+fn from_procfile(content: &str) -> Result<Vec<(String, ImportedService)>> {
+    content
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .filter_map(|line| line.split_once(':'))
+        .enumerate()
+        .map(|(index, (name, command))| {
+            let name = name.trim().to_string();
+            let command = command.trim().to_string();
+            let port = if command.contains("$PORT") || command.contains("${PORT}") {
+                let port = u16::try_from(index)
+                    .ok()
+                    .and_then(|index| index.checked_mul(100))
+                    .and_then(|offset| FOREMAN_BASE_PORT.checked_add(offset))
+                    .with_context(|| format!("Too many processes in the Procfile to give {} a port", name))?;
+                Some(port)
+            } else {
+                None
+            };
+            let service = ImportedService {
+                command,
+                path: None,
+                port,
+                depends_on: Vec::new(),
+                env: BTreeMap::new(),
+            };
+            Ok((name, service))
+        })
+        .collect()
+}
+
+#[derive(Deserialize)]
+struct ComposeFile {
+    #[serde(default)]
+    services: BTreeMap<String, ComposeService>,
+}
+
+#[derive(Deserialize)]
+struct ComposeService {
+    build: Option<ComposeBuild>,
+    command: Option<StringOrList>,
+    #[serde(default)]
+    ports: Vec<ComposePort>,
+    environment: Option<ComposeEnvironment>,
+    depends_on: Option<ComposeDependsOn>,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum ComposeBuild {
+    Context(String),
+    Detailed { context: Option<String> },
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum StringOrList {
+    String(String),
+    List(Vec<String>),
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum ComposePort {
+    Short(String),
+    Number(u16),
+    Long { published: Option<PortNumber> },
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum PortNumber {
+    Number(u16),
+    String(String),
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum ComposeEnvironment {
+    Map(BTreeMap<String, Option<serde_yaml_ng::Value>>),
+    List(Vec<String>),
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum ComposeDependsOn {
+    List(Vec<String>),
+    Map(HashMap<String, serde_yaml_ng::Value>),
+}
+
+/// Services built from source with a command run natively in their build context; the
+/// rest (databases, queues, ...) run through `docker compose` so groo still manages them
+fn from_compose(content: &str, file: &Path, git_root: &Path) -> Result<Vec<(String, ImportedService)>> {
+    let compose: ComposeFile = serde_yaml_ng::from_str(content)
+        .with_context(|| format!("Failed to parse {}", file.display()))?;
+    let compose_dir = file.parent().unwrap_or(git_root);
+    let compose_arg = file.strip_prefix(git_root).unwrap_or(file).display().to_string();
+
+    let mut imported = Vec::new();
+    for (name, service) in compose.services {
+        let context = service.build.and_then(|build| match build {
+            ComposeBuild::Context(context) => Some(context),
+            ComposeBuild::Detailed { context } => context,
+        });
+        let native = context.zip(service.command.map(|command| match command {
+            StringOrList::String(command) => command,
+            StringOrList::List(args) => args.join(" "),
+        }));
+        let is_native = native.is_some();
+        let (command, path) = match native {
+            Some((context, command)) => {
+                let dir = compose_dir.join(context);
+                let dir = dir.canonicalize().unwrap_or(dir);
+                let path = dir
+                    .strip_prefix(git_root)
+                    .ok()
+                    .map(|p| p.display().to_string())
+                    .filter(|p| !p.is_empty());
+                (command, path)
+            }
+            None => (format!("docker compose -f {} up {}", compose_arg, name), None),
+        };
+
+        // Containers get their environment from the compose file itself
+        let env = match service.environment.filter(|_| is_native) {
+            Some(ComposeEnvironment::Map(map)) => map
+                .into_iter()
+                .map(|(key, value)| (key, value.map(yaml_scalar).unwrap_or_default()))
+                .collect(),
+            Some(ComposeEnvironment::List(list)) => list
+                .iter()
+                .map(|entry| match entry.split_once('=') {
+                    Some((key, value)) => (key.to_string(), value.to_string()),
+                    None => (entry.clone(), String::new()),
+                })
+                .collect(),
+            None => BTreeMap::new(),
+        };
+        let mut depends_on = match service.depends_on {
+            Some(ComposeDependsOn::List(list)) => list,
+            Some(ComposeDependsOn::Map(map)) => map.into_keys().collect(),
+            None => Vec::new(),
+        };
+        depends_on.sort();
+
+        imported.push((
+            name,
+            ImportedService {
+                command,
+                path,
+                port: service.ports.iter().find_map(host_port),
+                depends_on,
+                env,
+            },
+        ));
+    }
+    Ok(imported)
+}
+
+/// Host side of a compose port mapping: `8080:80`, `127.0.0.1:8080:80/tcp`, `3000`
+fn host_port(port: &ComposePort) -> Option<u16> {
+    match port {
+        ComposePort::Number(port) => Some(*port),
+        ComposePort::Short(mapping) => {
+            let mapping = mapping.split('/').next().unwrap_or(mapping);
+            let parts: Vec<&str> = mapping.rsplit(':').collect();
+            // With a container side the host port is the one before it
+            let host = if parts.len() > 1 { parts[1] } else { parts[0] };
+            host.split('-').next().and_then(|p| p.parse().ok())
+        }
+        ComposePort::Long { published } => match published {
+            Some(PortNumber::Number(port)) => Some(*port),
+            Some(PortNumber::String(port)) => port.parse().ok(),
+            None => None,
+        },
+    }
+}
+
+fn yaml_scalar(value: serde_yaml_ng::Value) -> String {
+    match value {
+        serde_yaml_ng::Value::String(s) => s,
+        serde_yaml_ng::Value::Bool(b) => b.to_string(),
+        serde_yaml_ng::Value::Number(n) => n.to_string(),
+        _ => String::new(),
+    }
+}
@@ -0,0 +1,154 @@
+use anyhow::{Context, Result};
+use console::{style, Term};
+use dialoguer::{Confirm, Input, MultiSelect};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use crate::config::PROJECT_CONFIG_FILE;
+use crate::discovery::{discover_services, find_git_root, get_project_name, Service};
+use crate::output;
+use crate::select::theme;
+use crate::state::State;
+
+/// What `groo init` writes to groo.toml
+#[derive(Serialize)]
+struct InitConfig {
+    dev: InitDev,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    services: BTreeMap<String, InitService>,
+}
+
+#[derive(Serialize)]
+struct InitDev {
+    preselect: &'static str,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    auto_start: Vec<String>,
+}
+
+#[derive(Serialize)]
+struct InitService {
+    port: u16,
+}
+
+/// Walk through writing a groo.toml for the current repository
+pub fn run() -> Result<()> {
+    let git_root = find_git_root()?;
+    let config_path = git_root.join(PROJECT_CONFIG_FILE);
+    if config_path.exists() {
+        anyhow::bail!("{} already exists", config_path.display());
+    }
+    if !Term::stderr().is_term() {
+        anyhow::bail!("groo init asks questions; run it in a terminal");
+    }
+    guide(&git_root)
+}
+
+/// On the first `groo dev` in a repository without groo.toml, offer to set one up.
+/// Returns whether a config was written, so the caller can pick it up.
+pub fn offer(git_root: &Path) -> Result<bool> {
+    if git_root.join(PROJECT_CONFIG_FILE).exists() || !Term::stderr().is_term() || output::is_json() {
+        return Ok(false);
+    }
+    // Seen before means the offer was already made, whatever the answer was
+    let mut state = State::load().unwrap_or_default();
+    if state.recent.iter().any(|r| r.path == git_root) {
+        return Ok(false);
+    }
+    state.touch_recent(&get_project_name(git_root), git_root.to_path_buf());
+    state.save()?;
+
+    output::step(format!("First time running groo here: there's no {} yet.", PROJECT_CONFIG_FILE));
+    let set_up = Confirm::with_theme(&theme())
+        .with_prompt("Check the detected ports and pick what starts by default?")
+        .default(true)
+        .interact()?;
+    output::blank();
+    if !set_up {
+        output::line(style(format!("  Run {} any time to do this later.", style("groo init").cyan())).dim());
+        output::blank();
+        return Ok(false);
+    }
+    guide(git_root)?;
+    output::blank();
+    Ok(true)
+}
+
+fn guide(git_root: &Path) -> Result<()> {
+    let services = discover_services(git_root)?;
+    if services.is_empty() {
+        anyhow::bail!("No services with dev scripts found to configure");
+    }
+
+    let max_name_len = services.iter().map(|s| s.name.len()).max().unwrap_or(0);
+    output::heading("Discovered services:");
+    for service in &services {
+        let port = service.port.map(|p| p.to_string()).unwrap_or_else(|| "-".to_string());
+        output::line(format!(
+            "  {}  {:<6} {}",
+            style(format!("{:<width$}", service.name, width = max_name_len)).cyan(),
+            port,
+            style(service.framework.to_string()).dim()
+        ));
+    }
+    output::blank();
+
+    // Only the ports that are evidently off: missing, or claimed by two services
+    let mut ports: BTreeMap<String, InitService> = BTreeMap::new();
+    for service in &services {
+        let Some(problem) = port_problem(service, &services) else {
+            continue;
+        };
+        let answer: String = Input::with_theme(&theme())
+            .with_prompt(format!("Port for {} ({}; empty to skip)", service.name, problem))
+            .allow_empty(true)
+            .validate_with(|input: &String| -> Result<(), &str> {
+                if input.is_empty() || input.parse::<u16>().is_ok_and(|p| p > 0) {
+                    Ok(())
+                } else {
+                    Err("Enter a port number")
+                }
+            })
+            .interact_text()?;
+        if let Ok(port) = answer.parse::<u16>()
+            && Some(port) != service.port
+        {
+            ports.insert(service.name.clone(), InitService { port });
+        }
+    }
+
+    let items: Vec<&str> = services.iter().map(|s| s.name.as_str()).collect();
+    let checked: Vec<bool> = services.iter().map(|s| s.port.is_some()).collect();
+    let chosen = MultiSelect::with_theme(&theme())
+        .with_prompt("Which services should `groo dev` check by default?")
+        .items(&items)
+        .defaults(&checked)
+        .interact_on(&Term::stderr())?;
+    let auto_start: Vec<String> = chosen.into_iter().map(|i| services[i].name.clone()).collect();
+
+    let config = InitConfig {
+        dev: InitDev {
+            preselect: if auto_start.is_empty() { "none" } else { "auto_start" },
+            auto_start,
+        },
+        services: ports,
+    };
+    let config_path = git_root.join(PROJECT_CONFIG_FILE);
+    std::fs::write(&config_path, toml::to_string(&config)?)
+        .with_context(|| format!("Failed to write {}", config_path.display()))?;
+
+    output::success(format!("Wrote {}", PROJECT_CONFIG_FILE));
+    output::line("  Commit it so everyone gets the same defaults. The README lists every setting.");
+    Ok(())
+}
+
+/// Why a service's detected port needs a second look, if it does
+fn port_problem(service: &Service, services: &[Service]) -> Option<String> {
+    let Some(port) = service.port else {
+        return Some("no port detected".to_string());
+    };
+    services
+        .iter()
+        .find(|other| other.name != service.name && other.port == Some(port))
+        .map(|other| format!("{} also uses {}", other.name, port))
+}
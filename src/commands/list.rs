@@ -1,32 +1,113 @@
 use anyhow::Result;
 use console::style;
+use serde::Serialize;
 
-use crate::state::State;
+use crate::config::GlobalConfig;
+use crate::discovery::{get_git_branch, get_git_commit};
+use crate::format;
+use crate::output::{self, Stream};
+use crate::state::{logs_disk_usage, State};
+use crate::template::Template;
+
+/// Fields available to `groo list --format`
+const FORMAT_FIELDS: &[&str] = &["name", "path", "services", "branch", "commit"];
+
+/// One row of `groo list --output json`
+#[derive(Serialize)]
+struct ProjectRow<'a> {
+    name: &'a str,
+    path: String,
+    services: Vec<&'a str>,
+    branch: Option<String>,
+    commit: Option<String>,
+}
+
+pub fn run(format: Option<String>) -> Result<()> {
+    let template = format
+        .map(|f| Template::parse(&f, FORMAT_FIELDS))
+        .transpose()?;
 
-pub fn run() -> Result<()> {
     let mut state = State::load()?;
     state.clean_stale_pids();
     state.save()?;
 
+    if let Some(template) = template {
+        for (name, project) in &state.projects {
+            let line = template.render(|field| match field {
+                "name" => name.clone(),
+                "path" => project.path.display().to_string(),
+                "services" => project.services.len().to_string(),
+                "branch" => get_git_branch(&project.path).unwrap_or_default(),
+                "commit" => get_git_commit(&project.path).unwrap_or_default(),
+                _ => String::new(),
+            });
+            output::raw(Stream::Stdout, line);
+        }
+        return Ok(());
+    }
+
+    if output::is_json() {
+        for (name, project) in &state.projects {
+            output::data(&ProjectRow {
+                name,
+                path: project.path.display().to_string(),
+                services: project.services.keys().map(String::as_str).collect(),
+                branch: get_git_branch(&project.path),
+                commit: get_git_commit(&project.path),
+            });
+        }
+        return Ok(());
+    }
+
     if state.projects.is_empty() {
-        println!("{}", style("No projects with running services.").yellow());
+        output::warn("No projects with running services.");
+        print_logs_usage();
         return Ok(());
     }
 
-    println!("{}", style("Projects with running services:").bold());
-    println!();
+    output::heading("Projects with running services:");
+    output::blank();
 
     for (name, project) in &state.projects {
         let service_count = project.services.len();
         let suffix = if service_count == 1 { "service" } else { "services" };
-        println!(
-            "  {} {} ({} {})",
+        output::line(format!(
+            "  {} {} ({} {})  {}",
             style("●").green(),
             style(name).cyan().bold(),
             service_count,
-            suffix
-        );
+            suffix,
+            style(describe_head(&project.path)).dim()
+        ));
     }
 
+    print_logs_usage();
+
     Ok(())
 }
+
+/// Branch name, or `detached@<sha>` when no branch is checked out
+pub fn describe_head(path: &std::path::Path) -> String {
+    match (get_git_branch(path), get_git_commit(path)) {
+        (Some(branch), _) => branch,
+        (None, Some(commit)) => format!("detached@{}", commit),
+        (None, None) => String::new(),
+    }
+}
+
+fn print_logs_usage() {
+    let usage = logs_disk_usage();
+    let warn_size_mb = GlobalConfig::cached().logs.warn_size_mb;
+
+    output::blank();
+    if usage > warn_size_mb.saturating_mul(1024 * 1024) {
+        output::warn(format!(
+            "Logs use {} (over {} MB). Run {} to trim them.",
+            format::bytes(usage),
+            warn_size_mb,
+            style("groo clean --logs --older-than 7d").cyan()
+        ));
+    } else {
+        output::line(style(format!("Logs use {}", format::bytes(usage))).dim());
+    }
+}
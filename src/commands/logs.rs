@@ -1,31 +1,16 @@
 use anyhow::Result;
-use console::{style, Style, Term};
-use dialoguer::{theme::ColorfulTheme, MultiSelect};
-use std::collections::VecDeque;
+use console::{style, Style};
+use std::collections::{BTreeMap, VecDeque};
 use std::io::{BufRead, Seek, SeekFrom};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use tokio::sync::broadcast;
 
-use crate::config::get_service_log_file;
-use crate::discovery::{discover_services, find_git_root, Service};
-use crate::runner::get_color_for_index;
-use crate::state::is_port_in_use;
-
-fn create_theme() -> ColorfulTheme {
-    ColorfulTheme {
-        defaults_style: Style::new().dim(),
-        prompt_style: Style::new().bold(),
-        prompt_prefix: style("?".to_string()).green().bold(),
-        success_prefix: style("✓".to_string()).green().bold(),
-        error_prefix: style("✗".to_string()).red().bold(),
-        checked_item_prefix: style("  ◉".to_string()).green(),
-        unchecked_item_prefix: style("  ○".to_string()).dim(),
-        active_item_style: Style::new().cyan().bold(),
-        inactive_item_style: Style::new().dim(),
-        active_item_prefix: style("❯".to_string()).cyan().bold(),
-        ..ColorfulTheme::default()
-    }
-}
+use crate::config::{GlobalConfig, ProjectConfig};
+use crate::discovery::{discover_services, find_git_root, get_project_name, FrameworkType, Service};
+use crate::output;
+use crate::runner::{get_color_for_index, print_service_error, print_service_log};
+use crate::select::{Filter, Selector};
+use crate::state::{is_mark_line, log_key, migrate_renames, parse_log_line, service_log_file, LogManifest, State};
 
 struct ServiceLogInfo {
     name: String,
@@ -33,221 +18,326 @@ struct ServiceLogInfo {
     color: Style,
 }
 
-pub async fn run(lines: usize, follow: bool) -> Result<()> {
-    let git_root = find_git_root()?;
-    let services = discover_services(&git_root)?;
+pub async fn run(
+    project: Option<String>,
+    lines: usize,
+    follow: bool,
+    since_mark: Option<String>,
+    service_queries: Vec<String>,
+) -> Result<()> {
+    let (project_name, git_root, services) = match project {
+        Some(name) => resolve_project(&name)?,
+        None => {
+            let git_root = find_git_root()?;
+            let services = discover_services(&git_root)?;
+            let project_name = get_project_name(&git_root);
+            // Before log files are looked up, so a moved service shows its old log
+            let mut state = State::load().unwrap_or_default();
+            if !migrate_renames(&mut state, &project_name, &git_root, &services).is_empty() {
+                state.save()?;
+            }
+            (project_name, git_root, services)
+        }
+    };
 
-    // Filter to only running services (port-based detection)
-    let running_services: Vec<&Service> = services
-        .iter()
-        .filter(|s| s.port.map(is_port_in_use).unwrap_or(false))
-        .collect();
+    let selector = Selector::new(&services, "Select services to view logs")
+        .filter(Filter::Running)
+        .queries(&service_queries);
 
-    if running_services.is_empty() {
-        println!(
-            "{} No running services found. Use {} to start services.",
-            style("!").yellow(),
+    if selector.candidates().is_empty() {
+        output::warn(format!(
+            "No running services found. Use {} to start services.",
             style("groo dev").cyan()
-        );
+        ));
         return Ok(());
     }
 
-    // Find max name length for alignment
-    let max_name_len = running_services.iter().map(|s| s.name.len()).max().unwrap_or(0);
-
-    // Display running services for selection
-    let items: Vec<String> = running_services
-        .iter()
-        .map(|s| {
-            let port_str = s.port
-                .map(|p| format!("{}", p))
-                .unwrap_or_else(|| "-".to_string());
-            format!(
-                "{:<width$}  {}",
-                s.name,
-                style(port_str).dim(),
-                width = max_name_len
-            )
-        })
-        .collect();
-
-    // All selected by default
-    let defaults: Vec<bool> = vec![true; running_services.len()];
-
-    let theme = create_theme();
-    let selections = MultiSelect::with_theme(&theme)
-        .with_prompt("Select services to view logs")
-        .items(&items)
-        .defaults(&defaults)
-        .interact_on(&Term::stderr())?;
-
-    if selections.is_empty() {
-        println!("{}", style("No services selected.").yellow());
+    let selected_services = selector.select()?;
+    if selected_services.is_empty() {
         return Ok(());
     }
 
     // Build list of selected services with their log files and colors
-    let selected: Vec<ServiceLogInfo> = selections
+    let selected: Vec<ServiceLogInfo> = selected_services
         .iter()
-        .map(|&i| {
-            let service = running_services[i];
+        .enumerate()
+        .map(|(i, service)| {
             ServiceLogInfo {
                 name: service.name.clone(),
-                log_file: get_service_log_file(&service.path),
+                log_file: service_log_file(&project_name, &git_root, service),
                 color: get_color_for_index(i),
             }
         })
         .collect();
 
-    // Show last N lines from each service
-    println!();
-    for info in &selected {
-        show_last_lines(&info.name, &info.log_file, &info.color, lines)?;
-    }
+    // Show the last N lines of each service, merged
+    output::blank();
+    show_last_lines(&selected, lines, since_mark.as_deref())?;
 
     // If follow mode, stream new lines
     if follow {
-        println!(
-            "\n{} Following logs... (Ctrl+C to stop)\n",
-            style("→").cyan().bold()
-        );
+        output::blank();
+        output::step("Following logs... (Ctrl+C to stop)");
+        output::blank();
         follow_logs(selected).await?;
     }
 
     Ok(())
 }
 
-fn show_last_lines(name: &str, log_file: &PathBuf, color: &Style, lines: usize) -> Result<()> {
-    if !log_file.exists() {
-        let prefix = color.apply_to(format!("[{}]", name));
-        println!("{} {}", prefix, style("(no logs yet)").dim());
-        return Ok(());
+/// Services of a project by name, from state so it works outside the repository. Projects
+/// with nothing tracked fall back to discovery in their recent path.
+fn resolve_project(name: &str) -> Result<(String, PathBuf, Vec<Service>)> {
+    let state = State::load().unwrap_or_default();
+
+    if let Some(project) = state.get_project(name) {
+        let config = ProjectConfig::load(&project.path).unwrap_or_default();
+        let manifest = LogManifest::load();
+        let mut services: Vec<Service> = project
+            .services
+            .iter()
+            .map(|(service_name, entry)| Service {
+                name: service_name.clone(),
+                path: manifest
+                    .entries
+                    .get(&log_key(&project.path, service_name))
+                    .map(|e| e.service_path.clone())
+                    .unwrap_or_else(|| project.path.clone()),
+                framework: FrameworkType::Unknown,
+                port: entry.port,
+                scheme: entry.scheme,
+                base_path: entry.base_path.clone(),
+                tags: config.service(service_name).map(|c| c.tags.clone()).unwrap_or_default(),
+                command: None,
+                env: BTreeMap::new(),
+                framework_env: BTreeMap::new(),
+                package_name: None,
+                priority: Default::default(),
+            })
+            .collect();
+        services.sort_by(|a, b| a.name.cmp(&b.name));
+        return Ok((name.to_string(), project.path.clone(), services));
     }
 
-    let file = std::fs::File::open(log_file)?;
-    let reader = std::io::BufReader::new(file);
+    let Some(recent) = state.recent.iter().find(|r| r.name == name && r.path.exists()) else {
+        anyhow::bail!("No project '{}' is running or was used recently", name);
+    };
+    let services = discover_services(&recent.path)?;
+    Ok((name.to_string(), recent.path.clone(), services))
+}
 
-    // Read all lines and keep last N
-    let mut last_lines: VecDeque<String> = VecDeque::with_capacity(lines);
-    for line in reader.lines() {
-        if let Ok(line) = line {
-            if last_lines.len() >= lines {
-                last_lines.pop_front();
-            }
-            last_lines.push_back(line);
+/// A stored line of one of the followed services, ready to be merged with the others
+struct LogLine<'a> {
+    seq: Option<u64>,
+    message: String,
+    info: &'a ServiceLogInfo,
+}
+
+/// Print the last `lines` lines of each log, or everything from the latest mark matching
+/// `since_mark`, merged into one view in the order the lines were captured
+fn show_last_lines(services: &[ServiceLogInfo], lines: usize, since_mark: Option<&str>) -> Result<()> {
+    let mut merged: Vec<LogLine> = Vec::new();
+    for info in services {
+        let prefix = info.color.apply_to(format!("[{}]", info.name));
+        if !info.log_file.exists() {
+            output::line(format!("{} {}", prefix, style("(no logs yet)").dim()));
+            continue;
         }
-    }
 
-    // Print each line with colored prefix
-    for line in last_lines {
-        // Log file format: [service] message, so just print directly
-        let prefix = color.apply_to(format!("[{}]", name));
-        // Remove [service] prefix from stored line if present
-        let message = if line.starts_with('[') {
-            if let Some(idx) = line.find(']') {
-                line[idx + 1..].trim_start().to_string()
-            } else {
-                line
+        let file = std::fs::File::open(&info.log_file)?;
+        let reader = std::io::BufReader::new(file);
+
+        let last_lines: VecDeque<String> = match since_mark {
+            Some(label) => {
+                let all: Vec<String> = reader.lines().map_while(Result::ok).collect();
+                let Some(start) = all.iter().rposition(|l| is_mark_line(parse_log_line(l).1, Some(label))) else {
+                    output::line(format!("{} {}", prefix, style(format!("(no mark matching '{}')", label)).dim()));
+                    continue;
+                };
+                all.into_iter().skip(start).collect()
+            }
+            None => {
+                // Read all lines and keep last N
+                let mut last_lines: VecDeque<String> = VecDeque::with_capacity(lines);
+                for line in reader.lines().map_while(Result::ok) {
+                    if last_lines.len() >= lines {
+                        last_lines.pop_front();
+                    }
+                    last_lines.push_back(line);
+                }
+                last_lines
             }
-        } else {
-            line
         };
-        println!("{} {}", prefix, message);
+
+        merged.extend(last_lines.iter().map(|line| {
+            let (seq, message) = parse_log_line(line);
+            LogLine {
+                seq,
+                message: message.to_string(),
+                info,
+            }
+        }));
     }
 
+    print_merged(merged);
     Ok(())
 }
 
+/// Print lines of several services in capture order. The sort is stable, so lines logged
+/// before sequencing keep their order within a service.
+fn print_merged(mut merged: Vec<LogLine>) {
+    merged.sort_by_key(|line| line.seq.unwrap_or_default());
+    for line in merged {
+        print_log_line(&line.info.name, &line.message, &line.info.color);
+    }
+}
+
+/// Print a stored log line, making `groo mark` markers stand out
+fn print_log_line(name: &str, message: &str, color: &Style) {
+    if is_mark_line(message, None) {
+        print_service_log(name, &style(message).yellow().bold().to_string(), color);
+    } else {
+        print_service_log(name, message, color);
+    }
+}
+
+/// Where following a log has got to
+#[derive(Default)]
+struct TailPosition {
+    /// Bytes of the log already printed
+    pos: u64,
+    /// First line of the log when last read, in polling mode. Sessions start their logs
+    /// with a new sequence number, so a different first line means the file was rewritten.
+    first_line: Option<String>,
+}
+
+/// Tail every log from one loop, so lines that arrive in the same poll are printed in the
+/// order they were captured rather than one service after another
 async fn follow_logs(services: Vec<ServiceLogInfo>) -> Result<()> {
-    let (shutdown_tx, _) = broadcast::channel::<()>(1);
+    let (shutdown_tx, mut shutdown_rx) = broadcast::channel::<()>(1);
 
     // Set up Ctrl+C handler
-    let shutdown_tx_clone = shutdown_tx.clone();
     tokio::spawn(async move {
         tokio::signal::ctrl_c().await.ok();
-        println!("\n{} Stopped following logs.", style("→").yellow().bold());
-        let _ = shutdown_tx_clone.send(());
+        output::blank();
+        output::step("Stopped following logs.");
+        let _ = shutdown_tx.send(());
     });
 
-    // Spawn a task for each service to tail its log file
-    let mut handles = Vec::new();
-    for info in services {
-        let mut shutdown_rx = shutdown_tx.subscribe();
-        let handle = tokio::spawn(async move {
-            if let Err(e) = tail_log_file(&info.name, &info.log_file, &info.color, &mut shutdown_rx).await {
-                let prefix = info.color.apply_to(format!("[{}]", info.name));
-                eprintln!("{} Error: {}", prefix, e);
+    let settings = &GlobalConfig::cached().logs;
+
+    // Start from the end of existing logs; logs created later are read from the start
+    let mut positions: Vec<TailPosition> = services
+        .iter()
+        .map(|info| {
+            if settings.poll {
+                end_of_log(&info.log_file).unwrap_or_default()
+            } else {
+                TailPosition {
+                    pos: std::fs::metadata(&info.log_file).map(|m| m.len()).unwrap_or(0),
+                    first_line: None,
+                }
             }
-        });
-        handles.push(handle);
-    }
+        })
+        .collect();
 
-    // Wait for all tasks to complete
-    for handle in handles {
-        let _ = handle.await;
+    loop {
+        tokio::select! {
+            _ = shutdown_rx.recv() => break,
+            _ = tokio::time::sleep(settings.poll_interval()) => {
+                let mut merged: Vec<LogLine> = Vec::new();
+                for (info, tail) in services.iter().zip(positions.iter_mut()) {
+                    let result = if settings.poll {
+                        poll_new_lines(info, tail, &mut merged)
+                    } else {
+                        read_new_lines(info, &mut tail.pos, &mut merged)
+                    };
+                    if let Err(e) = result {
+                        print_service_error(&info.name, &format!("Error: {}", e), &info.color);
+                    }
+                }
+                print_merged(merged);
+            }
+        }
     }
 
     Ok(())
 }
 
-async fn tail_log_file(
-    name: &str,
-    log_file: &PathBuf,
-    color: &Style,
-    shutdown_rx: &mut broadcast::Receiver<()>,
-) -> Result<()> {
-    // Wait for file to exist
-    while !log_file.exists() {
-        tokio::select! {
-            _ = shutdown_rx.recv() => return Ok(()),
-            _ = tokio::time::sleep(tokio::time::Duration::from_millis(100)) => {}
-        }
-    }
+/// Position at the end of a log, found by reading it rather than from its (possibly cached) size
+fn end_of_log(log_file: &Path) -> std::io::Result<TailPosition> {
+    let mut reader = std::io::BufReader::new(std::fs::File::open(log_file)?);
+    let mut first_line = String::new();
+    reader.read_line(&mut first_line)?;
+    let pos = reader.seek(SeekFrom::End(0))?;
+    Ok(TailPosition {
+        pos,
+        first_line: Some(first_line),
+    })
+}
 
-    // Open file and seek to end
-    let file = tokio::fs::File::open(log_file).await?;
-    let metadata = file.metadata().await?;
-    let mut pos = metadata.len();
+/// Collect complete lines appended to a log since the last poll.
+///
+/// Network filesystems cache file sizes for seconds, so new lines and truncation can't be
+/// told from metadata. Opening the file revalidates it; a partial last line is left for
+/// the next poll.
+fn poll_new_lines<'a>(info: &'a ServiceLogInfo, tail: &mut TailPosition, merged: &mut Vec<LogLine<'a>>) -> Result<()> {
+    let Ok(file) = std::fs::File::open(&info.log_file) else {
+        return Ok(());
+    };
+    let mut reader = std::io::BufReader::new(file);
+    let mut first_line = String::new();
+    reader.read_line(&mut first_line)?;
+    if tail.first_line.as_ref() != Some(&first_line) {
+        // Rewritten by a new session (or created since): start over
+        tail.pos = 0;
+        tail.first_line = Some(first_line);
+    }
 
+    reader.seek(SeekFrom::Start(tail.pos))?;
+    let mut line = String::new();
     loop {
-        tokio::select! {
-            _ = shutdown_rx.recv() => break,
-            _ = tokio::time::sleep(tokio::time::Duration::from_millis(100)) => {
-                // Check if file has grown
-                let file = tokio::fs::File::open(log_file).await?;
-                let metadata = file.metadata().await?;
-                let new_len = metadata.len();
-
-                if new_len > pos {
-                    // Read new content
-                    let mut file = std::fs::File::open(log_file)?;
-                    file.seek(SeekFrom::Start(pos))?;
-
-                    let reader = std::io::BufReader::new(file);
-                    for line in reader.lines() {
-                        if let Ok(line) = line {
-                            let prefix = color.apply_to(format!("[{}]", name));
-                            // Remove [service] prefix from stored line if present
-                            let message = if line.starts_with('[') {
-                                if let Some(idx) = line.find(']') {
-                                    line[idx + 1..].trim_start().to_string()
-                                } else {
-                                    line
-                                }
-                            } else {
-                                line
-                            };
-                            println!("{} {}", prefix, message);
-                        }
-                    }
-                    pos = new_len;
-                } else if new_len < pos {
-                    // File was truncated (new session), reset position
-                    pos = 0;
-                }
-            }
+        line.clear();
+        let read = reader.read_line(&mut line)?;
+        if read == 0 || !line.ends_with('\n') {
+            break;
         }
+        tail.pos += read as u64;
+        let (seq, message) = parse_log_line(line.trim_end_matches(['\n', '\r']));
+        merged.push(LogLine {
+            seq,
+            message: message.to_string(),
+            info,
+        });
     }
+    Ok(())
+}
 
+/// Collect lines appended to a log since `pos`
+fn read_new_lines<'a>(info: &'a ServiceLogInfo, pos: &mut u64, merged: &mut Vec<LogLine<'a>>) -> Result<()> {
+    let Ok(metadata) = std::fs::metadata(&info.log_file) else {
+        return Ok(());
+    };
+    let new_len = metadata.len();
+
+    if new_len > *pos {
+        // Read new content
+        let mut file = std::fs::File::open(&info.log_file)?;
+        file.seek(SeekFrom::Start(*pos))?;
+
+        let reader = std::io::BufReader::new(file);
+        for line in reader.lines().map_while(Result::ok) {
+            let (seq, message) = parse_log_line(&line);
+            merged.push(LogLine {
+                seq,
+                message: message.to_string(),
+                info,
+            });
+        }
+        *pos = new_len;
+    } else if new_len < *pos {
+        // File was truncated (new session), reset position
+        *pos = 0;
+    }
     Ok(())
 }
@@ -0,0 +1,66 @@
+use anyhow::Result;
+use console::style;
+use std::io::Write;
+
+use crate::discovery::{discover_services, find_git_root, get_project_name};
+use crate::output;
+use crate::runner::local_time;
+use crate::select::{Filter, Selector};
+use crate::state::{encode_log_line, migrate_renames, next_log_seq, service_log_file, Mark, State};
+
+/// Write a timestamped marker line into the logs of running services, and onto the console
+/// of the `groo dev` session running them
+pub fn run(label: &str, service_queries: Vec<String>) -> Result<()> {
+    let git_root = find_git_root()?;
+    let project_name = get_project_name(&git_root);
+    let services = discover_services(&git_root)?;
+    // Before log files are looked up, so a moved service keeps writing to its old log
+    let mut state = State::load().unwrap_or_default();
+    if !migrate_renames(&mut state, &project_name, &git_root, &services).is_empty() {
+        state.save()?;
+    }
+
+    let selector = Selector::new(&services, "Select services to mark")
+        .filter(Filter::Running)
+        .queries(&service_queries);
+    if selector.candidates().is_empty() {
+        output::warn(format!(
+            "No running services found. Use {} to start services.",
+            style("groo dev").cyan()
+        ));
+        return Ok(());
+    }
+    // Every running service unless narrowed down with -s; no prompt mid-reproduction
+    let selected = if service_queries.is_empty() {
+        selector.candidates()
+    } else {
+        selector.select()?
+    };
+    if selected.is_empty() {
+        return Ok(());
+    }
+
+    let mark = Mark {
+        label: label.to_string(),
+        time: local_time(),
+        services: selected.iter().map(|s| s.name.clone()).collect(),
+    };
+    for service in &selected {
+        let log_file = service_log_file(&project_name, &git_root, service);
+        // Services started outside groo have no log to mark
+        if !log_file.exists() {
+            continue;
+        }
+        let mut file = std::fs::OpenOptions::new().append(true).open(&log_file)?;
+        file.write_all(encode_log_line(next_log_seq(), &service.name, &mark.line()).as_bytes())?;
+    }
+    mark.append(&project_name)?;
+
+    output::success(format!(
+        "Marked {} service(s) at {}: {}",
+        selected.len(),
+        mark.time,
+        style(label).bold()
+    ));
+    Ok(())
+}
@@ -1,7 +1,25 @@
+pub mod clean;
+pub mod custom;
 pub mod dev;
+pub mod discover;
+pub mod doctor;
+pub mod env;
+pub mod export;
+pub mod import;
+pub mod init;
 pub mod list;
 pub mod logs;
+pub mod mark;
+pub mod new;
 pub mod open;
+pub mod path;
+pub mod plan;
+pub mod recent;
+pub mod reconcile;
+pub mod report;
 pub mod restart;
+pub mod setup;
+pub mod shell_init;
 pub mod status;
 pub mod stop;
+pub mod task;
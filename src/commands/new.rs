@@ -0,0 +1,311 @@
+use anyhow::{Context, Result};
+use clap::ValueEnum;
+use console::style;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use crate::backend::backend;
+use crate::config::PROJECT_CONFIG_FILE;
+use crate::discovery::{discover_services, find_git_root};
+use crate::output;
+
+/// Built-in app scaffolds for `groo new`
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+pub enum AppTemplate {
+    /// Vite + React single-page app
+    ViteReact,
+    /// Next.js app router
+    Next,
+    /// Hono on Cloudflare Workers via wrangler
+    HonoWorker,
+}
+
+impl AppTemplate {
+    fn name(self) -> &'static str {
+        match self {
+            AppTemplate::ViteReact => "vite-react",
+            AppTemplate::Next => "next",
+            AppTemplate::HonoWorker => "hono-worker",
+        }
+    }
+
+    /// The framework's usual port, the first one tried for a new app
+    fn base_port(self) -> u16 {
+        match self {
+            AppTemplate::ViteReact => 5173,
+            AppTemplate::Next => 3000,
+            AppTemplate::HonoWorker => 8787,
+        }
+    }
+
+    /// Files to write, relative to the app directory. `{name}` and `{port}` are filled in.
+    fn files(self) -> &'static [(&'static str, &'static str)] {
+        match self {
+            AppTemplate::ViteReact => &[
+                ("package.json", VITE_REACT_PACKAGE),
+                ("vite.config.js", VITE_REACT_CONFIG),
+                ("index.html", VITE_REACT_INDEX),
+                ("src/main.jsx", VITE_REACT_MAIN),
+            ],
+            AppTemplate::Next => &[
+                ("package.json", NEXT_PACKAGE),
+                ("app/layout.js", NEXT_LAYOUT),
+                ("app/page.js", NEXT_PAGE),
+            ],
+            AppTemplate::HonoWorker => &[
+                ("package.json", HONO_PACKAGE),
+                ("wrangler.toml", HONO_WRANGLER),
+                ("src/index.ts", HONO_INDEX),
+            ],
+        }
+    }
+}
+
+/// Scaffold an app from a template at `path` so it shows up in discovery right away
+pub fn run(template: AppTemplate, path: &Path) -> Result<()> {
+    let git_root = find_git_root()?;
+    let app_dir = std::env::current_dir()?.join(path);
+
+    if app_dir.exists() && app_dir.read_dir()?.next().is_some() {
+        anyhow::bail!("{} already exists and isn't empty", path.display());
+    }
+    // Checked before anything is created, so a rejected path leaves nothing behind
+    let (resolved, created) = resolve_new_dir(&app_dir)?;
+    if !resolved.starts_with(&git_root) {
+        anyhow::bail!("{} is outside the repository at {}", path.display(), git_root.display());
+    }
+    std::fs::create_dir_all(&app_dir)
+        .with_context(|| format!("Failed to create {}", app_dir.display()))?;
+
+    let result = scaffold(template, &git_root, &resolved);
+    if result.is_err() {
+        // Put things back the way they were: gone if groo made the directory, else empty
+        match created {
+            Some(created) => {
+                let _ = std::fs::remove_dir_all(created);
+            }
+            None => {
+                for entry in std::fs::read_dir(&resolved).into_iter().flatten().flatten() {
+                    let path = entry.path();
+                    let _ = if path.is_dir() {
+                        std::fs::remove_dir_all(path)
+                    } else {
+                        std::fs::remove_file(path)
+                    };
+                }
+            }
+        }
+    }
+    result
+}
+
+/// Where a directory that may not exist yet would end up, with symlinks in the part that
+/// exists resolved, and the outermost directory creating it would add
+fn resolve_new_dir(dir: &Path) -> Result<(PathBuf, Option<PathBuf>)> {
+    let mut existing = dir;
+    let mut missing = Vec::new();
+    while !existing.exists() {
+        let (Some(name), Some(parent)) = (existing.file_name(), existing.parent()) else {
+            anyhow::bail!("{} isn't a valid directory for a new app", dir.display());
+        };
+        missing.push(name);
+        existing = parent;
+    }
+    let created = missing.last().map(|name| existing.join(name));
+    let mut resolved = existing.canonicalize()?;
+    resolved.extend(missing.iter().rev());
+    Ok((resolved, created))
+}
+
+/// Write the template's files into `app_dir` and tag the service it becomes
+fn scaffold(template: AppTemplate, git_root: &Path, app_dir: &Path) -> Result<()> {
+    let name = app_dir
+        .file_name()
+        .and_then(|n| n.to_str())
+        .unwrap_or("app")
+        .to_string();
+    let port = pick_port(git_root, template.base_port())?;
+
+    for (file, content) in template.files() {
+        let target = app_dir.join(file);
+        if let Some(parent) = target.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let content = content
+            .replace("{name}", &name)
+            .replace("{port}", &port.to_string());
+        std::fs::write(&target, content)
+            .with_context(|| format!("Failed to write {}", target.display()))?;
+    }
+
+    let Some(service) = discover_services(git_root)?
+        .into_iter()
+        .find(|s| s.path == app_dir)
+    else {
+        anyhow::bail!("Created {} but discovery didn't pick it up", app_dir.display());
+    };
+
+    tag_service(git_root, &service.name, template)?;
+
+    output::success(format!(
+        "Created {} from {} on port {}",
+        style(&service.name).cyan(),
+        template.name(),
+        port
+    ));
+    output::line(format!(
+        "  Install dependencies, then run {}",
+        style(format!("groo dev -s {}", service.name)).cyan()
+    ));
+
+    Ok(())
+}
+
+/// First port from `base` up that no discovered service claims and nothing listens on
+fn pick_port(git_root: &Path, base: u16) -> Result<u16> {
+    let taken: Vec<u16> = discover_services(git_root)?
+        .iter()
+        .filter_map(|s| s.port)
+        .collect();
+    (base..u16::MAX)
+        .find(|port| !taken.contains(port) && !backend().is_port_in_use(*port))
+        .context("No free port found")
+}
+
+/// Tag the new service with its template in groo.toml, e.g. for `groo dev -s @vite-react`
+fn tag_service(git_root: &Path, service_name: &str, template: AppTemplate) -> Result<()> {
+    let config_path = git_root.join(PROJECT_CONFIG_FILE);
+    let header = format!("[services.\"{}\"]", service_name);
+    let existing = std::fs::read_to_string(&config_path).unwrap_or_default();
+    if existing.contains(&header) {
+        return Ok(());
+    }
+
+    let mut file = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&config_path)
+        .with_context(|| format!("Failed to open {}", config_path.display()))?;
+    let separator = if existing.is_empty() || existing.ends_with("\n\n") { "" } else { "\n" };
+    write!(
+        file,
+        "{}{}\ntags = [\"{}\"]\n",
+        separator,
+        header,
+        template.name()
+    )?;
+    Ok(())
+}
+
+const VITE_REACT_PACKAGE: &str = r#"{
+  "name": "{name}",
+  "private": true,
+  "type": "module",
+  "scripts": {
+    "dev": "vite",
+    "build": "vite build"
+  },
+  "dependencies": {
+    "react": "^19.0.0",
+    "react-dom": "^19.0.0"
+  },
+  "devDependencies": {
+    "@vitejs/plugin-react": "^4.3.0",
+    "vite": "^6.0.0"
+  }
+}
+"#;
+
+const VITE_REACT_CONFIG: &str = r#"import { defineConfig } from "vite";
+import react from "@vitejs/plugin-react";
+
+export default defineConfig({
+  plugins: [react()],
+  server: {
+    port: {port},
+    strictPort: true,
+  },
+});
+"#;
+
+const VITE_REACT_INDEX: &str = r#"<!doctype html>
+<html lang="en">
+  <head>
+    <meta charset="UTF-8" />
+    <title>{name}</title>
+  </head>
+  <body>
+    <div id="root"></div>
+    <script type="module" src="/src/main.jsx"></script>
+  </body>
+</html>
+"#;
+
+const VITE_REACT_MAIN: &str = r#"import { createRoot } from "react-dom/client";
+
+createRoot(document.getElementById("root")).render(<h1>{name}</h1>);
+"#;
+
+const NEXT_PACKAGE: &str = r#"{
+  "name": "{name}",
+  "private": true,
+  "scripts": {
+    "dev": "next dev --port {port}",
+    "build": "next build"
+  },
+  "dependencies": {
+    "next": "^15.0.0",
+    "react": "^19.0.0",
+    "react-dom": "^19.0.0"
+  }
+}
+"#;
+
+const NEXT_LAYOUT: &str = r#"export const metadata = { title: "{name}" };
+
+export default function RootLayout({ children }) {
+  return (
+    <html lang="en">
+      <body>{children}</body>
+    </html>
+  );
+}
+"#;
+
+const NEXT_PAGE: &str = r#"export default function Page() {
+  return <h1>{name}</h1>;
+}
+"#;
+
+const HONO_PACKAGE: &str = r#"{
+  "name": "{name}",
+  "private": true,
+  "scripts": {
+    "dev": "wrangler dev",
+    "deploy": "wrangler deploy"
+  },
+  "dependencies": {
+    "hono": "^4.6.0"
+  },
+  "devDependencies": {
+    "wrangler": "^3.90.0"
+  }
+}
+"#;
+
+const HONO_WRANGLER: &str = r#"name = "{name}"
+main = "src/index.ts"
+compatibility_date = "2024-11-01"
+
+[dev]
+port = {port}
+"#;
+
+const HONO_INDEX: &str = r#"import { Hono } from "hono";
+
+const app = new Hono();
+
+app.get("/", (c) => c.text("{name}"));
+
+export default app;
+"#;
@@ -1,52 +1,98 @@
 use anyhow::Result;
-use console::style;
+use console::{style, Term};
+use tokio::time::{Duration, Instant};
 
-use crate::discovery::{find_git_root, get_project_name};
-use crate::state::State;
+use crate::discovery::{discover_services, find_git_root, find_service, get_project_name, service_url};
+use crate::output::{self, OutputMode};
+use crate::runner::is_port_ready;
+use crate::state::{ServiceState, State};
 
-pub fn run(service_name: &str) -> Result<()> {
+const SPINNER_FRAMES: &[&str] = &["⠋", "⠙", "⠹", "⠸", "⠼", "⠴", "⠦", "⠧", "⠇", "⠏"];
+
+pub async fn run(query: &str, no_wait: bool, timeout_secs: u64) -> Result<()> {
     let git_root = find_git_root()?;
     let project_name = get_project_name(&git_root);
+    let services = discover_services(&git_root)?;
+    let service_name = find_service(&services, query)?.name.as_str();
 
     let state = State::load()?;
+    let running = state.get_project(&project_name).and_then(|p| p.services.get(service_name));
 
-    let project_state = match state.get_project(&project_name) {
-        Some(p) => p,
-        None => {
-            anyhow::bail!(
-                "No running services found for project '{}'. Run 'gr dev' first.",
-                project_name
-            );
+    // A service that's stopped or only just starting is opened where it last came up
+    let (port, url) = match (running, state.last_endpoint(&project_name, service_name)) {
+        (Some(ServiceState { port: Some(port), scheme, base_path, .. }), _) => {
+            (*port, service_url(*scheme, *port, base_path.as_deref()))
         }
-    };
-
-    let service = match project_state.services.get(service_name) {
-        Some(s) => s,
-        None => {
-            let available: Vec<&str> = project_state.services.keys().map(|s| s.as_str()).collect();
+        (_, Some(endpoint)) => (endpoint.port, endpoint.url()),
+        (Some(_), None) => anyhow::bail!("Service '{}' has no port configured", service_name),
+        (None, None) => {
+            let mut known: Vec<&str> = state
+                .get_project(&project_name)
+                .map(|p| p.services.keys().map(|s| s.as_str()).collect())
+                .unwrap_or_default();
+            if known.is_empty() {
+                anyhow::bail!(
+                    "No running services found for project '{}'. Run 'gr dev' first.",
+                    project_name
+                );
+            }
+            known.sort();
             anyhow::bail!(
                 "Service '{}' not found. Available services: {}",
                 service_name,
-                available.join(", ")
+                known.join(", ")
             );
         }
     };
 
-    let port = match service.port {
-        Some(p) => p,
-        None => {
-            anyhow::bail!("Service '{}' has no port configured", service_name);
-        }
-    };
+    if !no_wait && !wait_until_ready(service_name, port, Duration::from_secs(timeout_secs)).await {
+        anyhow::bail!(
+            "{} didn't accept connections on port {} within {}s. Use --no-wait to open anyway.",
+            service_name,
+            port,
+            timeout_secs
+        );
+    }
 
-    let url = format!("http://localhost:{}", port);
-    println!(
-        "{} Opening {} in browser...",
-        style("→").green().bold(),
-        style(&url).cyan()
-    );
+    output::step(format!("Opening {} in browser...", style(&url).cyan()));
 
     open::that(&url)?;
 
     Ok(())
 }
+
+/// Poll the service's port with a spinner until it accepts connections or `timeout` passes
+async fn wait_until_ready(service_name: &str, port: u16, timeout: Duration) -> bool {
+    if is_port_ready(port).await {
+        return true;
+    }
+
+    let term = Term::stderr();
+    let spinner = term.is_term() && output::mode() == OutputMode::Human;
+    let started = Instant::now();
+    let mut frame = 0;
+    let ready = loop {
+        if is_port_ready(port).await {
+            break true;
+        }
+        if started.elapsed() >= timeout {
+            break false;
+        }
+        if spinner {
+            let _ = term.clear_line();
+            let _ = term.write_str(&format!(
+                "{} Waiting for {} on port {}... ({}s)",
+                style(SPINNER_FRAMES[frame % SPINNER_FRAMES.len()]).cyan(),
+                service_name,
+                port,
+                started.elapsed().as_secs()
+            ));
+        }
+        frame += 1;
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    };
+    if spinner {
+        let _ = term.clear_line();
+    }
+    ready
+}
@@ -0,0 +1,42 @@
+use anyhow::Result;
+use std::io::Write;
+use std::path::Path;
+
+use crate::discovery::{discover_services, find_git_root, find_service};
+use crate::output::{self, Stream};
+
+/// Print a service's directory (or the project root), for scripts and `groo cd`
+pub fn run(service: Option<String>, list: bool) -> Result<()> {
+    let git_root = find_git_root()?;
+
+    if list {
+        for service in discover_services(&git_root)? {
+            output::raw(Stream::Stdout, service.name);
+        }
+        return Ok(());
+    }
+
+    match service {
+        Some(query) => {
+            let services = discover_services(&git_root)?;
+            print_path(&find_service(&services, &query)?.path)?;
+        }
+        None => print_path(&git_root)?,
+    }
+
+    Ok(())
+}
+
+/// Print a path as its raw bytes, so `cd "$(groo path ...)"` works for paths that aren't UTF-8
+fn print_path(path: &Path) -> Result<()> {
+    let mut stdout = std::io::stdout();
+    #[cfg(unix)]
+    {
+        use std::os::unix::ffi::OsStrExt;
+        stdout.write_all(path.as_os_str().as_bytes())?;
+    }
+    #[cfg(not(unix))]
+    write!(stdout, "{}", path.display())?;
+    writeln!(stdout)?;
+    Ok(())
+}
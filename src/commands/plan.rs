@@ -0,0 +1,128 @@
+use anyhow::{Context, Result};
+use console::style;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use crate::commands::dev;
+use crate::config::ProjectConfig;
+use crate::discovery::{discover_services, find_git_root, get_project_name, Service};
+use crate::output::{self, Level};
+use crate::runner::Overrides;
+use crate::state::{Session, State};
+
+/// Which services a session started and how, saved by `groo plan save`. Services are
+/// stored by name, so a plan works in any clone of the repository.
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct Plan {
+    #[serde(default)]
+    services: Vec<PlanService>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct PlanService {
+    name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    port: Option<u16>,
+    /// Only ever written by hand: `save` leaves `--env` values out, since they may be secrets
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    env: BTreeMap<String, String>,
+}
+
+/// Plans live in the repository so they can be committed and shared. Names are limited to
+/// letters, digits, `-` and `_` so they can't point outside `.groo/plans`.
+fn plan_file(git_root: &Path, name: &str) -> Result<PathBuf> {
+    if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_') {
+        anyhow::bail!("Invalid plan name '{}'. Use letters, digits, '-' and '_'", name);
+    }
+    Ok(git_root.join(".groo").join("plans").join(format!("{}.toml", name)))
+}
+
+/// Save the services and ports of the current or last session as a plan
+pub fn save(name: &str) -> Result<()> {
+    let git_root = find_git_root()?;
+    let project_name = get_project_name(&git_root);
+    let path = plan_file(&git_root, name)?;
+
+    let Some(session) = Session::latest_for_project(&project_name) else {
+        anyhow::bail!("No session recorded for {} yet. Start one with `groo dev`", project_name);
+    };
+
+    // A service restarted during the session shows up once per run; the last run wins
+    let mut plan = Plan::default();
+    let mut left_out: BTreeMap<String, Vec<String>> = BTreeMap::new();
+    for run in session.services {
+        left_out.remove(&run.name);
+        if !run.env_hashes.is_empty() {
+            left_out.insert(run.name.clone(), run.env_hashes.into_keys().collect());
+        }
+        let service = PlanService {
+            name: run.name,
+            port: run.port,
+            env: BTreeMap::new(),
+        };
+        match plan.services.iter_mut().find(|s| s.name == service.name) {
+            Some(existing) => *existing = service,
+            None => plan.services.push(service),
+        }
+    }
+    if plan.services.is_empty() {
+        anyhow::bail!("The last session didn't start any services");
+    }
+
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(&path, toml::to_string_pretty(&plan)?)
+        .with_context(|| format!("Failed to write {}", path.display()))?;
+
+    output::success(format!(
+        "Saved plan {} with {} service(s) to {}",
+        style(name).bold(),
+        plan.services.len(),
+        path.strip_prefix(&git_root).unwrap_or(&path).display()
+    ));
+    for (service, keys) in &left_out {
+        output::item(
+            Level::Warning,
+            format!("Left out --env {} for {}: add it to the plan by hand if it isn't secret", keys.join(", "), service),
+        );
+    }
+    Ok(())
+}
+
+/// Start the services of a saved plan with their env overrides and ports
+pub async fn run(name: &str) -> Result<()> {
+    let git_root = find_git_root()?;
+    let project_name = get_project_name(&git_root);
+    let config = ProjectConfig::load(&git_root)?;
+
+    let path = plan_file(&git_root, name)?;
+    let content = std::fs::read_to_string(&path)
+        .with_context(|| format!("No plan '{}' at {}", name, path.display()))?;
+    let plan: Plan = toml::from_str(&content)
+        .with_context(|| format!("Failed to parse {}", path.display()))?;
+
+    let services = discover_services(&git_root)?;
+
+    // Resolve every service before starting any
+    let mut selected: Vec<(&Service, Overrides)> = Vec::new();
+    for entry in &plan.services {
+        let Some(service) = services.iter().find(|s| s.name == entry.name) else {
+            anyhow::bail!("Plan '{}' starts {}, which wasn't found in this repository", name, entry.name);
+        };
+        let overrides = Overrides {
+            // Only override when the plan ran the service somewhere other than its usual port
+            port: entry.port.filter(|port| Some(*port) != service.port),
+            env: entry.env.clone(),
+        };
+        selected.push((service, overrides));
+    }
+
+    let mut state = State::load().unwrap_or_default();
+    state.clean_stale_pids();
+    state.save()?;
+
+    output::step(format!("Running plan {}", style(name).bold()));
+    dev::launch(&git_root, &project_name, &config, state, selected, dev::Cleanup::Project).await
+}
@@ -0,0 +1,126 @@
+use anyhow::Result;
+use console::{style, Term};
+use dialoguer::FuzzySelect;
+use serde::Serialize;
+use std::path::PathBuf;
+
+use crate::format;
+use crate::output;
+use crate::select::theme;
+use crate::state::{RecentProject, State};
+
+/// One row of `groo recent --output json`
+#[derive(Serialize)]
+struct RecentRow<'a> {
+    name: &'a str,
+    path: String,
+    /// Unix time in milliseconds
+    last_used: u64,
+    running: usize,
+}
+
+pub fn run() -> Result<()> {
+    let mut state = State::load()?;
+    state.clean_stale_pids();
+    state.save()?;
+
+    if state.recent.is_empty() {
+        output::warn("No recent projects yet. Run groo dev in a project first.");
+        return Ok(());
+    }
+
+    if output::is_json() {
+        for recent in &state.recent {
+            output::data(&RecentRow {
+                name: &recent.name,
+                path: recent.path.display().to_string(),
+                last_used: recent.last_used,
+                running: running_count(&state, recent),
+            });
+        }
+        return Ok(());
+    }
+
+    let max_name_len = state.recent.iter().map(|r| r.name.len()).max().unwrap_or(0);
+
+    for recent in &state.recent {
+        let running = running_count(&state, recent);
+        let marker = if running > 0 { style("●").green() } else { style("○").dim() };
+        let running_str = if running > 0 {
+            format!("  {}", style(format!("{} running", running)).green())
+        } else {
+            String::new()
+        };
+        output::line(format!(
+            "  {} {:<width$}  {}  {}{}",
+            marker,
+            style(&recent.name).cyan().bold(),
+            style(recent.path.display()).dim(),
+            style(format::since(recent.last_used)).dim(),
+            running_str,
+            width = max_name_len
+        ));
+    }
+
+    Ok(())
+}
+
+/// Resolve a recent project root by name or path substring, or pick one interactively
+/// when `query` is empty
+pub fn pick_project(query: &str) -> Result<PathBuf> {
+    let mut state = State::load().unwrap_or_default();
+    state.clean_stale_pids();
+
+    // Projects whose directory is gone can't be switched to
+    let recent: Vec<&RecentProject> = state.recent.iter().filter(|r| r.path.exists()).collect();
+    if recent.is_empty() {
+        anyhow::bail!("No recent projects yet. Run groo dev in a project first.");
+    }
+
+    if !query.is_empty() {
+        if let Some(exact) = recent.iter().find(|r| r.name == query) {
+            return Ok(exact.path.clone());
+        }
+        let matches: Vec<&&RecentProject> = recent
+            .iter()
+            .filter(|r| r.name.contains(query) || r.path.to_string_lossy().contains(query))
+            .collect();
+        return match matches.as_slice() {
+            [only] => Ok(only.path.clone()),
+            [] => anyhow::bail!("No recent project matches '{}'", query),
+            _ => {
+                let names: Vec<&str> = matches.iter().map(|r| r.name.as_str()).collect();
+                anyhow::bail!("'{}' matches several projects: {}", query, names.join(", "))
+            }
+        };
+    }
+
+    let items: Vec<String> = recent
+        .iter()
+        .map(|r| {
+            let running = running_count(&state, r);
+            let running_str = if running > 0 {
+                format!("  ({} running)", running)
+            } else {
+                String::new()
+            };
+            format!("{}  {}{}", r.name, style(r.path.display()).dim(), running_str)
+        })
+        .collect();
+
+    let selection = FuzzySelect::with_theme(&theme())
+        .with_prompt("Select a project")
+        .items(&items)
+        .default(0)
+        .interact_on(&Term::stderr())?;
+
+    Ok(recent[selection].path.clone())
+}
+
+fn running_count(state: &State, recent: &RecentProject) -> usize {
+    state
+        .get_project(&recent.name)
+        .filter(|p| p.path == recent.path)
+        .map(|p| p.services.len())
+        .unwrap_or(0)
+}
@@ -0,0 +1,229 @@
+use anyhow::Result;
+use console::{style, Term};
+use dialoguer::Select;
+use std::path::Path;
+
+use crate::backend::backend;
+use crate::discovery::{discover_services, Service};
+use crate::output::{self, Level, OutputMode};
+use crate::select::theme;
+use crate::config::ProjectConfig;
+use crate::state::{find_mismatches, find_orphans, migrate_renames, Mismatch, ServiceState, Session, State};
+
+/// Explain where state disagrees with running processes and let the user fix each case.
+///
+/// Services whose directory moved are carried over to their new name first. Stale entries
+/// for exited processes are forgotten without asking. Other mismatches prompt for an action
+/// in a terminal and are only reported otherwise. State is saved if anything changed.
+pub fn reconcile(
+    state: &mut State,
+    project_name: &str,
+    project_path: &Path,
+    services: &[Service],
+    include_untracked: bool,
+) -> Result<()> {
+    let renames = migrate_renames(state, project_name, project_path, services);
+    if !renames.is_empty() {
+        let config = ProjectConfig::load(project_path).unwrap_or_default();
+        for rename in &renames {
+            output::step(format!(
+                "{} moved to {}; its logs and history moved with it",
+                style(&rename.from).cyan(),
+                style(&rename.to).cyan()
+            ));
+            let referenced = config.service(&rename.from).is_some()
+                || config.dev.auto_start.contains(&rename.from)
+                || config.services.values().any(|c| c.depends_on.contains(&rename.from));
+            if referenced {
+                output::item(
+                    Level::Warning,
+                    format!("groo.toml still refers to {}: update it to {}", rename.from, rename.to),
+                );
+            }
+        }
+        state.save()?;
+        output::blank();
+    }
+
+    let mismatches = find_mismatches(state, project_name, services, include_untracked, backend());
+    if mismatches.is_empty() {
+        return Ok(());
+    }
+
+    let interactive = Term::stderr().is_term();
+    let mut changed = false;
+    // `groo dev` is still probing these; not listening yet is expected
+    let starting = Session::latest_for_project(project_name)
+        .map(|session| session.starting_services())
+        .unwrap_or_default();
+
+    for mismatch in &mismatches {
+        let name = mismatch.service();
+        match mismatch {
+            Mismatch::Exited { pid, .. } => {
+                output::warn(format!(
+                    "{} was tracked as running (pid {}) but has exited. Forgot it.",
+                    style(name).cyan(),
+                    pid
+                ));
+                state.remove_service(project_name, name);
+                changed = true;
+            }
+            Mismatch::NotListening { .. } if starting.iter().any(|s| s == name) => {}
+            Mismatch::NotListening { pid, port, .. } => {
+                output::warn(format!(
+                    "{} is running (pid {}) but nothing listens on :{}.",
+                    style(name).cyan(),
+                    pid,
+                    port
+                ));
+                if !interactive {
+                    continue;
+                }
+                match choose(&["Keep tracking it (it may still be starting)", "Forget it", "Kill it"])? {
+                    1 => {
+                        state.remove_service(project_name, name);
+                        changed = true;
+                    }
+                    2 => {
+                        report_kill(name, backend().kill(*pid));
+                        state.remove_service(project_name, name);
+                        changed = true;
+                    }
+                    _ => {}
+                }
+            }
+            Mismatch::Untracked { port, pids, .. } => {
+                let pid_list = pids.iter().map(|p| p.to_string()).collect::<Vec<_>>().join(", ");
+                output::warn(format!(
+                    "{}: :{} is in use by a process groo isn't tracking{}.",
+                    style(name).cyan(),
+                    port,
+                    if pid_list.is_empty() { String::new() } else { format!(" (pid {})", pid_list) }
+                ));
+                if !interactive || pids.is_empty() {
+                    continue;
+                }
+                match choose(&["Leave it", "Adopt it (track it as this service)", "Kill it"])? {
+                    1 => {
+                        let service = services.iter().find(|s| s.name == name);
+                        state.add_service(
+                            project_name,
+                            project_path.to_path_buf(),
+                            name,
+                            ServiceState {
+                                pid: pids[0],
+                                port: Some(*port),
+                                scheme: service.map(|s| s.scheme).unwrap_or_default(),
+                                base_path: service.and_then(|s| s.base_path.clone()),
+                            },
+                        );
+                        changed = true;
+                    }
+                    2 => {
+                        let mut killed = false;
+                        for &pid in pids {
+                            killed |= backend().kill(pid);
+                        }
+                        report_kill(name, killed);
+                        state.remove_service(project_name, name);
+                        changed = true;
+                    }
+                    _ => {}
+                }
+            }
+        }
+    }
+
+    if changed {
+        state.save()?;
+    }
+    output::blank();
+    Ok(())
+}
+
+/// Look for services a crashed groo left running without state knowing about them, and
+/// offer to adopt or stop each one.
+///
+/// Only runs at a terminal with human output, since it asks, and never fails the command
+/// it runs before. Once the user has decided, or when a crashed session left nothing
+/// running, the session is closed so it isn't brought up again.
+pub fn audit_orphans() {
+    if output::mode() != OutputMode::Human || !Term::stdout().is_term() || !Term::stderr().is_term() {
+        return;
+    }
+    let mut state = State::load().unwrap_or_default();
+    let crashed = find_orphans(&state, backend());
+    let asked = crashed.iter().any(|c| !c.orphans.is_empty());
+
+    let mut changed = false;
+    for mut crashed in crashed {
+        let session = &crashed.session;
+        for orphan in &crashed.orphans {
+            let port = orphan.port.map(|p| format!(" on :{}", p)).unwrap_or_default();
+            output::warn(format!(
+                "{} ({}) is still running{} (pid {}), left behind by a groo session that didn't shut down.",
+                style(&orphan.service).cyan(),
+                session.project,
+                port,
+                orphan.pid
+            ));
+            // A PID that may have been reused since isn't safe to kill
+            let actions: &[&str] = if orphan.verified {
+                &["Leave it", "Adopt it (track it as this service)", "Kill it"]
+            } else {
+                &["Leave it", "Adopt it (track it as this service)"]
+            };
+            match choose(actions).unwrap_or(0) {
+                1 => {
+                    let services = discover_services(&session.path).unwrap_or_default();
+                    let service = services.iter().find(|s| s.name == orphan.service);
+                    state.add_service(
+                        &session.project,
+                        session.path.clone(),
+                        &orphan.service,
+                        ServiceState {
+                            pid: orphan.pid,
+                            port: orphan.port,
+                            scheme: service.map(|s| s.scheme).unwrap_or_default(),
+                            base_path: service.and_then(|s| s.base_path.clone()),
+                        },
+                    );
+                    changed = true;
+                }
+                2 => {
+                    backend().kill_tree(orphan.pid);
+                    report_kill(&orphan.service, backend().kill(orphan.pid));
+                }
+                _ => {}
+            }
+        }
+        crashed.session.finish();
+        if let Err(e) = crashed.session.save() {
+            output::warn(format!("Failed to close the crashed session: {}", e));
+        }
+    }
+
+    if changed && let Err(e) = state.save() {
+        output::warn(format!("Failed to save state: {}", e));
+    }
+    if asked {
+        output::blank();
+    }
+}
+
+fn choose(actions: &[&str]) -> Result<usize> {
+    Ok(Select::with_theme(&theme())
+        .with_prompt("What now?")
+        .items(actions)
+        .default(0)
+        .interact_on(&Term::stderr())?)
+}
+
+fn report_kill(name: &str, killed: bool) {
+    if killed {
+        output::item(Level::Success, format!("Stopped {}", name));
+    } else {
+        output::item(Level::Error, format!("Failed to stop {}", name));
+    }
+}
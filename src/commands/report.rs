@@ -0,0 +1,142 @@
+use anyhow::Result;
+use console::style;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use crate::discovery::{find_git_root, get_project_name};
+use crate::format;
+use crate::output;
+use crate::state::Session;
+
+#[derive(Debug, Default, Serialize)]
+struct ServiceUsage {
+    project: String,
+    service: String,
+    starts: usize,
+    avg_startup_ms: Option<u64>,
+    crashes: usize,
+    #[serde(skip)]
+    startup_total_ms: u64,
+    #[serde(skip)]
+    startup_samples: u64,
+}
+
+#[derive(Debug, Serialize)]
+struct UsageReport {
+    scope: String,
+    sessions: usize,
+    services: Vec<ServiceUsage>,
+}
+
+/// Summarize local session metadata into a shareable file. Never touches the network.
+pub fn run(project: Option<String>, all: bool, json: bool, file: Option<PathBuf>) -> Result<()> {
+    let project_name = if all {
+        None
+    } else {
+        match project {
+            Some(p) => Some(p),
+            None => Some(get_project_name(&find_git_root()?)),
+        }
+    };
+
+    let sessions: Vec<Session> = Session::load_all()
+        .into_iter()
+        .filter(|s| project_name.as_ref().is_none_or(|p| &s.project == p))
+        .collect();
+
+    if sessions.is_empty() {
+        output::warn("No recorded sessions yet. Run groo dev first.");
+        return Ok(());
+    }
+
+    let report = build_report(
+        project_name.unwrap_or_else(|| "all projects".to_string()),
+        &sessions,
+    );
+
+    let path = file.unwrap_or_else(|| {
+        PathBuf::from(if json { "groo-report.json" } else { "groo-report.md" })
+    });
+    let content = if json {
+        serde_json::to_string_pretty(&report)?
+    } else {
+        render_markdown(&report)
+    };
+    std::fs::write(&path, content)?;
+
+    output::success(format!(
+        "Wrote report for {} session(s) to {}",
+        report.sessions,
+        style(path.display()).cyan()
+    ));
+
+    Ok(())
+}
+
+fn build_report(scope: String, sessions: &[Session]) -> UsageReport {
+    let mut usage: HashMap<(String, String), ServiceUsage> = HashMap::new();
+
+    for session in sessions {
+        for run in &session.services {
+            let entry = usage
+                .entry((session.project.clone(), run.name.clone()))
+                .or_insert_with(|| ServiceUsage {
+                    project: session.project.clone(),
+                    service: run.name.clone(),
+                    ..Default::default()
+                });
+            entry.starts += 1;
+            if run.crashed {
+                entry.crashes += 1;
+            }
+            if let Some(ms) = run.ready_after_ms {
+                entry.startup_total_ms += ms;
+                entry.startup_samples += 1;
+            }
+        }
+    }
+
+    let mut services: Vec<ServiceUsage> = usage
+        .into_values()
+        .map(|mut u| {
+            u.avg_startup_ms = u.startup_total_ms.checked_div(u.startup_samples);
+            u
+        })
+        .collect();
+
+    // Most-started first, then by name for a stable order
+    services.sort_by(|a, b| {
+        b.starts
+            .cmp(&a.starts)
+            .then_with(|| a.project.cmp(&b.project))
+            .then_with(|| a.service.cmp(&b.service))
+    });
+
+    UsageReport {
+        scope,
+        sessions: sessions.len(),
+        services,
+    }
+}
+
+fn render_markdown(report: &UsageReport) -> String {
+    let mut out = String::new();
+    out.push_str("# groo usage report\n\n");
+    out.push_str(&format!("- Scope: {}\n", report.scope));
+    out.push_str(&format!("- Sessions: {}\n\n", report.sessions));
+    out.push_str("| Project | Service | Starts | Avg startup | Crashes |\n");
+    out.push_str("|---------|---------|--------|-------------|---------|\n");
+    for u in &report.services {
+        let startup = u
+            .avg_startup_ms
+            .map(|ms| format::precise(Duration::from_millis(ms)))
+            .unwrap_or_else(|| "-".to_string());
+        out.push_str(&format!(
+            "| {} | {} | {} | {} | {} |\n",
+            u.project, u.service, u.starts, startup, u.crashes
+        ));
+    }
+    out
+}
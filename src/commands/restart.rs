@@ -1,114 +1,33 @@
 use anyhow::Result;
-use console::{style, Style, Term};
-use dialoguer::{theme::ColorfulTheme, MultiSelect};
-use tokio::sync::broadcast;
+use console::style;
 
-use crate::config::get_service_log_file;
+use crate::backend::backend;
+use crate::commands::dev::{launch, Cleanup};
+use crate::config::ProjectConfig;
 use crate::discovery::{discover_services, find_git_root, get_project_name, Service};
-use crate::runner::{get_color_for_index, spawn_service, wait_for_processes, ProcessHandle};
-use crate::state::{is_port_in_use, State};
+use crate::output::{self, Level};
+use crate::runner::Overrides;
+use crate::select::{Filter, Selector};
+use crate::state::{Session, State};
 
-fn create_theme() -> ColorfulTheme {
-    ColorfulTheme {
-        defaults_style: Style::new().dim(),
-        prompt_style: Style::new().bold(),
-        prompt_prefix: style("?".to_string()).green().bold(),
-        success_prefix: style("✓".to_string()).green().bold(),
-        error_prefix: style("✗".to_string()).red().bold(),
-        checked_item_prefix: style("  ◉".to_string()).green(),
-        unchecked_item_prefix: style("  ○".to_string()).dim(),
-        active_item_style: Style::new().cyan().bold(),
-        inactive_item_style: Style::new().dim(),
-        active_item_prefix: style("❯".to_string()).cyan().bold(),
-        ..ColorfulTheme::default()
-    }
-}
-
-pub async fn run() -> Result<()> {
+pub async fn run(crashed: bool, service_queries: Vec<String>) -> Result<()> {
     let git_root = find_git_root()?;
     let project_name = get_project_name(&git_root);
+    let config = ProjectConfig::load(&git_root)?;
     let services = discover_services(&git_root)?;
 
-    // Filter to only running services (port-based detection)
-    let running_service_list: Vec<&Service> = services
-        .iter()
-        .filter(|s| s.port.map(is_port_in_use).unwrap_or(false))
-        .collect();
-
-    if running_service_list.is_empty() {
-        println!(
-            "{} No running services found. Use {} to start services.",
-            style("!").yellow(),
-            style("groo dev").cyan()
-        );
-        return Ok(());
-    }
-
-    // Find max name length for alignment
-    let max_name_len = running_service_list.iter().map(|s| s.name.len()).max().unwrap_or(0);
-
-    // Display running services for selection
-    let items: Vec<String> = running_service_list
-        .iter()
-        .map(|s| {
-            let port_str = s.port
-                .map(|p| format!("{}", p))
-                .unwrap_or_else(|| "-".to_string());
-            format!(
-                "{:<width$}  {}",
-                s.name,
-                style(port_str).dim(),
-                width = max_name_len
-            )
-        })
-        .collect();
-
-    // All selected by default
-    let defaults: Vec<bool> = vec![true; running_service_list.len()];
-
-    let theme = create_theme();
-    let selections = MultiSelect::with_theme(&theme)
-        .with_prompt("Select services to restart")
-        .items(&items)
-        .defaults(&defaults)
-        .interact_on(&Term::stderr())?;
-
-    if selections.is_empty() {
-        println!("{}", style("No services selected.").yellow());
-        return Ok(());
-    }
-
-    let selected_services: Vec<_> = selections
-        .iter()
-        .map(|&i| running_service_list[i])
-        .collect();
-
-    // Stop selected services
-    println!(
-        "\n{} Stopping {} service(s)...\n",
-        style("→").yellow().bold(),
-        selected_services.len()
-    );
-
-    for service in &selected_services {
-        if let Some(port) = service.port {
-            if let Some(pid) = get_pid_by_port(port) {
-                if kill_process(pid) {
-                    println!(
-                        "  {} Stopped {}",
-                        style("✓").green(),
-                        service.name
-                    );
-                } else {
-                    println!(
-                        "  {} Failed to stop {}",
-                        style("✗").red(),
-                        service.name
-                    );
-                }
-            }
-        }
-    }
+    let selected_services = if crashed {
+        let Some(crashed_services) = select_crashed(&services, &project_name) else {
+            return Ok(());
+        };
+        crashed_services
+    } else {
+        let Some(running_services) = select_running(&services, &service_queries)? else {
+            return Ok(());
+        };
+        stop_services(&running_services);
+        running_services
+    };
 
     // Clean state
     let mut state = State::load().unwrap_or_default();
@@ -118,138 +37,79 @@ pub async fn run() -> Result<()> {
     // Brief pause to allow ports to be released
     tokio::time::sleep(tokio::time::Duration::from_millis(500)).await;
 
-    // Start selected services
-    println!(
-        "\n{} Starting {} service(s)...\n",
-        style("→").green().bold(),
-        selected_services.len()
-    );
-
-    // Set up shutdown signal
-    let (shutdown_tx, _) = broadcast::channel::<()>(1);
-
-    // Set up Ctrl+C handler
-    let shutdown_tx_clone = shutdown_tx.clone();
-    tokio::spawn(async move {
-        tokio::signal::ctrl_c().await.ok();
-        println!("\n{} Shutting down...", style("→").yellow().bold());
-        let _ = shutdown_tx_clone.send(());
-    });
-
-    // Reload state
-    let mut state = State::load().unwrap_or_default();
+    let state = State::load().unwrap_or_default();
+    let selected = selected_services
+        .into_iter()
+        .map(|service| (service, Overrides::default()))
+        .collect();
+    launch(&git_root, &project_name, &config, state, selected, Cleanup::Services).await
+}
 
-    // Spawn all selected services
-    let mut handles: Vec<ProcessHandle> = Vec::new();
-    for (idx, service) in selected_services.iter().enumerate() {
-        let color = get_color_for_index(idx);
-        let log_file = get_service_log_file(&service.path);
+/// Pick services whose last run in the latest session exited on its own and that
+/// aren't listening again
+fn select_crashed<'a>(services: &'a [Service], project_name: &str) -> Option<Vec<&'a Service>> {
+    let exited = Session::latest_for_project(project_name)
+        .map(|session| session.exited_services())
+        .unwrap_or_default();
+
+    let crashed: Vec<&Service> = Selector::new(services, "")
+        .filter(Filter::Stopped)
+        .candidates()
+        .into_iter()
+        .filter(|s| exited.contains(&s.name))
+        .collect();
 
-        match spawn_service(
-            &service.name,
-            &service.path,
-            &service.dev_command,
-            color.clone(),
-            log_file,
-        )
-        .await
-        {
-            Ok(handle) => {
-                if let Some(pid) = handle.pid() {
-                    state.add_service(
-                        &project_name,
-                        git_root.clone(),
-                        &service.name,
-                        pid,
-                        service.port,
-                    );
-                }
-                handles.push(handle);
-            }
-            Err(e) => {
-                eprintln!(
-                    "{} Failed to start {}: {}",
-                    style("✗").red().bold(),
-                    service.name,
-                    e
-                );
-            }
-        }
+    if crashed.is_empty() {
+        output::success("No crashed services in the last session.");
+        return None;
     }
 
-    // Save state
-    state.save()?;
-
-    // Wait for all processes or shutdown
-    let shutdown_rx = shutdown_tx.subscribe();
-    wait_for_processes(handles, shutdown_rx).await;
-
-    // Clean up state on exit
-    let mut state = State::load().unwrap_or_default();
-    for service in &selected_services {
-        state.remove_service(&project_name, &service.name);
-    }
-    state.save()?;
-
-    Ok(())
+    output::warn(format!(
+        "Crashed in the last session: {}",
+        crashed.iter().map(|s| s.name.as_str()).collect::<Vec<_>>().join(", ")
+    ));
+    Some(crashed)
 }
 
-/// Get PID of process listening on a port using lsof
-#[cfg(unix)]
-fn get_pid_by_port(port: u16) -> Option<u32> {
-    use std::process::Command;
-    let output = Command::new("lsof")
-        .args(["-ti", &format!(":{}", port)])
-        .output()
-        .ok()?;
+/// Let the user pick which running services to restart
+fn select_running<'a>(services: &'a [Service], queries: &'a [String]) -> Result<Option<Vec<&'a Service>>> {
+    let selector = Selector::new(services, "Select services to restart")
+        .filter(Filter::Running)
+        .queries(queries);
 
-    if output.status.success() {
-        let stdout = String::from_utf8_lossy(&output.stdout);
-        // lsof can return multiple PIDs, take the first one
-        stdout.lines().next()?.trim().parse().ok()
-    } else {
-        None
+    if selector.candidates().is_empty() {
+        output::warn(format!(
+            "No running services found. Use {} to start services.",
+            style("groo dev").cyan()
+        ));
+        return Ok(None);
     }
-}
 
-#[cfg(not(unix))]
-fn get_pid_by_port(port: u16) -> Option<u32> {
-    use std::process::Command;
-    let output = Command::new("netstat")
-        .args(["-ano"])
-        .output()
-        .ok()?;
+    let selected = selector.select()?;
+    Ok((!selected.is_empty()).then_some(selected))
+}
 
-    if output.status.success() {
-        let stdout = String::from_utf8_lossy(&output.stdout);
-        for line in stdout.lines() {
-            if line.contains(&format!(":{}", port)) && line.contains("LISTENING") {
-                let parts: Vec<&str> = line.split_whitespace().collect();
-                if let Some(pid_str) = parts.last() {
-                    return pid_str.parse().ok();
-                }
-            }
+fn stop_services(services: &[&Service]) {
+    output::blank();
+    output::step(format!("Stopping {} service(s)...", services.len()));
+    output::blank();
+
+    for service in services {
+        let Some(port) = service.port else {
+            continue;
+        };
+        let pids = backend().pids_on_port(port);
+        if pids.is_empty() {
+            continue;
+        }
+        let mut killed = false;
+        for pid in pids {
+            killed |= backend().kill(pid);
+        }
+        if killed {
+            output::item(Level::Success, format!("Stopped {}", service.name));
+        } else {
+            output::item(Level::Error, format!("Failed to stop {}", service.name));
         }
     }
-    None
-}
-
-#[cfg(unix)]
-fn kill_process(pid: u32) -> bool {
-    use std::process::Command;
-    Command::new("kill")
-        .args(["-15", &pid.to_string()])
-        .output()
-        .map(|o| o.status.success())
-        .unwrap_or(false)
-}
-
-#[cfg(not(unix))]
-fn kill_process(pid: u32) -> bool {
-    use std::process::Command;
-    Command::new("taskkill")
-        .args(["/F", "/PID", &pid.to_string()])
-        .output()
-        .map(|o| o.status.success())
-        .unwrap_or(false)
 }
@@ -0,0 +1,386 @@
+use anyhow::Result;
+use console::style;
+use dialoguer::{Confirm, Input};
+use std::io::Write;
+use std::net::{TcpStream, ToSocketAddrs};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::time::Duration;
+
+use crate::config::{ProjectConfig, Requirements};
+use crate::discovery::{discover_services, find_git_root, get_project_name, load_env_files, Service};
+use crate::output::{self, Level};
+use crate::select::theme;
+
+/// Something `groo setup` knows how to repair
+enum Fix {
+    InstallDependencies(PathBuf),
+    SetEnv { service_dir: PathBuf, key: String },
+    #[cfg(target_os = "macos")]
+    StartDocker,
+}
+
+struct Check {
+    label: String,
+    passed: bool,
+    detail: Option<String>,
+    fix: Option<Fix>,
+}
+
+impl Check {
+    fn pass(label: String) -> Self {
+        Self { label, passed: true, detail: None, fix: None }
+    }
+
+    fn fail(label: String, detail: impl Into<String>, fix: Option<Fix>) -> Self {
+        Self { label, passed: false, detail: Some(detail.into()), fix }
+    }
+}
+
+pub fn run(yes: bool) -> Result<()> {
+    let git_root = find_git_root()?;
+    let project_name = get_project_name(&git_root);
+    let config = ProjectConfig::load(&git_root)?;
+    let services = discover_services(&git_root)?;
+
+    if services.is_empty() {
+        output::warn("No services with dev scripts found.");
+        return Ok(());
+    }
+
+    output::line(style(&project_name).cyan().bold());
+    output::blank();
+
+    let mut fixes: Vec<(String, Fix)> = Vec::new();
+    let mut failures = 0;
+
+    for (service, checks) in services.iter().zip(run_checks(&git_root, &config, &services)) {
+        output::line(format!("  {}", style(&service.name).bold()));
+        for check in checks {
+            if check.passed {
+                output::subitem(Level::Success, check.label);
+            } else {
+                failures += 1;
+                output::subitem(
+                    Level::Error,
+                    format!("{} {}", check.label, style(check.detail.unwrap_or_default()).dim()),
+                );
+                if let Some(fix) = check.fix {
+                    fixes.push((service.name.clone(), fix));
+                }
+            }
+        }
+    }
+
+    output::blank();
+
+    if failures == 0 {
+        output::success("All prerequisites met.");
+        return Ok(());
+    }
+
+    let mut applied_install = false;
+    let mut applied_any = false;
+    for (service_name, fix) in fixes {
+        // A single install at the root covers every workspace package
+        if matches!(fix, Fix::InstallDependencies(_)) && applied_install {
+            continue;
+        }
+        let prompt = match &fix {
+            Fix::InstallDependencies(dir) => {
+                format!("Install dependencies in {}?", dir.display())
+            }
+            Fix::SetEnv { key, .. } => format!("Set {} for {}?", key, service_name),
+            #[cfg(target_os = "macos")]
+            Fix::StartDocker => "Start Docker Desktop?".to_string(),
+        };
+        // A value has to be typed in, which `--yes` runs are there to avoid
+        if yes && let Fix::SetEnv { key, .. } = &fix {
+            output::item(
+                Level::Warning,
+                format!("Set {} for {} by hand: its value can't be filled in with --yes", key, service_name),
+            );
+            continue;
+        }
+        let apply = yes || Confirm::with_theme(&theme()).with_prompt(prompt).default(true).interact()?;
+        if !apply {
+            continue;
+        }
+        if let Fix::InstallDependencies(_) = fix {
+            applied_install = true;
+        }
+        match apply_fix(fix) {
+            Ok(()) => applied_any = true,
+            Err(e) => output::item(Level::Error, e),
+        }
+    }
+
+    // Only a clean second pass counts: a fix can succeed and still not satisfy the check
+    if applied_any {
+        failures = run_checks(&git_root, &config, &services)
+            .iter()
+            .flatten()
+            .filter(|check| !check.passed)
+            .count();
+        if failures == 0 {
+            output::blank();
+            output::success("All prerequisites met.");
+            return Ok(());
+        }
+    }
+
+    output::blank();
+    anyhow::bail!("{} check(s) failed. Re-run `groo setup` to verify.", failures)
+}
+
+/// Every check for each service, in the order of `services`
+fn run_checks(git_root: &Path, config: &ProjectConfig, services: &[Service]) -> Vec<Vec<Check>> {
+    // Tool checks are the same for every service, so run them once
+    let node_version = detect_node_version();
+    let needs_docker = services
+        .iter()
+        .any(|s| config.service(&s.name).is_some_and(|c| c.requires.docker));
+    let docker_running = needs_docker && is_docker_running();
+
+    services
+        .iter()
+        .map(|service| {
+            let default_requirements = Requirements::default();
+            let requires = config
+                .service(&service.name)
+                .map(|c| &c.requires)
+                .unwrap_or(&default_requirements);
+
+            let mut checks = vec![check_dependencies(git_root, &service.path)];
+
+            if let Some(required) = &requires.node {
+                checks.push(check_node(node_version.as_deref(), required));
+            }
+            for key in &requires.env {
+                checks.push(check_env(&service.path, key));
+            }
+            if requires.docker {
+                checks.push(check_docker(docker_running));
+            }
+            for address in &requires.reachable {
+                checks.push(check_reachable(address));
+            }
+            checks
+        })
+        .collect()
+}
+
+fn apply_fix(fix: Fix) -> Result<()> {
+    match fix {
+        Fix::InstallDependencies(dir) => {
+            let status = Command::new("npm").arg("install").current_dir(&dir).status()?;
+            if !status.success() {
+                anyhow::bail!("npm install failed with status: {}", status);
+            }
+            output::item(Level::Success, "Installed dependencies");
+        }
+        Fix::SetEnv { service_dir, key } => {
+            let value: String = Input::with_theme(&theme())
+                .with_prompt(format!("Value for {}", key))
+                .validate_with(|value: &String| {
+                    if value.contains(['\n', '\r']) {
+                        Err("Values can't span lines")
+                    } else {
+                        Ok(())
+                    }
+                })
+                .interact_text()?;
+            let env_file = service_dir.join(".env.local");
+            append_env_entry(&env_file, &key, &value)?;
+            output::item(Level::Success, format!("Added {} to {}", key, env_file.display()));
+        }
+        #[cfg(target_os = "macos")]
+        Fix::StartDocker => {
+            Command::new("open").args(["-a", "Docker"]).status()?;
+            output::item(Level::Step, "Starting Docker Desktop...");
+        }
+    }
+    Ok(())
+}
+
+/// Add `KEY="value"` to an env file, on a line of its own even if the file's last line has
+/// no newline. Quoted so `#` and surrounding spaces are kept as part of the value.
+fn append_env_entry(env_file: &Path, key: &str, value: &str) -> Result<()> {
+    let needs_newline = std::fs::read(env_file)
+        .is_ok_and(|content| content.last().is_some_and(|&byte| byte != b'\n'));
+    let mut file = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(env_file)?;
+    if needs_newline {
+        writeln!(file)?;
+    }
+    writeln!(file, "{}=\"{}\"", key, value)?;
+    Ok(())
+}
+
+fn check_dependencies(git_root: &Path, service_dir: &Path) -> Check {
+    let label = "dependencies installed".to_string();
+    if service_dir.join("node_modules").exists() || git_root.join("node_modules").exists() {
+        Check::pass(label)
+    } else {
+        Check::fail(
+            label,
+            "(node_modules missing)",
+            Some(Fix::InstallDependencies(git_root.to_path_buf())),
+        )
+    }
+}
+
+fn check_node(installed: Option<&str>, required: &str) -> Check {
+    let label = format!("node {}", required);
+    match installed {
+        None => Check::fail(label, "(node not found)", None),
+        Some(version) if version_satisfies(version, required) => {
+            Check::pass(format!("node {} (requires {})", version, required))
+        }
+        Some(version) => Check::fail(label, format!("(found {})", version), None),
+    }
+}
+
+fn check_env(service_dir: &Path, key: &str) -> Check {
+    let label = format!("env {}", key);
+    let in_shell = std::env::var_os(key).is_some();
+    let in_files = load_env_files(service_dir).get(key).is_some_and(|v| !v.is_empty());
+
+    if in_shell || in_files {
+        Check::pass(label)
+    } else {
+        Check::fail(
+            label,
+            "(not set)",
+            Some(Fix::SetEnv {
+                service_dir: service_dir.to_path_buf(),
+                key: key.to_string(),
+            }),
+        )
+    }
+}
+
+fn check_docker(running: bool) -> Check {
+    let label = "docker running".to_string();
+    if running {
+        return Check::pass(label);
+    }
+    #[cfg(target_os = "macos")]
+    let fix = Some(Fix::StartDocker);
+    #[cfg(not(target_os = "macos"))]
+    let fix = None;
+    Check::fail(label, "(docker info failed)", fix)
+}
+
+fn check_reachable(address: &str) -> Check {
+    let label = format!("{} reachable", address);
+    let reachable = address
+        .to_socket_addrs()
+        .map(|mut addrs| {
+            addrs.any(|addr| TcpStream::connect_timeout(&addr, Duration::from_secs(1)).is_ok())
+        })
+        .unwrap_or(false);
+    if reachable {
+        Check::pass(label)
+    } else {
+        Check::fail(label, "(connection failed)", None)
+    }
+}
+
+fn detect_node_version() -> Option<String> {
+    let output = Command::new("node").arg("--version").output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    Some(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+fn is_docker_running() -> bool {
+    Command::new("docker")
+        .arg("info")
+        .output()
+        .map(|o| o.status.success())
+        .unwrap_or(false)
+}
+
+/// Check a version like `v20.11.1` against `20`, `20.11`, `>=18` or `>18.2`.
+/// A bare version matches by prefix; `>=`/`>` compare numerically.
+fn version_satisfies(version: &str, requirement: &str) -> bool {
+    let parse = |s: &str| -> Vec<u64> {
+        s.trim()
+            .trim_start_matches('v')
+            .split('.')
+            .map_while(|part| part.parse().ok())
+            .collect()
+    };
+    let installed = parse(version);
+    let requirement = requirement.trim();
+
+    // Compared over the requirement's length, with missing installed parts read as 0
+    let leading = |len: usize| -> Vec<u64> {
+        (0..len).map(|i| installed.get(i).copied().unwrap_or(0)).collect()
+    };
+
+    if let Some(min) = requirement.strip_prefix(">=") {
+        let min = parse(min);
+        leading(min.len()) >= min
+    } else if let Some(min) = requirement.strip_prefix('>') {
+        let min = parse(min);
+        leading(min.len()) > min
+    } else {
+        let wanted = parse(requirement);
+        installed.starts_with(&wanted)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bare_versions_match_by_prefix() {
+        assert!(version_satisfies("v20.11.1", "20"));
+        assert!(version_satisfies("v20.11.1", "20.11"));
+        assert!(!version_satisfies("v20.11.1", "20.1"));
+        assert!(!version_satisfies("v18.19.0", "20"));
+    }
+
+    #[test]
+    fn at_least_compares_numerically() {
+        assert!(version_satisfies("v20.11.1", ">=18"));
+        assert!(version_satisfies("v18.0.0", ">=18"));
+        assert!(!version_satisfies("v16.20.2", ">=18"));
+        assert!(version_satisfies("v18.10.0", ">=18.9"));
+    }
+
+    #[test]
+    fn greater_than_excludes_the_named_version() {
+        assert!(version_satisfies("v18.3.0", ">18.2"));
+        assert!(!version_satisfies("v18.2.5", ">18.2"));
+        assert!(!version_satisfies("v18.1.0", ">18.2"));
+    }
+
+    #[test]
+    fn requirement_longer_than_the_installed_version() {
+        assert!(version_satisfies("v20", ">=20.0"));
+        assert!(!version_satisfies("v20", ">=20.1"));
+        assert!(!version_satisfies("v20", "20.11"));
+    }
+
+    #[test]
+    fn env_entries_start_on_their_own_line() {
+        let dir = std::env::temp_dir().join(format!("groo-setup-test-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let env_file = dir.join(".env.local");
+        std::fs::write(&env_file, "A=1").unwrap();
+
+        append_env_entry(&env_file, "B", "two # words").unwrap();
+        append_env_entry(&env_file, "C", "3").unwrap();
+
+        let content = std::fs::read_to_string(&env_file).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(content, "A=1\nB=\"two # words\"\nC=\"3\"\n");
+    }
+}
@@ -0,0 +1,79 @@
+use anyhow::Result;
+use clap::ValueEnum;
+
+use crate::output;
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum Shell {
+    Bash,
+    Zsh,
+    Fish,
+}
+
+// A child process can't change its parent's working directory, so `groo cd` is
+// implemented as a shell function wrapping the binary and `groo path`.
+
+const POSIX_FUNCTION: &str = r#"groo() {
+  if [ "$1" = "cd" ]; then
+    shift
+    local __groo_dir
+    __groo_dir="$(command groo path "$@")" && cd "$__groo_dir"
+  else
+    command groo "$@"
+  fi
+}
+"#;
+
+const BASH_COMPLETION: &str = r#"_groo_complete() {
+  [ "${COMP_WORDS[1]}" = "cd" ] || return
+  local line="${COMP_LINE:0:$COMP_POINT}"
+  local cur="${line##* }"
+  COMPREPLY=( $(compgen -W "$(command groo path --list 2>/dev/null)" -- "$cur") )
+  # bash splits words on ':', so only complete the part after the last colon
+  if [[ "$cur" == *:* ]]; then
+    local prefix="${cur%"${cur##*:}"}"
+    COMPREPLY=( "${COMPREPLY[@]#"$prefix"}" )
+  fi
+}
+complete -F _groo_complete groo
+"#;
+
+const ZSH_COMPLETION: &str = r#"_groo_cd() {
+  if (( CURRENT == 3 )) && [[ ${words[2]} == cd ]]; then
+    local -a services
+    services=(${(f)"$(command groo path --list 2>/dev/null)"})
+    compadd -a services
+  fi
+}
+(( $+functions[compdef] )) && compdef _groo_cd groo
+"#;
+
+const FISH_SCRIPT: &str = r#"function groo
+    if test "$argv[1]" = cd
+        set -l dir (command groo path $argv[2..-1]); and cd $dir
+    else
+        command groo $argv
+    end
+end
+complete -c groo -n '__fish_use_subcommand' -f -a cd -d 'Change to a service directory'
+complete -c groo -n '__fish_seen_subcommand_from cd' -f -a '(command groo path --list 2>/dev/null)'
+"#;
+
+pub fn run(shell: Shell) -> Result<()> {
+    match shell {
+        Shell::Bash => output::document(&format!("{}{}", POSIX_FUNCTION, BASH_COMPLETION)),
+        Shell::Zsh => output::document(&format!("{}{}", POSIX_FUNCTION, ZSH_COMPLETION)),
+        Shell::Fish => output::document(FISH_SCRIPT),
+    }
+    Ok(())
+}
+
+/// Reached only when the shell function isn't installed
+pub fn cd_without_integration() -> Result<()> {
+    anyhow::bail!(
+        "groo cd needs shell integration. Add this to your shell config:\n\n  \
+         bash: eval \"$(groo shell-init bash)\"\n  \
+         zsh:  eval \"$(groo shell-init zsh)\"\n  \
+         fish: groo shell-init fish | source"
+    )
+}
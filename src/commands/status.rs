@@ -1,62 +1,439 @@
 use anyhow::Result;
+use clap::ValueEnum;
 use console::style;
+use serde::Serialize;
+use std::cmp::Reverse;
+use std::time::{Duration, SystemTime};
+use std::path::Path;
 
-use crate::discovery::{discover_services, find_git_root, get_project_name};
-use crate::state::is_port_in_use;
+use crate::backend::{backend, ProcessStats};
+use crate::commands::list::describe_head;
+use crate::commands::reconcile::reconcile;
+use crate::config::{ExternalDependency, ProjectConfig};
+use crate::discovery::{discover_services, find_git_root, get_git_branch, get_project_name, service_url, Service};
+use crate::format;
+use crate::output::{self, Stream};
+use crate::runner::is_reachable;
+use crate::state::{ExitEvent, LogManifest, Session, State};
+use crate::template::Template;
+
+/// Fields available to `groo status --format`
+const FORMAT_FIELDS: &[&str] = &[
+    "name", "port", "status", "path", "project", "branch", "uptime", "memory", "last_output", "url",
+];
+
+/// Column to order `groo status` by
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+pub enum SortKey {
+    Name,
+    Port,
+    Status,
+    /// Longest running first
+    Uptime,
+    /// Largest first
+    Memory,
+    /// Quietest first
+    LastOutput,
+}
+
+/// What a service is doing, most in need of attention first
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Health {
+    /// Kept crashing under `on_crash = "restart"` until groo stopped restarting it
+    CrashLooping,
+    /// Exited on its own in the latest session
+    Crashed,
+    /// The tracked process is alive but nothing listens on its port
+    Unhealthy,
+    /// Running, but silent for longer than its `stall_timeout`
+    Stalled,
+    /// The tracked process is alive and `groo dev` is still waiting for its port
+    Starting,
+    Running,
+    Stopped,
+}
+
+impl Health {
+    fn as_str(self) -> &'static str {
+        match self {
+            Health::CrashLooping => "crash-looping",
+            Health::Crashed => "crashed",
+            Health::Unhealthy => "unhealthy",
+            Health::Stalled => "stalled",
+            Health::Starting => "starting",
+            Health::Running => "running",
+            Health::Stopped => "stopped",
+        }
+    }
+
+    fn needs_attention(self) -> bool {
+        matches!(self, Health::CrashLooping | Health::Crashed | Health::Unhealthy | Health::Stalled)
+    }
+}
+
+struct Row<'a> {
+    service: &'a Service,
+    port: Option<u16>,
+    health: Health,
+    stats: ProcessStats,
+    /// Time since the service last wrote to its log
+    last_output: Option<Duration>,
+    /// Where the service is reachable, or was when it last came up
+    url: Option<String>,
+    /// What a crash-looping service printed before its last crash
+    crash_tail: Vec<String>,
+}
+
+/// A URL or `host:port` from `depends_on`, with the services that need it
+struct ExternalRow<'a> {
+    dependency: ExternalDependency,
+    up: bool,
+    used_by: Vec<&'a str>,
+}
+
+/// One external dependency in `groo status --output json`, after the service rows
+#[derive(Serialize)]
+struct ExternalStatus<'a> {
+    dependency: &'a str,
+    address: &'a str,
+    status: &'a str,
+    used_by: &'a [&'a str],
+}
+
+/// One row of `groo status --output json`
+#[derive(Serialize)]
+struct ServiceStatus<'a> {
+    name: &'a str,
+    port: Option<u16>,
+    status: &'a str,
+    path: String,
+    project: &'a str,
+    branch: Option<&'a str>,
+    uptime_secs: Option<u64>,
+    memory_bytes: Option<u64>,
+    last_output_secs: Option<u64>,
+    url: Option<&'a str>,
+    #[serde(skip_serializing_if = "<[_]>::is_empty")]
+    crash_tail: &'a [String],
+    /// Recent exits, oldest first; only with `--verbose`
+    #[serde(skip_serializing_if = "<[_]>::is_empty")]
+    exits: &'a [ExitEvent],
+}
+
+pub async fn run(
+    project: Option<String>,
+    format: Option<String>,
+    sort: Option<SortKey>,
+    verbose: bool,
+) -> Result<()> {
+    let template = format
+        .map(|f| Template::parse(&f, FORMAT_FIELDS))
+        .transpose()?;
 
-pub fn run(project: Option<String>) -> Result<()> {
     let git_root = find_git_root()?;
     let project_name = project.unwrap_or_else(|| get_project_name(&git_root));
 
     // Discover all services
     let services = discover_services(&git_root)?;
+    let config = ProjectConfig::load(&git_root).unwrap_or_default();
+
+    // Services started on other ports (e.g. ephemeral stacks) are tracked in state
+    let mut state = State::load().unwrap_or_default();
+    if template.is_none() && !output::is_json() {
+        reconcile(&mut state, &project_name, &git_root, &services, true)?;
+    }
+    let mut rows = collect_rows(&state, &project_name, &git_root, &services, &config);
+    sort_rows(&mut rows, sort);
+
+    if let Some(template) = template {
+        let branch = get_git_branch(&git_root).unwrap_or_default();
+        for row in &rows {
+            let line = template.render(|field| match field {
+                "name" => row.service.name.clone(),
+                "port" => row.port.map(|p| p.to_string()).unwrap_or_default(),
+                "status" => row.health.as_str().to_string(),
+                "path" => row.service.path.display().to_string(),
+                "project" => project_name.clone(),
+                "branch" => branch.clone(),
+                "uptime" => row.stats.uptime.map(format::duration).unwrap_or_default(),
+                "memory" => row.stats.memory.map(format::bytes).unwrap_or_default(),
+                "last_output" => row.last_output.map(format::ago).unwrap_or_default(),
+                "url" => row.url.clone().unwrap_or_default(),
+                _ => String::new(),
+            });
+            output::raw(Stream::Stdout, line);
+        }
+        return Ok(());
+    }
+
+    if output::is_json() {
+        let branch = get_git_branch(&git_root);
+        for row in &rows {
+            output::data(&ServiceStatus {
+                name: &row.service.name,
+                port: row.port,
+                status: row.health.as_str(),
+                path: row.service.path.display().to_string(),
+                project: &project_name,
+                branch: branch.as_deref(),
+                uptime_secs: row.stats.uptime.map(|u| u.as_secs()),
+                memory_bytes: row.stats.memory,
+                last_output_secs: row.last_output.map(|d| d.as_secs()),
+                url: row.url.as_deref(),
+                crash_tail: &row.crash_tail,
+                exits: if verbose { state.exit_history(&project_name, &row.service.name) } else { &[] },
+            });
+        }
+        for external in collect_externals(&config, &services).await {
+            output::data(&ExternalStatus {
+                dependency: &external.dependency.label,
+                address: &external.dependency.address,
+                status: if external.up { "up" } else { "down" },
+                used_by: &external.used_by,
+            });
+        }
+        return Ok(());
+    }
 
     if services.is_empty() {
-        println!(
-            "{} No services with dev scripts found in '{}'",
-            style("!").yellow(),
-            project_name
-        );
+        output::warn(format!("No services with dev scripts found in '{}'", project_name));
         return Ok(());
     }
 
     // Find max name length for alignment
     let max_name_len = services.iter().map(|s| s.name.len()).max().unwrap_or(0);
 
-    println!("{}", style(&project_name).cyan().bold());
-    println!();
+    output::line(format!(
+        "{}  {}",
+        style(&project_name).cyan().bold(),
+        style(describe_head(&git_root)).dim()
+    ));
+    output::blank();
 
     // Print header
-    println!(
-        "  {:<width$}  {:<6} {}",
+    output::line(format!(
+        "  {:<width$}  {:<6} {:<13} {:<8} {:<10} {}",
         style("Service").bold(),
         style("Port").bold(),
         style("Status").bold(),
+        style("Uptime").bold(),
+        style("Memory").bold(),
+        style("Last output").bold(),
         width = max_name_len
-    );
-    println!("  {}", "-".repeat(max_name_len + 20));
+    ));
+    output::line(format!("  {}", "-".repeat(max_name_len + 55)));
 
-    // Print all discovered services
-    for service in &services {
-        let port_str = service
+    for row in &rows {
+        let port_str = row
             .port
             .map(|p| p.to_string())
             .unwrap_or_else(|| "-".to_string());
 
-        // Check if this service is running (port-based)
-        let status = match service.port {
-            Some(port) if is_port_in_use(port) => style("Running").green(),
-            _ => style("Stopped").dim(),
+        let label = match row.health {
+            Health::CrashLooping => "Crash-looping",
+            Health::Crashed => "Crashed",
+            Health::Unhealthy => "Unhealthy",
+            Health::Stalled => "Stalled",
+            Health::Starting => "Starting",
+            Health::Running => "Running",
+            Health::Stopped => "Stopped",
+        };
+        // Pad before styling so ANSI codes don't throw off the alignment
+        let label = format!("{:<13}", label);
+        let status = match row.health {
+            Health::CrashLooping => style(label).red().bold(),
+            Health::Crashed => style(label).red(),
+            Health::Unhealthy => style(label).yellow(),
+            Health::Stalled => style(label).yellow(),
+            Health::Starting => style(label).cyan(),
+            Health::Running => style(label).green(),
+            Health::Stopped => style(label).dim(),
         };
 
-        println!(
-            "  {:<width$}  {:<6} {}",
-            service.name,
+        output::line(format!(
+            "  {:<width$}  {:<6} {} {:<8} {:<10} {}",
+            row.service.name,
             port_str,
             status,
+            row.stats.uptime.map(format::duration).unwrap_or_else(|| "-".to_string()),
+            row.stats.memory.map(format::bytes).unwrap_or_else(|| "-".to_string()),
+            row.last_output.map(format::ago).unwrap_or_else(|| "-".to_string()),
             width = max_name_len
-        );
+        ));
+
+        for line in &row.crash_tail {
+            output::line(style(format!("  {:<width$}  {}", "", line, width = max_name_len)).dim());
+        }
+
+        if verbose {
+            // Newest first, indented under the service
+            for exit in state.exit_history(&project_name, &row.service.name).iter().rev() {
+                output::line(style(format!(
+                    "  {:<width$}  {:<13} {} after {}",
+                    "",
+                    format::since(exit.at),
+                    exit.describe(),
+                    format::duration(Duration::from_millis(exit.uptime_ms)),
+                    width = max_name_len
+                )).dim());
+            }
+        }
+    }
+
+    let externals = collect_externals(&config, &services).await;
+    if !externals.is_empty() {
+        let max_label_len = externals.iter().map(|e| e.dependency.label.len()).max().unwrap_or(0);
+        output::blank();
+        output::line(format!(
+            "  {:<width$}  {:<10} {}",
+            style("External").bold(),
+            style("Status").bold(),
+            style("Used by").bold(),
+            width = max_label_len
+        ));
+        output::line(format!("  {}", "-".repeat(max_label_len + 22)));
+        for external in &externals {
+            let status = if external.up {
+                style(format!("{:<10}", "Up")).green()
+            } else {
+                style(format!("{:<10}", "Down")).red()
+            };
+            output::line(format!(
+                "  {:<width$}  {} {}",
+                external.dependency.label,
+                status,
+                external.used_by.join(", "),
+                width = max_label_len
+            ));
+        }
     }
 
     Ok(())
 }
+
+/// External dependencies of the project's services, probed in the order they're declared
+async fn collect_externals<'a>(config: &ProjectConfig, services: &'a [Service]) -> Vec<ExternalRow<'a>> {
+    let mut rows: Vec<ExternalRow> = Vec::new();
+    for service in services {
+        let Some(service_config) = config.service(&service.name) else {
+            continue;
+        };
+        for dependency in service_config.external_dependencies() {
+            match rows.iter_mut().find(|r| r.dependency == dependency) {
+                Some(row) => row.used_by.push(&service.name),
+                None => rows.push(ExternalRow {
+                    dependency,
+                    up: false,
+                    used_by: vec![&service.name],
+                }),
+            }
+        }
+    }
+    for row in &mut rows {
+        row.up = is_reachable(&row.dependency.address).await;
+    }
+    rows
+}
+
+fn collect_rows<'a>(
+    state: &State,
+    project_name: &str,
+    project_path: &Path,
+    services: &'a [Service],
+    config: &ProjectConfig,
+) -> Vec<Row<'a>> {
+    let tracked = state.get_project(project_name).map(|p| &p.services);
+    let session = Session::latest_for_project(project_name);
+    let crashed = session.as_ref().map(|s| s.exited_services()).unwrap_or_default();
+    let starting = session.as_ref().map(|s| s.starting_services()).unwrap_or_default();
+    let looping = session.as_ref().map(|s| s.crash_looping_runs()).unwrap_or_default();
+    let manifest = LogManifest::load();
+    let now = SystemTime::now();
+
+    services
+        .iter()
+        .map(|service| {
+            let entry = tracked.and_then(|t| t.get(&service.name));
+            let port = entry.and_then(|s| s.port).or(service.port);
+            let listeners = port.map(|p| backend().pids_on_port(p)).unwrap_or_default();
+
+            let mut health = if !listeners.is_empty() {
+                Health::Running
+            } else if entry.is_some_and(|e| e.port.is_some() && backend().is_pid_running(e.pid)) {
+                if starting.contains(&service.name) {
+                    Health::Starting
+                } else {
+                    Health::Unhealthy
+                }
+            } else if looping.iter().any(|r| r.name == service.name) {
+                Health::CrashLooping
+            } else if crashed.contains(&service.name) {
+                Health::Crashed
+            } else {
+                Health::Stopped
+            };
+
+            // The listener is the dev server itself; the tracked pid is often its `sh` wrapper
+            let pid = listeners.first().copied().or(entry.map(|e| e.pid));
+            let alive = matches!(health, Health::Running | Health::Unhealthy | Health::Starting);
+            let stats = if alive {
+                pid.and_then(|pid| backend().process_stats(pid)).unwrap_or_default()
+            } else {
+                ProcessStats::default()
+            };
+            // A watcher can hang with its port still bound; a quiet log is often the only sign
+            let last_output = alive
+                .then(|| manifest.last_written(project_path, &service.name))
+                .flatten()
+                .map(|written| now.duration_since(written).unwrap_or_default());
+            if health == Health::Running
+                && let (Some(quiet), Some(timeout)) = (last_output, config.stall_timeout(&service.name))
+                && quiet >= timeout
+            {
+                health = Health::Stalled;
+            }
+
+            let url = match (alive, port) {
+                (true, Some(port)) => Some(match entry {
+                    Some(entry) => service_url(entry.scheme, port, entry.base_path.as_deref()),
+                    None => service_url(service.scheme, port, service.base_path.as_deref()),
+                }),
+                _ => state.last_endpoint(project_name, &service.name).map(|e| e.url()),
+            };
+
+            let crash_tail = match health {
+                Health::CrashLooping => looping
+                    .iter()
+                    .find(|r| r.name == service.name)
+                    .map(|r| r.crash_tail.clone())
+                    .unwrap_or_default(),
+                _ => Vec::new(),
+            };
+
+            Row {
+                service,
+                port,
+                health,
+                stats,
+                last_output,
+                url,
+                crash_tail,
+            }
+        })
+        .collect()
+}
+
+/// Order rows by `sort`, or keep discovery order with crashed and unhealthy services first
+fn sort_rows(rows: &mut [Row], sort: Option<SortKey>) {
+    match sort {
+        None => rows.sort_by_key(|r| !r.health.needs_attention()),
+        Some(SortKey::Name) => rows.sort_by(|a, b| a.service.name.cmp(&b.service.name)),
+        // Services without a port go last
+        Some(SortKey::Port) => rows.sort_by_key(|r| (r.port.is_none(), r.port)),
+        Some(SortKey::Status) => rows.sort_by_key(|r| r.health),
+        Some(SortKey::Uptime) => rows.sort_by_key(|r| Reverse(r.stats.uptime)),
+        Some(SortKey::Memory) => rows.sort_by_key(|r| Reverse(r.stats.memory)),
+        // Services without any output go last
+        Some(SortKey::LastOutput) => rows.sort_by_key(|r| (r.last_output.is_none(), Reverse(r.last_output))),
+    }
+}
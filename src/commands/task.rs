@@ -0,0 +1,89 @@
+use anyhow::Result;
+use console::style;
+use tokio::process::Child;
+
+use crate::backend::backend;
+use crate::config::ProjectConfig;
+use crate::discovery::{discover_services, find_git_root, find_service, Service};
+use crate::output;
+use crate::runner::{get_color_for_index, spawn_script, Overrides};
+
+/// Run a task from groo.toml: every `<service>:<script>` step at once, with `groo dev`'s
+/// prefixed output
+pub async fn run(name: &str) -> Result<()> {
+    let git_root = find_git_root()?;
+    let config = ProjectConfig::load(&git_root)?;
+    let services = discover_services(&git_root)?;
+
+    let Some(steps) = config.tasks.get(name) else {
+        let mut defined: Vec<&str> = config.tasks.keys().map(|k| k.as_str()).collect();
+        defined.sort();
+        if defined.is_empty() {
+            anyhow::bail!("No tasks defined. Add them under [tasks] in groo.toml");
+        }
+        anyhow::bail!("No task '{}'. Defined tasks: {}", name, defined.join(", "));
+    };
+
+    // Resolve every step before starting any, so a typo doesn't leave half a task running
+    let mut resolved: Vec<(&Service, &str)> = Vec::new();
+    for step in steps {
+        let Some((query, script)) = step.rsplit_once(':') else {
+            anyhow::bail!("Task step '{}' should look like <service>:<script>", step);
+        };
+        resolved.push((find_service(&services, query)?, script));
+    }
+
+    output::step(format!("Running {} ({} step(s))...", style(name).bold(), resolved.len()));
+    output::blank();
+
+    let mut children: Vec<(String, Child)> = Vec::new();
+    for (idx, (service, script)) in resolved.iter().enumerate() {
+        let color = get_color_for_index(idx);
+        match spawn_script(service, script, &color, None, &Overrides::default()).await {
+            Ok(child) => children.push((format!("{}:{}", service.name, script), child)),
+            Err(e) => {
+                stop_steps(&step_pids(&children));
+                return Err(e);
+            }
+        }
+    }
+
+    let pids = step_pids(&children);
+    let wait_all = async {
+        let mut failed = Vec::new();
+        for (step, child) in children.iter_mut() {
+            let success = child.wait().await.map(|s| s.success()).unwrap_or(false);
+            if !success {
+                failed.push(step.clone());
+            }
+        }
+        failed
+    };
+
+    let failed = tokio::select! {
+        failed = wait_all => failed,
+        _ = tokio::signal::ctrl_c() => {
+            stop_steps(&pids);
+            anyhow::bail!("Task '{}' cancelled", name);
+        }
+    };
+
+    output::blank();
+    if !failed.is_empty() {
+        anyhow::bail!("{} of {} step(s) failed: {}", failed.len(), resolved.len(), failed.join(", "));
+    }
+    output::success(format!("Task {} finished.", name));
+    Ok(())
+}
+
+fn step_pids(children: &[(String, Child)]) -> Vec<u32> {
+    children.iter().filter_map(|(_, child)| child.id()).collect()
+}
+
+/// Stop started steps along with everything their scripts spawned, which killing the
+/// shell alone would leave running
+fn stop_steps(pids: &[u32]) {
+    for &pid in pids {
+        backend().kill_tree(pid);
+    }
+}
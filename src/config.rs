@@ -1,4 +1,11 @@
-use std::path::PathBuf;
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use std::time::Duration;
+
+use crate::discovery::Scheme;
 
 pub fn get_config_dir() -> PathBuf {
     dirs::config_dir()
@@ -10,39 +17,767 @@ pub fn get_config_dir() -> PathBuf {
         })
 }
 
+static NAMESPACE: OnceLock<String> = OnceLock::new();
+
+/// Isolate state, logs and sessions under a namespace for the rest of this run
+pub fn set_namespace(name: &str) {
+    let _ = NAMESPACE.set(name.to_string());
+}
+
+/// Active state namespace, set by `groo dev --ephemeral` or `GROO_NAMESPACE`
+pub fn namespace() -> Option<String> {
+    NAMESPACE
+        .get()
+        .cloned()
+        .or_else(|| std::env::var("GROO_NAMESPACE").ok())
+        .filter(|name| !name.is_empty())
+}
+
+/// Namespace `groo dev --ephemeral` makes up for itself when none is given
+pub fn ephemeral_namespace(pid: u32) -> String {
+    format!("ephemeral-{}", pid)
+}
+
+/// The groo process a made-up ephemeral namespace belongs to
+pub fn ephemeral_namespace_pid(name: &str) -> Option<u32> {
+    name.strip_prefix("ephemeral-")?.parse().ok()
+}
+
+/// Names of every namespace with a data dir
+pub fn namespaces() -> Vec<String> {
+    std::fs::read_dir(get_config_dir().join("namespaces"))
+        .into_iter()
+        .flatten()
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.path().is_dir())
+        .filter_map(|entry| entry.file_name().into_string().ok())
+        .collect()
+}
+
+/// Everything kept for a namespace: its data dir and, with `logs.local`, its logs
+pub fn namespace_dirs(name: &str) -> Vec<PathBuf> {
+    let mut dirs = vec![get_config_dir().join("namespaces").join(name)];
+    if let Some(root) = GlobalConfig::cached().logs.local.then(local_logs_root).flatten() {
+        dirs.push(root.join("namespaces").join(name));
+    }
+    dirs
+}
+
+/// Directory holding state, logs and sessions; shared settings stay in the config dir
+pub fn get_data_dir() -> PathBuf {
+    match namespace() {
+        Some(name) => get_config_dir().join("namespaces").join(name),
+        None => get_config_dir(),
+    }
+}
+
 pub fn get_state_file() -> PathBuf {
-    get_config_dir().join("state.json")
+    get_data_dir().join("state.json")
 }
 
 pub fn ensure_config_dir() -> std::io::Result<()> {
-    let config_dir = get_config_dir();
-    if !config_dir.exists() {
-        std::fs::create_dir_all(&config_dir)?;
+    let data_dir = get_data_dir();
+    if !data_dir.exists() {
+        create_private_dir(&data_dir)?;
     }
     Ok(())
 }
 
+/// Create a directory and its missing parents, accessible only to the current user. Logs
+/// and state hold service output and env, which other users of a shared machine shouldn't see.
+pub fn create_private_dir(path: &Path) -> std::io::Result<()> {
+    let mut builder = std::fs::DirBuilder::new();
+    builder.recursive(true);
+    #[cfg(unix)]
+    std::os::unix::fs::DirBuilderExt::mode(&mut builder, 0o700);
+    builder.create(path)
+}
+
+/// Options for opening files groo writes; files they create are readable only by the
+/// current user
+pub fn private_file() -> std::fs::OpenOptions {
+    let mut options = std::fs::OpenOptions::new();
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+    options
+}
+
+/// `std::fs::write` for groo's own files, creating them readable only by the current user
+pub fn write_private(path: &Path, contents: impl AsRef<[u8]>) -> std::io::Result<()> {
+    use std::io::Write;
+    private_file()
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .open(path)?
+        .write_all(contents.as_ref())
+}
+
+/// Directory holding service log files. With `logs.local` set they're kept under the
+/// system temp dir, for config dirs on network filesystems.
 pub fn get_logs_dir() -> PathBuf {
-    get_config_dir().join("logs")
+    let Some(root) = GlobalConfig::cached().logs.local.then(local_logs_root).flatten() else {
+        return get_data_dir().join("logs");
+    };
+    match namespace() {
+        Some(name) => root.join("namespaces").join(name).join("logs"),
+        None => root.join("logs"),
+    }
 }
 
-pub fn get_service_log_file(service_path: &std::path::Path) -> PathBuf {
-    use std::collections::hash_map::DefaultHasher;
-    use std::hash::{Hash, Hasher};
+static LOCAL_LOGS_ROOT: OnceLock<Option<PathBuf>> = OnceLock::new();
 
-    let mut hasher = DefaultHasher::new();
-    service_path.hash(&mut hasher);
-    let hash = format!("{:x}", hasher.finish());
-    let short_hash = &hash[..8.min(hash.len())];
+/// `groo-<user>` under the system temp dir, created private to the current user.
+///
+/// The temp dir is shared, so another user could have put a directory or symlink there
+/// first to read or redirect the logs. One groo didn't create for this user is refused,
+/// and logs stay in the data dir.
+fn local_logs_root() -> Option<PathBuf> {
+    LOCAL_LOGS_ROOT
+        .get_or_init(|| {
+            let root = std::env::temp_dir().join(format!("groo-{}", current_user()));
+            let result = create_private_dir(&root).and_then(|_| check_owned(&root));
+            if let Err(e) = &result {
+                crate::output::warn(format!(
+                    "Not keeping logs in {}: {}. Using {} instead.",
+                    root.display(),
+                    e,
+                    get_data_dir().join("logs").display()
+                ));
+            }
+            result.ok().map(|_| root)
+        })
+        .clone()
+}
 
-    get_logs_dir().join(format!("{}.log", short_hash))
+/// Name of the current user for per-user paths, falling back to the uid where `USER` isn't set
+fn current_user() -> String {
+    let user = std::env::var("USER").or_else(|_| std::env::var("USERNAME")).unwrap_or_default();
+    if !user.is_empty() {
+        return user;
+    }
+    #[cfg(unix)]
+    {
+        // SAFETY: geteuid can't fail
+        unsafe { libc::geteuid() }.to_string()
+    }
+    #[cfg(not(unix))]
+    {
+        "user".to_string()
+    }
+}
+
+/// Fail unless `path` is a real directory (not a symlink) owned by the current user
+fn check_owned(path: &Path) -> std::io::Result<()> {
+    let metadata = std::fs::symlink_metadata(path)?;
+    if !metadata.is_dir() {
+        return Err(std::io::Error::other("it's a symlink or not a directory"));
+    }
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::MetadataExt;
+        // SAFETY: geteuid can't fail
+        if metadata.uid() != unsafe { libc::geteuid() } {
+            return Err(std::io::Error::other("it belongs to another user"));
+        }
+    }
+    Ok(())
+}
+
+/// The manifest of log files stays in the data dir even when the logs themselves don't
+pub fn get_log_manifest_file() -> PathBuf {
+    get_data_dir().join("logs").join("manifest.json")
+}
+
+pub fn get_cache_dir() -> PathBuf {
+    get_config_dir().join("cache")
+}
+
+pub fn get_sessions_dir() -> PathBuf {
+    get_data_dir().join("sessions")
+}
+
+/// Bookmarks from `groo mark`, one file per project
+pub fn get_marks_dir() -> PathBuf {
+    get_data_dir().join("marks")
+}
+
+pub fn get_global_config_file() -> PathBuf {
+    get_config_dir().join("config.toml")
+}
+
+/// User-wide settings loaded from `~/.config/groo/config.toml`
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct GlobalConfig {
+    pub logs: LogsConfig,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(default)]
+pub struct LogsConfig {
+    /// Warn when the logs directory grows past this many megabytes
+    pub warn_size_mb: u64,
+    /// Write log files under the system temp dir instead of the config dir
+    pub local: bool,
+    /// Follow logs by reading the files rather than watching their size, which network
+    /// filesystems (NFS, SMB) cache and report late
+    pub poll: bool,
+    /// How often `groo logs -f` checks for new lines, in milliseconds
+    pub poll_interval_ms: Option<u64>,
+}
+
+impl Default for LogsConfig {
+    fn default() -> Self {
+        Self {
+            warn_size_mb: 500,
+            local: false,
+            poll: false,
+            poll_interval_ms: None,
+        }
+    }
+}
+
+impl LogsConfig {
+    /// Polling a network filesystem every 100ms mostly returns cached data, so `poll`
+    /// slows the default down
+    pub fn poll_interval(&self) -> std::time::Duration {
+        let default = if self.poll { 1000 } else { 100 };
+        std::time::Duration::from_millis(self.poll_interval_ms.unwrap_or(default))
+    }
+}
+
+impl GlobalConfig {
+    pub fn load() -> Result<Self> {
+        let path = get_global_config_file();
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let content = std::fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        toml::from_str(&content).with_context(|| format!("Invalid config in {}", path.display()))
+    }
+
+    /// Settings loaded once per run, falling back to defaults when the file is broken
+    /// (`groo doctor` reports why)
+    pub fn cached() -> &'static Self {
+        static CONFIG: OnceLock<GlobalConfig> = OnceLock::new();
+        CONFIG.get_or_init(|| Self::load().unwrap_or_default())
+    }
 }
 
 #[allow(dead_code)]
 pub fn ensure_logs_dir() -> std::io::Result<()> {
     let logs_dir = get_logs_dir();
     if !logs_dir.exists() {
-        std::fs::create_dir_all(&logs_dir)?;
+        create_private_dir(&logs_dir)?;
     }
     Ok(())
 }
+
+/// Name of the optional per-project config file at the repository root
+pub const PROJECT_CONFIG_FILE: &str = "groo.toml";
+
+/// Project-level configuration loaded from `groo.toml`
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct ProjectConfig {
+    /// Per-service settings keyed by service name (e.g. `"apps:web"`)
+    pub services: HashMap<String, ServiceConfig>,
+    pub hooks: HooksConfig,
+    pub git: GitConfig,
+    pub dev: DevConfig,
+    /// Named chores run by `groo task <name>`: `"<service>:<script>"` steps run in parallel
+    pub tasks: HashMap<String, Vec<String>>,
+    /// Project chores run as `groo <name>`, e.g. `groo db-reset`
+    pub commands: HashMap<String, CustomCommand>,
+}
+
+/// A project command from `[commands.<name>]`: an npm script or a shell command, run in a
+/// directory of the repository
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct CustomCommand {
+    /// Directory to run in, relative to the repository root. Defaults to the root.
+    pub run: Option<PathBuf>,
+    /// npm script to run there
+    pub script: Option<String>,
+    /// Shell command to run instead of an npm script
+    pub command: Option<String>,
+    /// One line shown when listing the project's commands
+    pub description: Option<String>,
+}
+
+/// Settings for `groo dev`
+#[derive(Debug, Deserialize)]
+#[serde(default)]
+pub struct DevConfig {
+    /// Which services the selector checks up front
+    pub preselect: Preselect,
+    /// Services checked by `preselect = "auto_start"`
+    pub auto_start: Vec<String>,
+    /// Tags whose services are checked by `preselect = "tags"`
+    pub preselect_tags: Vec<String>,
+    /// In which order services are stopped on Ctrl+C and by `groo stop`
+    pub shutdown_order: ShutdownOrder,
+    /// Start services with their framework's defaults for clean output, like
+    /// `FORCE_COLOR=1` and `NEXT_TELEMETRY_DISABLED=1`
+    pub framework_env: bool,
+    /// Variables every service starts with, over the framework defaults. An empty value
+    /// drops a default.
+    pub env: BTreeMap<String, String>,
+}
+
+impl Default for DevConfig {
+    fn default() -> Self {
+        Self {
+            preselect: Preselect::default(),
+            auto_start: Vec::new(),
+            preselect_tags: Vec::new(),
+            shutdown_order: ShutdownOrder::default(),
+            framework_env: true,
+            env: BTreeMap::new(),
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ShutdownOrder {
+    /// Services that others `depends_on` are stopped after them, each layer getting its
+    /// `stop_timeout` to exit first
+    #[default]
+    Dependents,
+    /// Everything at once
+    Parallel,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Preselect {
+    /// Start with nothing checked
+    None,
+    /// Every service with a detected port that isn't running yet
+    #[default]
+    NotRunning,
+    /// The services listed in `auto_start`
+    AutoStart,
+    /// The services started in the project's last session
+    LastUsed,
+    /// Services carrying one of `preselect_tags`
+    Tags,
+}
+
+/// How `groo dev` reacts to git activity in the project
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct GitConfig {
+    /// What to do with running services whose directories changed after a branch switch
+    pub on_branch_switch: BranchSwitchAction,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BranchSwitchAction {
+    /// Don't watch for branch switches
+    #[default]
+    Off,
+    /// Ask before restarting affected services
+    Prompt,
+    /// Restart affected services without asking
+    Restart,
+}
+
+/// Shell commands run on session lifecycle events.
+///
+/// Each command runs via `sh -c` from the project root with `GROO_*` env vars describing the event.
+#[derive(Debug, Default, Clone, Deserialize)]
+#[serde(default)]
+pub struct HooksConfig {
+    /// After all selected services have been spawned
+    pub on_start: Option<String>,
+    /// When a service's port starts accepting connections
+    pub on_ready: Option<String>,
+    /// When a service exits on its own with a failure status
+    pub on_crash: Option<String>,
+    /// When a service has been silent for its `stall_timeout`
+    pub on_stall: Option<String>,
+    /// When the session shuts down
+    pub on_stop: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct ServiceConfig {
+    /// Shell command to run instead of the package's `dev` script. With no package.json
+    /// at `path`, this declares a service of its own, e.g. one imported from a Procfile.
+    pub command: Option<String>,
+    /// Directory `command` runs in, relative to the repository root. Defaults to the root.
+    pub path: Option<PathBuf>,
+    /// Port the service listens on, when detection gets it wrong or can't see it
+    pub port: Option<u16>,
+    /// Environment variables the service always starts with
+    pub env: BTreeMap<String, String>,
+    pub requires: Requirements,
+    /// Force `http` or `https` when detection gets it wrong
+    pub scheme: Option<Scheme>,
+    /// Path prefix the app is served under, e.g. `/docs`
+    pub base_path: Option<String>,
+    /// Labels for selecting groups of services, e.g. `groo dev -s @frontend`
+    pub tags: Vec<String>,
+    /// Services this one talks to, e.g. `["apps:api"]`. URLs and `host:port` entries are
+    /// external dependencies, waited for before this service starts.
+    pub depends_on: Vec<String>,
+    /// What `groo dev` does when a dependency becomes healthy again after going down
+    pub on_dependency_restart: DependencyAction,
+    /// Signal sent by `on_dependency_restart = "reload"`, e.g. `"USR2"`. Defaults to `HUP`.
+    pub reload_signal: Option<String>,
+    /// Seconds to wait for the port before reporting the service as failed to start,
+    /// instead of the framework's default
+    pub ready_timeout: Option<u64>,
+    /// Seconds to let the service exit on shutdown before the services it depends on are
+    /// stopped. Defaults to 5.
+    pub stop_timeout: Option<u64>,
+    /// Seconds without output after which a running service is reported as stalled
+    pub stall_timeout: Option<u64>,
+    /// What `groo dev` does when the service stalls
+    pub on_stall: StallAction,
+    /// What `groo dev` does when the service exits with a failure status
+    pub on_crash: CrashAction,
+    /// Restarts `on_crash = "restart"` makes within `restart_window` before giving up on a
+    /// crash-looping service. Defaults to 5.
+    pub max_restarts: Option<u32>,
+    /// Seconds that `max_restarts` counts over. Defaults to 120.
+    pub restart_window: Option<u64>,
+    /// CPU and disk priority, e.g. `{ nice = 10, io = "idle" }` for a background build
+    pub priority: Priority,
+}
+
+/// Grace period on shutdown for services without a `stop_timeout`
+const DEFAULT_STOP_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Crash restarts allowed within the restart window for services without `max_restarts`
+const DEFAULT_MAX_RESTARTS: u32 = 5;
+
+/// Window crash restarts are counted over for services without a `restart_window`
+const DEFAULT_RESTART_WINDOW: Duration = Duration::from_secs(120);
+
+impl ServiceConfig {
+    /// `depends_on` entries that point outside the repository
+    pub fn external_dependencies(&self) -> Vec<ExternalDependency> {
+        self.depends_on
+            .iter()
+            .filter_map(|entry| ExternalDependency::parse(entry))
+            .collect()
+    }
+}
+
+/// A `depends_on` entry that isn't a service: a URL like `https://staging.example.com/api`
+/// or an address like `db.internal:5432`
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExternalDependency {
+    /// The entry as written in groo.toml
+    pub label: String,
+    /// `host:port` probed for TCP connections
+    pub address: String,
+}
+
+impl ExternalDependency {
+    pub fn parse(entry: &str) -> Option<Self> {
+        let (authority, default_port) = if let Some(rest) = entry.strip_prefix("https://") {
+            (rest, Some(443))
+        } else if let Some(rest) = entry.strip_prefix("http://") {
+            (rest, Some(80))
+        } else {
+            (entry, None)
+        };
+        let authority = authority.split(['/', '?', '#']).next().unwrap_or_default();
+
+        // Service names contain colons too (`apps:api`), but never end in a port number
+        let address = match authority.rsplit_once(':') {
+            Some((host, port)) if !host.is_empty() && port.parse::<u16>().is_ok() => authority.to_string(),
+            _ => match default_port {
+                Some(port) if !authority.is_empty() => format!("{}:{}", authority, port),
+                _ => return None,
+            },
+        };
+        Some(Self {
+            label: entry.to_string(),
+            address,
+        })
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DependencyAction {
+    #[default]
+    Off,
+    /// Restart this service
+    Restart,
+    /// Send `reload_signal` to the process listening on this service's port (Unix only)
+    Reload,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StallAction {
+    /// Print a warning and fire the `on_stall` hook
+    #[default]
+    Warn,
+    /// Warn, then restart the service
+    Restart,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CrashAction {
+    /// Leave the service stopped and fire the `on_crash` hook
+    #[default]
+    Stop,
+    /// Fire the hook, then start the service again, until it crashes `max_restarts` times
+    /// within `restart_window`
+    Restart,
+}
+
+/// OS scheduling priority a service starts with
+#[derive(Debug, Default, Clone, Copy, PartialEq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Priority {
+    /// From -20 (most CPU) to 19 (least), as with `nice`. Windows uses the nearest
+    /// priority class. Negative values usually need root.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub nice: Option<i32>,
+    /// Disk priority, as with `ionice` (Linux only)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub io: Option<IoPriority>,
+}
+
+impl Priority {
+    pub fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// `nice` within the range the OS accepts
+    pub fn nice(&self) -> Option<i32> {
+        self.nice.map(|nice| nice.clamp(-20, 19))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum IoPriority {
+    /// Only use the disk when nothing else does
+    Idle,
+    /// Lowest best-effort priority
+    Low,
+    Normal,
+}
+
+/// Prerequisites checked by `groo setup`
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct Requirements {
+    /// Node version requirement, e.g. `"20"` or `">=18.17"`
+    pub node: Option<String>,
+    /// Environment variables that must be set (in the shell or the service's .env files)
+    pub env: Vec<String>,
+    /// Whether the Docker daemon must be running
+    pub docker: bool,
+    /// `host:port` addresses that must accept TCP connections (databases, queues, ...)
+    pub reachable: Vec<String>,
+}
+
+impl ProjectConfig {
+    /// Load `groo.toml` from the project root, falling back to defaults if it doesn't exist
+    pub fn load(git_root: &Path) -> Result<Self> {
+        let path = git_root.join(PROJECT_CONFIG_FILE);
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let content = std::fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        toml::from_str(&content).with_context(|| format!("Invalid config in {}", path.display()))
+    }
+
+    pub fn service(&self, service_name: &str) -> Option<&ServiceConfig> {
+        self.services.get(service_name)
+    }
+
+    /// `stall_timeout` of a service, if it has one
+    pub fn stall_timeout(&self, service_name: &str) -> Option<Duration> {
+        self.service(service_name)
+            .and_then(|c| c.stall_timeout)
+            .map(Duration::from_secs)
+    }
+
+    /// How often a crashing service is restarted, and over how long, before it's treated as
+    /// crash-looping
+    pub fn restart_limit(&self, service_name: &str) -> (u32, Duration) {
+        let service = self.service(service_name);
+        (
+            service.and_then(|c| c.max_restarts).unwrap_or(DEFAULT_MAX_RESTARTS),
+            service
+                .and_then(|c| c.restart_window)
+                .map(Duration::from_secs)
+                .unwrap_or(DEFAULT_RESTART_WINDOW),
+        )
+    }
+
+    /// How long a service gets to exit on shutdown before its dependencies are stopped
+    pub fn stop_timeout(&self, service_name: &str) -> Duration {
+        self.service(service_name)
+            .and_then(|c| c.stop_timeout)
+            .map(Duration::from_secs)
+            .unwrap_or(DEFAULT_STOP_TIMEOUT)
+    }
+
+    /// Group services into the order they should be stopped in: first those no other
+    /// given service depends on, then what they depended on, and so on. With
+    /// `shutdown_order = "parallel"`, or for services in a dependency cycle, the rest
+    /// share a layer.
+    pub fn shutdown_layers<'a>(&self, service_names: &[&'a str]) -> Vec<Vec<&'a str>> {
+        if self.dev.shutdown_order == ShutdownOrder::Parallel {
+            return vec![service_names.to_vec()];
+        }
+        let mut remaining = service_names.to_vec();
+        let mut layers = Vec::new();
+        while !remaining.is_empty() {
+            let needed = |name: &str| {
+                remaining
+                    .iter()
+                    .filter_map(|other| self.service(other))
+                    .any(|c| c.depends_on.iter().any(|d| d == name))
+            };
+            let (layer, rest): (Vec<&str>, Vec<&str>) = remaining.iter().partition(|name| !needed(name));
+            if layer.is_empty() {
+                layers.push(rest);
+                break;
+            }
+            layers.push(layer);
+            remaining = rest;
+        }
+        layers
+    }
+
+    /// External dependencies of the given services, each listed once
+    pub fn external_dependencies<'a>(&self, service_names: impl IntoIterator<Item = &'a str>) -> Vec<ExternalDependency> {
+        let mut dependencies: Vec<ExternalDependency> = Vec::new();
+        for name in service_names {
+            let Some(service_config) = self.service(name) else {
+                continue;
+            };
+            for dependency in service_config.external_dependencies() {
+                if !dependencies.contains(&dependency) {
+                    dependencies.push(dependency);
+                }
+            }
+        }
+        dependencies
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn address(entry: &str) -> Option<String> {
+        ExternalDependency::parse(entry).map(|d| d.address)
+    }
+
+    #[test]
+    fn urls_are_probed_on_their_port_or_the_scheme_default() {
+        assert_eq!(address("https://staging.example.com/api"), Some("staging.example.com:443".to_string()));
+        assert_eq!(address("http://localhost?x=1"), Some("localhost:80".to_string()));
+        assert_eq!(address("http://localhost:8080/health"), Some("localhost:8080".to_string()));
+    }
+
+    #[test]
+    fn host_and_port_entries_are_probed_as_written() {
+        assert_eq!(address("db.internal:5432"), Some("db.internal:5432".to_string()));
+        assert_eq!(address("[::1]:6379"), Some("[::1]:6379".to_string()));
+        assert_eq!(
+            ExternalDependency::parse("db.internal:5432").map(|d| d.label),
+            Some("db.internal:5432".to_string())
+        );
+    }
+
+    #[test]
+    fn service_names_are_not_external() {
+        assert_eq!(address("apps:api"), None);
+        assert_eq!(address("api"), None);
+        assert_eq!(address(":5432"), None);
+        assert_eq!(address("db:99999"), None);
+        assert_eq!(address("https://"), None);
+    }
+
+    fn project(toml: &str) -> ProjectConfig {
+        toml::from_str(toml).unwrap()
+    }
+
+    #[test]
+    fn dependents_stop_before_what_they_depend_on() {
+        let config = project(
+            r#"
+            [services.web]
+            depends_on = ["api"]
+            [services.api]
+            depends_on = ["db"]
+            "#,
+        );
+        assert_eq!(config.shutdown_layers(&["db", "api", "web"]), vec![vec!["web"], vec!["api"], vec!["db"]]);
+    }
+
+    #[test]
+    fn independent_services_share_a_layer() {
+        let config = project(
+            r#"
+            [services.web]
+            depends_on = ["api"]
+            [services.admin]
+            depends_on = ["api"]
+            "#,
+        );
+        assert_eq!(config.shutdown_layers(&["api", "web", "admin"]), vec![vec!["web", "admin"], vec!["api"]]);
+    }
+
+    #[test]
+    fn services_in_a_cycle_stop_together_last() {
+        let config = project(
+            r#"
+            [services.a]
+            depends_on = ["b"]
+            [services.b]
+            depends_on = ["a"]
+            [services.c]
+            depends_on = ["a"]
+            "#,
+        );
+        assert_eq!(config.shutdown_layers(&["a", "b", "c"]), vec![vec!["c"], vec!["a", "b"]]);
+    }
+
+    #[test]
+    fn dependencies_outside_the_selection_are_ignored() {
+        let config = project(
+            r#"
+            [services.web]
+            depends_on = ["api", "db.internal:5432"]
+            "#,
+        );
+        assert_eq!(config.shutdown_layers(&["web", "worker"]), vec![vec!["web", "worker"]]);
+        assert!(config.shutdown_layers(&[]).is_empty());
+    }
+
+    #[test]
+    fn parallel_order_is_one_layer() {
+        let config = project(
+            r#"
+            [dev]
+            shutdown_order = "parallel"
+            [services.web]
+            depends_on = ["api"]
+            "#,
+        );
+        assert_eq!(config.shutdown_layers(&["api", "web"]), vec![vec!["api", "web"]]);
+    }
+}
@@ -0,0 +1,55 @@
+//! groo's own diagnostic log: what it decided and did (discovery, spawns, signals, state
+//! changes), kept in a small rotating file so odd behavior can be looked into afterwards
+//! with `groo doctor --show-log`.
+
+use std::fmt::Display;
+use std::io::Write;
+use std::path::PathBuf;
+
+use crate::config;
+use crate::runner::local_datetime;
+
+/// Size at which the log is rotated
+const MAX_LOG_SIZE: u64 = 1024 * 1024;
+
+/// Rotated files kept next to the current one: `groo.log.1`, `groo.log.2`
+const ROTATED_LOGS: usize = 2;
+
+pub fn log_file() -> PathBuf {
+    config::get_data_dir().join("groo.log")
+}
+
+/// The current log and its rotated predecessors, oldest first
+pub fn log_files() -> Vec<PathBuf> {
+    let current = log_file();
+    let mut files: Vec<PathBuf> = (1..=ROTATED_LOGS)
+        .rev()
+        .map(|n| current.with_extension(format!("log.{}", n)))
+        .collect();
+    files.push(current);
+    files
+}
+
+/// Append a line under `topic` (e.g. `spawn`, `signal`, `state`). Failing to write is
+/// never an error: the log is only a diagnostic aid.
+pub fn record(topic: &str, message: impl Display) {
+    let path = log_file();
+    if std::fs::metadata(&path).is_ok_and(|m| m.len() > MAX_LOG_SIZE) {
+        rotate();
+    } else if let Some(parent) = path.parent() {
+        let _ = config::create_private_dir(parent);
+    }
+    let Ok(mut file) = config::private_file().create(true).append(true).open(&path) else {
+        return;
+    };
+    let line = format!("{} [{}] {}: {}\n", local_datetime(), std::process::id(), topic, message);
+    let _ = file.write_all(line.as_bytes());
+}
+
+fn rotate() {
+    let files = log_files();
+    // Shift each file one place towards the oldest, dropping what falls off the end
+    for pair in files.windows(2) {
+        let _ = std::fs::rename(&pair[1], &pair[0]);
+    }
+}
@@ -0,0 +1,169 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::time::{Duration, Instant};
+
+use super::ports::FrameworkType;
+use crate::config;
+
+/// Bumped whenever the evaluation scripts change so stale cache entries are discarded
+const CACHE_VERSION: u32 = 2;
+
+/// How long the framework's own tooling gets to evaluate a config
+const EVAL_TIMEOUT: Duration = Duration::from_secs(10);
+
+const VITE_CONFIG_FILES: &[&str] = &["vite.config.ts", "vite.config.js", "vite.config.mts", "vite.config.mjs"];
+
+/// Loads the config through the project's own vite install and prints the resolved values
+const VITE_EVAL_SCRIPT: &str = r#"
+const path = require('path');
+const { createRequire } = require('module');
+const { pathToFileURL } = require('url');
+(async () => {
+  const req = createRequire(path.join(process.cwd(), 'package.json'));
+  const vite = await import(pathToFileURL(req.resolve('vite')).href);
+  const result = await vite.loadConfigFromFile({ command: 'serve', mode: 'development' }, undefined, process.cwd(), 'silent');
+  const config = (result && result.config) || {};
+  console.log(JSON.stringify({
+    port: (config.server && config.server.port) || null,
+    base_path: config.base || null,
+  }));
+})().catch((e) => { console.error(e.message); process.exit(1); });
+"#;
+
+/// Values resolved by evaluating a framework config
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DeepConfig {
+    #[serde(default)]
+    pub port: Option<u16>,
+    #[serde(default)]
+    pub base_path: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct CacheEntry {
+    fingerprint: u64,
+    config: DeepConfig,
+}
+
+/// Configs resolved in earlier runs. Loaded once per discovery and saved at the end if
+/// anything was evaluated.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct DeepCache {
+    entries: HashMap<PathBuf, CacheEntry>,
+    #[serde(skip)]
+    changed: bool,
+}
+
+impl DeepCache {
+    fn path() -> PathBuf {
+        config::get_cache_dir().join("deep-scan.json")
+    }
+
+    pub fn load() -> Self {
+        std::fs::read_to_string(Self::path())
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self) {
+        if !self.changed {
+            return;
+        }
+        let path = Self::path();
+        if let Some(parent) = path.parent() {
+            let _ = config::create_private_dir(parent);
+        }
+        if let Ok(content) = serde_json::to_string_pretty(self) {
+            let _ = config::write_private(&path, content);
+        }
+    }
+}
+
+/// Resolve a service's config by running the framework's own tooling.
+///
+/// Results are cached until the service's config files or package.json change.
+/// Returns `None` for frameworks without deep support or when evaluation fails.
+pub fn resolve(framework: &FrameworkType, service_dir: &Path, cache: &mut DeepCache) -> Option<DeepConfig> {
+    let (config_files, script) = match framework {
+        FrameworkType::Vite => (VITE_CONFIG_FILES, VITE_EVAL_SCRIPT),
+        _ => return None,
+    };
+
+    let fingerprint = fingerprint(service_dir, config_files);
+    if let Some(entry) = cache.entries.get(service_dir)
+        && entry.fingerprint == fingerprint
+    {
+        return Some(entry.config.clone());
+    }
+
+    let config = evaluate(service_dir, script)?;
+    cache.entries.insert(
+        service_dir.to_path_buf(),
+        CacheEntry {
+            fingerprint,
+            config: config.clone(),
+        },
+    );
+    cache.changed = true;
+    Some(config)
+}
+
+/// Hash the contents of every file that can influence the resolved config
+fn fingerprint(service_dir: &Path, config_files: &[&str]) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    CACHE_VERSION.hash(&mut hasher);
+    for file in config_files.iter().chain(["package.json"].iter()) {
+        if let Ok(content) = std::fs::read(service_dir.join(file)) {
+            file.hash(&mut hasher);
+            content.hash(&mut hasher);
+        }
+    }
+    hasher.finish()
+}
+
+fn evaluate(service_dir: &Path, script: &str) -> Option<DeepConfig> {
+    let mut child = Command::new("node")
+        .args(["-e", script])
+        .current_dir(service_dir)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .ok()?;
+
+    // Read while waiting, so a config that prints more than the pipe holds can't stall node
+    let mut stdout = child.stdout.take()?;
+    let (tx, rx) = std::sync::mpsc::channel();
+    std::thread::spawn(move || {
+        let mut output = Vec::new();
+        let _ = stdout.read_to_end(&mut output);
+        let _ = tx.send(output);
+    });
+
+    let started = Instant::now();
+    let status = loop {
+        match child.try_wait() {
+            Ok(Some(status)) => break status,
+            Ok(None) if started.elapsed() < EVAL_TIMEOUT => {
+                std::thread::sleep(Duration::from_millis(50));
+            }
+            _ => {
+                let _ = child.kill();
+                let _ = child.wait();
+                return None;
+            }
+        }
+    };
+
+    // Something node started could hold the pipe open after it exits
+    let output = rx.recv_timeout(EVAL_TIMEOUT.saturating_sub(started.elapsed())).ok()?;
+    if !status.success() {
+        return None;
+    }
+    serde_json::from_slice(&output).ok()
+}
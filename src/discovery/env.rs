@@ -0,0 +1,36 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+
+/// Env files dev servers load from a service directory, lowest precedence first
+pub const ENV_FILES: &[&str] = &[".env", ".env.development", ".env.local", ".env.development.local", ".dev.vars"];
+
+/// Variables a service's env files define, with later files overriding earlier ones
+pub fn load_env_files(service_dir: &Path) -> BTreeMap<String, String> {
+    let mut vars = BTreeMap::new();
+    for file in ENV_FILES {
+        let Ok(content) = std::fs::read_to_string(service_dir.join(file)) else {
+            continue;
+        };
+        for line in content.lines() {
+            let line = line.trim_start();
+            if line.starts_with('#') {
+                continue;
+            }
+            let line = line.strip_prefix("export ").unwrap_or(line);
+            let Some((key, value)) = line.split_once('=') else {
+                continue;
+            };
+            vars.insert(key.trim().to_string(), unquote(value.trim()).to_string());
+        }
+    }
+    vars
+}
+
+fn unquote(value: &str) -> &str {
+    for quote in ['"', '\''] {
+        if let Some(inner) = value.strip_prefix(quote).and_then(|v| v.strip_suffix(quote)) {
+            return inner;
+        }
+    }
+    value
+}
@@ -1,4 +1,10 @@
+mod deep;
+mod env;
 mod ports;
+mod scope;
 mod services;
 
+pub use env::{load_env_files, ENV_FILES};
+pub use ports::{free_port, service_url, FrameworkType, Scheme};
+pub use scope::Scope;
 pub use services::*;
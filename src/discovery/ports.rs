@@ -1,7 +1,9 @@
 use regex::Regex;
+use serde::{Deserialize, Serialize};
 use std::path::Path;
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
 pub enum FrameworkType {
     NextJs,
     Vite,
@@ -9,6 +11,152 @@ pub enum FrameworkType {
     Unknown,
 }
 
+impl std::fmt::Display for FrameworkType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            FrameworkType::NextJs => "next.js",
+            FrameworkType::Vite => "vite",
+            FrameworkType::Wrangler => "wrangler",
+            FrameworkType::Unknown => "unknown",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+impl FrameworkType {
+    /// CLI flag the framework's dev server accepts to override its port
+    pub fn port_flag(&self) -> Option<&'static str> {
+        match self {
+            FrameworkType::NextJs | FrameworkType::Vite | FrameworkType::Wrangler => Some("--port"),
+            FrameworkType::Unknown => None,
+        }
+    }
+
+    /// CLI flag the framework's dev server accepts to listen on another interface
+    pub fn host_flag(&self) -> Option<&'static str> {
+        match self {
+            FrameworkType::NextJs => Some("-H"),
+            FrameworkType::Vite => Some("--host"),
+            FrameworkType::Wrangler => Some("--ip"),
+            FrameworkType::Unknown => None,
+        }
+    }
+
+    /// Environment that keeps the dev server's output plain and consistent: colors kept,
+    /// no CI mode, no interactive UIs or telemetry notices breaking up the captured lines
+    pub fn default_env(&self) -> Vec<(&'static str, &'static str)> {
+        let mut env = vec![("FORCE_COLOR", "1"), ("CI", "false"), ("TURBO_UI", "false")];
+        match self {
+            FrameworkType::NextJs => env.push(("NEXT_TELEMETRY_DISABLED", "1")),
+            FrameworkType::Wrangler => env.push(("WRANGLER_SEND_METRICS", "false")),
+            FrameworkType::Vite | FrameworkType::Unknown => {}
+        }
+        env
+    }
+}
+
+/// Ask the OS for a port that is currently free on localhost
+pub fn free_port() -> Option<u16> {
+    std::net::TcpListener::bind("127.0.0.1:0")
+        .and_then(|listener| listener.local_addr())
+        .map(|addr| addr.port())
+        .ok()
+}
+
+/// URL scheme a dev server is served over
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Scheme {
+    #[default]
+    Http,
+    Https,
+}
+
+impl std::fmt::Display for Scheme {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Scheme::Http => write!(f, "http"),
+            Scheme::Https => write!(f, "https"),
+        }
+    }
+}
+
+/// Local URL for a service served on `port`, including its base path if it has one
+pub fn service_url(scheme: Scheme, port: u16, base_path: Option<&str>) -> String {
+    format!("{}://localhost:{}{}", scheme, port, base_path.unwrap_or(""))
+}
+
+/// Detect the path prefix a service is mounted under (Next.js `basePath`, Vite `base`)
+pub fn detect_base_path(framework: &FrameworkType, service_dir: &Path) -> Option<String> {
+    let (config_files, key): (&[&str], &str) = match framework {
+        FrameworkType::NextJs => (&["next.config.js", "next.config.mjs", "next.config.ts"], "basePath"),
+        FrameworkType::Vite => (&["vite.config.ts", "vite.config.js", "vite.config.mts", "vite.config.mjs"], "base"),
+        _ => return None,
+    };
+    let re = Regex::new(&format!(r#"\b{}\s*:\s*['"`]([^'"`]*)['"`]"#, key)).ok()?;
+
+    config_files
+        .iter()
+        .filter_map(|f| std::fs::read_to_string(service_dir.join(f)).ok())
+        .find_map(|content| {
+            re.captures(&content)
+                .and_then(|cap| cap.get(1))
+                .map(|m| m.as_str().to_string())
+        })
+        .and_then(|p| normalize_base_path(&p))
+}
+
+/// Ensure a leading slash (`app/` becomes `/app/`); the root path means no base path
+pub fn normalize_base_path(path: &str) -> Option<String> {
+    let path = path.trim();
+    // Relative bases like "./" only affect asset URLs, not where the app is served
+    if path.trim_matches('/').is_empty() || path.starts_with('.') || path.contains("://") {
+        return None;
+    }
+    if path.starts_with('/') {
+        Some(path.to_string())
+    } else {
+        Some(format!("/{}", path))
+    }
+}
+
+pub fn detect_scheme(framework: &FrameworkType, dev_command: &str, service_dir: &Path) -> Scheme {
+    let https = match framework {
+        // next dev --experimental-https
+        FrameworkType::NextJs => dev_command.contains("--experimental-https"),
+        FrameworkType::Vite => dev_command.contains("--https") || vite_config_uses_https(service_dir),
+        FrameworkType::Wrangler => {
+            dev_command.contains("--local-protocol=https")
+                || dev_command.contains("--local-protocol https")
+                || wrangler_config_uses_https(service_dir)
+        }
+        FrameworkType::Unknown => dev_command.contains("--https"),
+    };
+    if https { Scheme::Https } else { Scheme::Http }
+}
+
+fn vite_config_uses_https(service_dir: &Path) -> bool {
+    let config_files = ["vite.config.ts", "vite.config.js", "vite.config.mts", "vite.config.mjs"];
+    // server.https set to true/an options object, or the basic-ssl/mkcert plugins
+    let Ok(re) = Regex::new(r"https\s*:\s*(true|\{)|plugin-basic-ssl|vite-plugin-mkcert") else {
+        return false;
+    };
+    config_files
+        .iter()
+        .filter_map(|f| std::fs::read_to_string(service_dir.join(f)).ok())
+        .any(|content| re.is_match(&content))
+}
+
+fn wrangler_config_uses_https(service_dir: &Path) -> bool {
+    let Ok(re) = Regex::new(r#"local_protocol"?\s*[:=]\s*"https""#) else {
+        return false;
+    };
+    ["wrangler.jsonc", "wrangler.json", "wrangler.toml"]
+        .iter()
+        .filter_map(|f| std::fs::read_to_string(service_dir.join(f)).ok())
+        .any(|content| re.is_match(&content))
+}
+
 pub fn detect_port(framework: &FrameworkType, dev_command: &str, service_dir: &Path) -> Option<u16> {
     match framework {
         FrameworkType::NextJs => detect_nextjs_port(dev_command),
@@ -31,20 +179,18 @@ fn detect_vite_port(service_dir: &Path) -> Option<u16> {
     // Try vite.config.ts first, then vite.config.js
     let config_files = ["vite.config.ts", "vite.config.js", "vite.config.mts", "vite.config.mjs"];
 
+    // Look for server.port or port: in the config
+    let re = Regex::new(r"port\s*:\s*(\d+)").ok()?;
+
     for config_file in &config_files {
         let config_path = service_dir.join(config_file);
-        if config_path.exists() {
-            if let Ok(content) = std::fs::read_to_string(&config_path) {
-                // Look for server.port or port: in the config
-                let re = Regex::new(r"port\s*:\s*(\d+)").ok()?;
-                if let Some(cap) = re.captures(&content) {
-                    if let Some(m) = cap.get(1) {
-                        if let Ok(port) = m.as_str().parse() {
-                            return Some(port);
-                        }
-                    }
-                }
-            }
+        if let Ok(content) = std::fs::read_to_string(&config_path)
+            && let Some(port) = re
+                .captures(&content)
+                .and_then(|cap| cap.get(1))
+                .and_then(|m| m.as_str().parse().ok())
+        {
+            return Some(port);
         }
     }
 
@@ -54,34 +200,28 @@ fn detect_vite_port(service_dir: &Path) -> Option<u16> {
 fn detect_wrangler_port(service_dir: &Path) -> Option<u16> {
     // Try wrangler.jsonc first, then wrangler.toml
     let jsonc_path = service_dir.join("wrangler.jsonc");
-    if jsonc_path.exists() {
-        if let Ok(content) = std::fs::read_to_string(&jsonc_path) {
-            // Simple regex to find port in JSON (handles comments by just looking for pattern)
-            let re = Regex::new(r#""port"\s*:\s*(\d+)"#).ok()?;
-            if let Some(cap) = re.captures(&content) {
-                if let Some(m) = cap.get(1) {
-                    if let Ok(port) = m.as_str().parse() {
-                        return Some(port);
-                    }
-                }
-            }
+    if let Ok(content) = std::fs::read_to_string(&jsonc_path) {
+        // Simple regex to find port in JSON (handles comments by just looking for pattern)
+        let re = Regex::new(r#""port"\s*:\s*(\d+)"#).ok()?;
+        if let Some(port) = re
+            .captures(&content)
+            .and_then(|cap| cap.get(1))
+            .and_then(|m| m.as_str().parse().ok())
+        {
+            return Some(port);
         }
     }
 
     let toml_path = service_dir.join("wrangler.toml");
-    if toml_path.exists() {
-        if let Ok(content) = std::fs::read_to_string(&toml_path) {
-            // Parse TOML and look for dev.port
-            if let Ok(value) = content.parse::<toml::Value>() {
-                if let Some(port) = value
-                    .get("dev")
-                    .and_then(|d| d.get("port"))
-                    .and_then(|p| p.as_integer())
-                {
-                    return Some(port as u16);
-                }
-            }
-        }
+    if let Ok(content) = std::fs::read_to_string(&toml_path)
+        && let Ok(value) = content.parse::<toml::Value>()
+        // Parse TOML and look for dev.port
+        && let Some(port) = value
+            .get("dev")
+            .and_then(|d| d.get("port"))
+            .and_then(|p| p.as_integer())
+    {
+        return Some(port as u16);
     }
 
     Some(8787) // Wrangler default
@@ -0,0 +1,140 @@
+use std::path::Path;
+
+use crate::select::glob_matches;
+
+/// Directories discovery is limited to by `--scope`: paths relative to the repository root
+/// like `apps/shop` or `apps/shop/**`, where `*` matches within one directory name and `**`
+/// any number of directories. Packages in or below a matching directory are in scope.
+#[derive(Debug, Clone, Default)]
+pub struct Scope {
+    patterns: Vec<Vec<String>>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
+enum Fit {
+    Outside,
+    /// Not in scope itself, but something below it may be
+    Ancestor,
+    Inside,
+}
+
+impl Scope {
+    pub fn new(patterns: &[String]) -> Self {
+        Self {
+            patterns: patterns
+                .iter()
+                .map(|p| {
+                    p.split(['/', '\\'])
+                        .filter(|s| !s.is_empty() && *s != ".")
+                        .map(str::to_string)
+                        .collect()
+                })
+                .collect(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.patterns.is_empty()
+    }
+
+    /// Whether a directory relative to the repository root is in scope
+    pub fn contains(&self, dir: &Path) -> bool {
+        self.is_empty() || self.fit(dir) == Fit::Inside
+    }
+
+    /// Whether discovery has to look inside a directory to find what's in scope
+    pub fn reaches(&self, dir: &Path) -> bool {
+        self.is_empty() || self.fit(dir) != Fit::Outside
+    }
+
+    fn fit(&self, dir: &Path) -> Fit {
+        let segments: Vec<String> = dir
+            .components()
+            .map(|c| c.as_os_str().to_string_lossy().into_owned())
+            .collect();
+        self.patterns
+            .iter()
+            .map(|pattern| fit(pattern, &segments))
+            .fold(Fit::Outside, |best, fit| if fit > best { fit } else { best })
+    }
+}
+
+fn fit(pattern: &[String], path: &[String]) -> Fit {
+    match (pattern.first(), path.first()) {
+        // Everything below a matched directory is in scope
+        (None, _) => Fit::Inside,
+        (Some(_), None) if pattern.iter().all(|p| p == "**") => Fit::Inside,
+        (Some(_), None) => Fit::Ancestor,
+        (Some(segment), Some(_)) if segment == "**" => {
+            let skip = fit(&pattern[1..], path);
+            let consume = fit(pattern, &path[1..]);
+            if skip > consume { skip } else { consume }
+        }
+        (Some(segment), Some(name)) if glob_matches(segment, name) => fit(&pattern[1..], &path[1..]),
+        _ => Fit::Outside,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scope(patterns: &[&str]) -> Scope {
+        Scope::new(&patterns.iter().map(|p| p.to_string()).collect::<Vec<_>>())
+    }
+
+    #[test]
+    fn no_patterns_is_everything() {
+        let scope = scope(&[]);
+        assert!(scope.contains(Path::new("apps/web")));
+        assert!(scope.reaches(Path::new("anything")));
+    }
+
+    #[test]
+    fn a_directory_covers_everything_below_it() {
+        let scope = scope(&["./apps/shop/"]);
+        assert!(scope.contains(Path::new("apps/shop")));
+        assert!(scope.contains(Path::new("apps/shop/web")));
+        assert!(!scope.contains(Path::new("apps/admin")));
+        assert!(!scope.contains(Path::new("apps")));
+    }
+
+    #[test]
+    fn ancestors_are_reached_but_not_contained() {
+        let scope = scope(&["apps/shop"]);
+        assert!(scope.reaches(Path::new("")));
+        assert!(scope.reaches(Path::new("apps")));
+        assert!(!scope.reaches(Path::new("packages")));
+        assert!(!scope.reaches(Path::new("apps/admin")));
+    }
+
+    #[test]
+    fn star_matches_within_one_directory_name() {
+        let scope = scope(&["apps/s*"]);
+        assert!(scope.contains(Path::new("apps/shop")));
+        assert!(scope.contains(Path::new("apps/site/src")));
+        assert!(!scope.contains(Path::new("apps/web")));
+    }
+
+    #[test]
+    fn double_star_matches_any_number_of_directories() {
+        let leading = scope(&["**/web"]);
+        assert!(leading.contains(Path::new("web")));
+        assert!(leading.contains(Path::new("apps/web")));
+        assert!(leading.contains(Path::new("a/b/web/src")));
+        assert!(!leading.contains(Path::new("apps/api")));
+        assert!(leading.reaches(Path::new("apps/api")));
+
+        let trailing = scope(&["apps/shop/**"]);
+        assert!(trailing.contains(Path::new("apps/shop")));
+        assert!(!trailing.contains(Path::new("apps")));
+    }
+
+    #[test]
+    fn any_pattern_can_match() {
+        let scope = scope(&["apps/shop", "packages/ui"]);
+        assert!(scope.contains(Path::new("apps/shop")));
+        assert!(scope.contains(Path::new("packages/ui")));
+        assert!(!scope.contains(Path::new("packages/config")));
+    }
+}
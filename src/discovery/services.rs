@@ -1,23 +1,106 @@
 use anyhow::{Context, Result};
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::path::{Path, PathBuf};
 use std::process::Command;
 use walkdir::WalkDir;
 
-use super::ports::{detect_port, FrameworkType};
+use super::deep::{self, DeepCache};
+use super::scope::Scope;
+use super::ports::{detect_base_path, detect_port, detect_scheme, normalize_base_path, FrameworkType, Scheme};
+use crate::config::{Priority, ProjectConfig, ServiceConfig};
+use crate::diag;
+use crate::select::matching_services;
+
+/// Knobs for a discovery run
+#[derive(Debug, Clone, Default)]
+pub struct DiscoveryOptions {
+    /// Evaluate framework configs with the framework's own tooling instead of regex heuristics
+    pub deep: bool,
+    /// Only look at packages in these directories
+    pub scope: Scope,
+}
 
 #[derive(Debug, Clone)]
 pub struct Service {
     pub name: String,
     pub path: PathBuf,
-    pub dev_command: String,
-    #[allow(dead_code)]
     pub framework: FrameworkType,
     pub port: Option<u16>,
+    pub scheme: Scheme,
+    /// Path prefix the app is served under, e.g. `/docs`
+    pub base_path: Option<String>,
+    /// Labels from groo.toml for selecting groups of services, e.g. `@frontend`
+    pub tags: Vec<String>,
+    /// `command` from groo.toml, run instead of `npm run dev`
+    pub command: Option<String>,
+    /// `[dev.env]` and the service's `env` from groo.toml
+    pub env: BTreeMap<String, String>,
+    /// The framework's env defaults, set at spawn time unless the shell or `env` sets them
+    pub framework_env: BTreeMap<String, String>,
+    /// `name` in package.json, which survives the directory being moved
+    pub package_name: Option<String>,
+    /// `priority` from groo.toml
+    pub priority: Priority,
+}
+
+/// Why a package.json was not turned into a service
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "reason", content = "detail", rename_all = "snake_case")]
+pub enum SkipReason {
+    /// The repository root package.json (usually workspace tooling)
+    RootPackage,
+    NoDevScript,
+    /// The dev script fans out to other packages (turbo, pnpm -r, ...)
+    OrchestratorScript,
+    InvalidPackageJson(String),
+}
+
+impl std::fmt::Display for SkipReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SkipReason::RootPackage => write!(f, "root package"),
+            SkipReason::NoDevScript => write!(f, "no dev script"),
+            SkipReason::OrchestratorScript => write!(f, "orchestrator script"),
+            SkipReason::InvalidPackageJson(e) => write!(f, "invalid package.json: {}", e),
+        }
+    }
+}
+
+/// A package.json found during discovery, whether or not it became a service
+#[derive(Debug, Clone, Serialize)]
+pub struct Candidate {
+    pub name: String,
+    #[serde(serialize_with = "crate::state::os_path::serialize")]
+    pub path: PathBuf,
+    pub accepted: bool,
+    pub dev_command: Option<String>,
+    pub framework: Option<FrameworkType>,
+    pub port: Option<u16>,
+    /// Port reported by `--deep` evaluation, when it ran
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub deep_port: Option<u16>,
+    pub scheme: Option<Scheme>,
+    pub base_path: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub command: Option<String>,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub env: BTreeMap<String, String>,
+    #[serde(skip)]
+    pub framework_env: BTreeMap<String, String>,
+    /// `name` in package.json
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub package_name: Option<String>,
+    #[serde(skip_serializing_if = "Priority::is_default")]
+    pub priority: Priority,
+    pub skip_reason: Option<SkipReason>,
 }
 
 #[derive(Deserialize)]
 struct PackageJson {
+    name: Option<String>,
     scripts: Option<std::collections::HashMap<String, String>>,
 }
 
@@ -31,95 +114,331 @@ pub fn find_git_root() -> Result<PathBuf> {
         anyhow::bail!("Not in a git repository");
     }
 
-    let path = String::from_utf8(output.stdout)?
-        .trim()
-        .to_string();
-    Ok(PathBuf::from(path))
+    let path = path_from_output(output.stdout);
+    // One canonical root however the repo was reached (symlinks, `-w`), so state entries
+    // and log paths recorded from one place are found from another
+    Ok(path.canonicalize().unwrap_or(path))
+}
+
+/// A path printed by a tool, kept byte-for-byte where paths needn't be UTF-8
+fn path_from_output(mut stdout: Vec<u8>) -> PathBuf {
+    while stdout.last().is_some_and(|b| b.is_ascii_whitespace()) {
+        stdout.pop();
+    }
+    #[cfg(unix)]
+    {
+        use std::os::unix::ffi::OsStringExt;
+        PathBuf::from(std::ffi::OsString::from_vec(stdout))
+    }
+    #[cfg(not(unix))]
+    {
+        PathBuf::from(String::from_utf8_lossy(&stdout).into_owned())
+    }
+}
+
+/// Current branch of the repository at `git_root`, or `None` on a detached HEAD
+pub fn get_git_branch(git_root: &Path) -> Option<String> {
+    git_output(git_root, &["symbolic-ref", "--short", "-q", "HEAD"])
+}
+
+/// Abbreviated SHA of the commit checked out at `git_root`
+pub fn get_git_commit(git_root: &Path) -> Option<String> {
+    git_output(git_root, &["rev-parse", "--short", "HEAD"])
+}
+
+fn git_output(git_root: &Path, args: &[&str]) -> Option<String> {
+    let output = Command::new("git")
+        .args(args)
+        .current_dir(git_root)
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let value = String::from_utf8(output.stdout).ok()?.trim().to_string();
+    (!value.is_empty()).then_some(value)
 }
 
 pub fn get_project_name(git_root: &Path) -> String {
     git_root
         .file_name()
-        .and_then(|n| n.to_str())
-        .unwrap_or("unknown")
-        .to_string()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "unknown".to_string())
+}
+
+/// Find the one service a query matches the way `-s/--service` does (so `web`, `*web` and
+/// `wb` find `apps:web`), failing if it matches none or several
+pub fn find_service<'a>(services: &'a [Service], query: &str) -> Result<&'a Service> {
+    let matches = matching_services(services, query);
+    match matches.as_slice() {
+        [index] => Ok(&services[*index]),
+        [] => {
+            let available: Vec<&str> = services.iter().map(|s| s.name.as_str()).collect();
+            anyhow::bail!(
+                "Service '{}' not found. Available services: {}",
+                query,
+                available.join(", ")
+            )
+        }
+        _ => {
+            let names: Vec<&str> = matches.iter().map(|&i| services[i].name.as_str()).collect();
+            anyhow::bail!("Service '{}' is ambiguous: {}", query, names.join(", "))
+        }
+    }
 }
 
 pub fn discover_services(git_root: &Path) -> Result<Vec<Service>> {
+    discover_services_with(git_root, &DiscoveryOptions::default())
+}
+
+pub fn discover_services_with(git_root: &Path, options: &DiscoveryOptions) -> Result<Vec<Service>> {
     let mut services = Vec::new();
+    let mut skipped = Vec::new();
+
+    for candidate in discover_candidates(git_root, options)? {
+        if let Some(reason) = &candidate.skip_reason {
+            skipped.push(format!("{} ({})", candidate.name, reason));
+        }
+        match candidate.skip_reason {
+            None => services.push(Service {
+                name: candidate.name,
+                path: candidate.path,
+                framework: candidate.framework.unwrap_or(FrameworkType::Unknown),
+                port: candidate.port,
+                scheme: candidate.scheme.unwrap_or_default(),
+                base_path: candidate.base_path,
+                tags: candidate.tags,
+                command: candidate.command,
+                env: candidate.env,
+                framework_env: candidate.framework_env,
+                package_name: candidate.package_name,
+                priority: candidate.priority,
+            }),
+            Some(SkipReason::InvalidPackageJson(e)) => {
+                anyhow::bail!("Invalid package.json in {}: {}", candidate.path.display(), e)
+            }
+            Some(_) => {}
+        }
+    }
+
+    diag::record(
+        "discovery",
+        format!(
+            "{}: found [{}], skipped [{}]",
+            git_root.display(),
+            services.iter().map(|s| s.name.as_str()).collect::<Vec<_>>().join(", "),
+            skipped.join(", ")
+        ),
+    );
+    Ok(services)
+}
+
+/// Walk the repository and describe every package.json, including the ones that are skipped
+pub fn discover_candidates(git_root: &Path, options: &DiscoveryOptions) -> Result<Vec<Candidate>> {
+    let config = ProjectConfig::load(git_root)?;
+    let mut candidates = Vec::new();
+    let mut deep_cache = options.deep.then(DeepCache::load);
+    let relative = |path: &Path| path.strip_prefix(git_root).unwrap_or(path).to_path_buf();
 
     for entry in WalkDir::new(git_root)
         .follow_links(true)
         .into_iter()
-        .filter_entry(|e| !is_ignored(e.path()))
+        .filter_entry(|e| {
+            // Huge repositories are walked only as deep as the scope needs
+            !is_ignored(e.path()) && (!e.file_type().is_dir() || options.scope.reaches(&relative(e.path())))
+        })
     {
         let entry = entry?;
         if entry.file_name() == "package.json" {
             let package_path = entry.path();
             let service_dir = package_path.parent().unwrap();
-
-            // Skip root package.json
-            if service_dir == git_root {
+            if !options.scope.contains(&relative(service_dir)) {
                 continue;
             }
-
-            if let Some(service) = parse_service(git_root, service_dir, package_path)? {
-                services.push(service);
+            let mut candidate = parse_candidate(git_root, service_dir, package_path, deep_cache.as_mut());
+            if candidate.accepted {
+                apply_overrides(&mut candidate, &config);
             }
+            candidates.push(candidate);
         }
     }
 
-    Ok(services)
-}
-
-fn is_ignored(path: &Path) -> bool {
-    let name = path.file_name().and_then(|n| n.to_str()).unwrap_or("");
-    matches!(name, "node_modules" | ".git" | "dist" | "build" | ".next" | ".turbo")
-}
+    if let Some(cache) = &deep_cache {
+        cache.save();
+    }
 
-fn parse_service(git_root: &Path, service_dir: &Path, package_path: &Path) -> Result<Option<Service>> {
-    let content = std::fs::read_to_string(package_path)?;
-    let package: PackageJson = serde_json::from_str(&content)?;
+    // Services declared only in groo.toml, with a command and no package.json
+    let mut declared: Vec<(&String, &ServiceConfig)> = config
+        .services
+        .iter()
+        .filter(|(name, c)| c.command.is_some() && !candidates.iter().any(|cand| &cand.name == *name))
+        .filter(|(_, c)| options.scope.contains(c.path.as_deref().unwrap_or(Path::new(""))))
+        .collect();
+    declared.sort_by(|a, b| a.0.cmp(b.0));
+    for (name, service_config) in declared {
+        let path = match &service_config.path {
+            Some(path) => git_root.join(path),
+            None => git_root.to_path_buf(),
+        };
+        let command = service_config.command.clone().unwrap_or_default();
+        let mut candidate = Candidate {
+            name: name.clone(),
+            port: detect_port(&FrameworkType::Unknown, &command, &path),
+            path,
+            accepted: true,
+            dev_command: Some(command),
+            framework: Some(FrameworkType::Unknown),
+            deep_port: None,
+            scheme: Some(Scheme::default()),
+            base_path: None,
+            tags: Vec::new(),
+            command: None,
+            env: BTreeMap::new(),
+            framework_env: BTreeMap::new(),
+            package_name: None,
+            priority: Priority::default(),
+            skip_reason: None,
+        };
+        apply_overrides(&mut candidate, &config);
+        candidates.push(candidate);
+    }
 
-    let dev_command = match package.scripts {
-        Some(scripts) => scripts.get("dev").cloned(),
-        None => None,
-    };
+    Ok(candidates)
+}
 
-    let dev_command = match dev_command {
-        Some(cmd) => cmd,
-        None => return Ok(None),
+/// Let explicit groo.toml settings win over detected values
+fn apply_overrides(candidate: &mut Candidate, config: &ProjectConfig) {
+    candidate.framework_env = framework_env(candidate.framework.as_ref().unwrap_or(&FrameworkType::Unknown), config);
+    candidate.env = config
+        .dev
+        .env
+        .iter()
+        .filter(|(_, value)| !value.is_empty())
+        .map(|(key, value)| (key.clone(), value.clone()))
+        .collect();
+    let Some(service_config) = config.service(&candidate.name) else {
+        return;
     };
+    if let Some(scheme) = service_config.scheme {
+        candidate.scheme = Some(scheme);
+    }
+    if let Some(base_path) = &service_config.base_path {
+        candidate.base_path = normalize_base_path(base_path);
+    }
+    if let Some(port) = service_config.port {
+        candidate.port = Some(port);
+    }
+    candidate.tags = service_config.tags.clone();
+    candidate.command = service_config.command.clone();
+    candidate.env.extend(service_config.env.clone());
+    candidate.priority = service_config.priority;
+}
 
-    // Skip orchestrator scripts (turbo, pnpm workspace, npm workspace, etc.)
-    if is_orchestrator_script(&dev_command) {
-        return Ok(None);
+/// The framework's env defaults, unless turned off or dropped with an empty `[dev.env]` value
+fn framework_env(framework: &FrameworkType, config: &ProjectConfig) -> BTreeMap<String, String> {
+    if !config.dev.framework_env {
+        return BTreeMap::new();
     }
+    framework
+        .default_env()
+        .into_iter()
+        .filter(|(key, _)| !config.dev.env.contains_key(*key))
+        .map(|(key, value)| (key.to_string(), value.to_string()))
+        .collect()
+}
 
-    let framework = detect_framework(&dev_command, service_dir);
-    let port = detect_port(&framework, &dev_command, service_dir);
+fn is_ignored(path: &Path) -> bool {
+    let name = path.file_name().and_then(|n| n.to_str()).unwrap_or("");
+    matches!(name, "node_modules" | ".git" | "dist" | "build" | ".next" | ".turbo")
+}
 
-    // Use relative path from git root as the service name
+fn parse_candidate(
+    git_root: &Path,
+    service_dir: &Path,
+    package_path: &Path,
+    deep_cache: Option<&mut DeepCache>,
+) -> Candidate {
+    // Use relative path from git root as the service name. Directories whose names
+    // aren't UTF-8 get a lossy name; `path` keeps the real one.
     let name = service_dir
         .strip_prefix(git_root)
         .ok()
-        .and_then(|p| p.to_str())
+        .map(|p| p.to_string_lossy())
+        .filter(|s| !s.is_empty())
         .map(|s| s.replace('/', ":"))
         .unwrap_or_else(|| {
             service_dir
                 .file_name()
-                .and_then(|n| n.to_str())
-                .unwrap_or("unknown")
-                .to_string()
+                .map(|n| n.to_string_lossy().into_owned())
+                .unwrap_or_else(|| "unknown".to_string())
         });
 
-    Ok(Some(Service {
+    let mut candidate = Candidate {
         name,
         path: service_dir.to_path_buf(),
-        dev_command,
-        framework,
-        port,
-    }))
+        accepted: false,
+        dev_command: None,
+        framework: None,
+        port: None,
+        deep_port: None,
+        scheme: None,
+        base_path: None,
+        tags: Vec::new(),
+        command: None,
+        env: BTreeMap::new(),
+        framework_env: BTreeMap::new(),
+        package_name: None,
+        priority: Priority::default(),
+        skip_reason: None,
+    };
+
+    // Skip root package.json
+    if service_dir == git_root {
+        candidate.skip_reason = Some(SkipReason::RootPackage);
+        return candidate;
+    }
+
+    let package = std::fs::read_to_string(package_path)
+        .map_err(|e| e.to_string())
+        .and_then(|content| serde_json::from_str::<PackageJson>(&content).map_err(|e| e.to_string()));
+    let package = match package {
+        Ok(p) => p,
+        Err(e) => {
+            candidate.skip_reason = Some(SkipReason::InvalidPackageJson(e));
+            return candidate;
+        }
+    };
+
+    candidate.package_name = package.name;
+    candidate.dev_command = package.scripts.and_then(|scripts| scripts.get("dev").cloned());
+
+    let Some(dev_command) = candidate.dev_command.clone() else {
+        candidate.skip_reason = Some(SkipReason::NoDevScript);
+        return candidate;
+    };
+
+    // Skip orchestrator scripts (turbo, pnpm workspace, npm workspace, etc.)
+    if is_orchestrator_script(&dev_command) {
+        candidate.skip_reason = Some(SkipReason::OrchestratorScript);
+        return candidate;
+    }
+
+    let framework = detect_framework(&dev_command, service_dir);
+    candidate.port = detect_port(&framework, &dev_command, service_dir);
+    candidate.base_path = detect_base_path(&framework, service_dir);
+    if let Some(cache) = deep_cache
+        && let Some(resolved) = deep::resolve(&framework, service_dir, cache)
+    {
+        candidate.deep_port = resolved.port;
+        if resolved.port.is_some() {
+            candidate.port = resolved.port;
+        }
+        candidate.base_path = resolved.base_path.as_deref().and_then(normalize_base_path);
+    }
+    candidate.scheme = Some(detect_scheme(&framework, &dev_command, service_dir));
+    candidate.framework = Some(framework);
+    candidate.accepted = true;
+    candidate
 }
 
 fn is_orchestrator_script(dev_command: &str) -> bool {
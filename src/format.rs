@@ -0,0 +1,103 @@
+//! How sizes, durations and relative times read across commands, so `status`, `list`,
+//! `recent`, `clean` and the runner say `3h 12m` or `12.4 MB` the same way.
+
+use anyhow::Result;
+use std::time::Duration;
+
+use crate::state::now_millis;
+
+/// Byte size, e.g. `512 B` or `12.4 MB`
+pub fn bytes(bytes: u64) -> String {
+    const UNITS: &[&str] = &["B", "KB", "MB", "GB", "TB"];
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} {}", bytes, UNITS[0])
+    } else {
+        format!("{:.1} {}", size, UNITS[unit])
+    }
+}
+
+/// Duration to its two largest units, e.g. `45s`, `12m`, `3h 12m` or `2d 5h`
+pub fn duration(duration: Duration) -> String {
+    let secs = duration.as_secs();
+    match secs {
+        0..60 => format!("{}s", secs),
+        60..3600 => format!("{}m", secs / 60),
+        3600..86400 => format!("{}h {}m", secs / 3600, (secs % 3600) / 60),
+        _ => format!("{}d {}h", secs / 86400, (secs % 86400) / 3600),
+    }
+}
+
+/// Duration down to the second, for configured timeouts that shouldn't read rounded:
+/// `90s` is `1m 30s`, not `1m`
+pub fn exact(duration: Duration) -> String {
+    let secs = duration.as_secs();
+    let units = [
+        (secs / 86400, "d"),
+        ((secs % 86400) / 3600, "h"),
+        ((secs % 3600) / 60, "m"),
+        (secs % 60, "s"),
+    ];
+    let parts: Vec<String> = units
+        .into_iter()
+        .filter(|&(value, _)| value > 0)
+        .map(|(value, unit)| format!("{}{}", value, unit))
+        .collect();
+    if parts.is_empty() {
+        "0s".to_string()
+    } else {
+        parts.join(" ")
+    }
+}
+
+/// Short duration with sub-second detail, for timings like startup: `850ms`, `1.6s`
+pub fn precise(duration: Duration) -> String {
+    match duration.as_millis() {
+        0..1000 => format!("{}ms", duration.as_millis()),
+        1000..60_000 => format!("{:.1}s", duration.as_secs_f64()),
+        _ => self::duration(duration),
+    }
+}
+
+/// Time elapsed since something happened, e.g. `12m ago`
+pub fn ago(elapsed: Duration) -> String {
+    if elapsed < Duration::from_secs(1) {
+        "just now".to_string()
+    } else {
+        format!("{} ago", duration(elapsed))
+    }
+}
+
+/// [`ago`] for a Unix time in milliseconds
+pub fn since(millis: u64) -> String {
+    ago(Duration::from_millis(now_millis().saturating_sub(millis)))
+}
+
+/// Parse durations like `30m`, `12h`, `7d` or `2w`; a bare number is days
+pub fn parse_duration(input: &str) -> Result<Duration> {
+    let input = input.trim();
+    let split = input
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(input.len());
+    let (number, unit) = input.split_at(split);
+    let number: u64 = number
+        .parse()
+        .map_err(|_| anyhow::anyhow!("Invalid duration '{}'. Use e.g. 30m, 12h, 7d", input))?;
+    let seconds = match unit {
+        "s" => 1,
+        "m" => 60,
+        "h" => 60 * 60,
+        "d" | "" => 24 * 60 * 60,
+        "w" => 7 * 24 * 60 * 60,
+        _ => anyhow::bail!("Invalid duration unit '{}'. Use s, m, h, d or w", unit),
+    };
+    let seconds = number
+        .checked_mul(seconds)
+        .ok_or_else(|| anyhow::anyhow!("Invalid duration '{}': too long", input))?;
+    Ok(Duration::from_secs(seconds))
+}
@@ -1,12 +1,18 @@
+mod backend;
 mod commands;
 mod config;
+mod diag;
 mod discovery;
+mod format;
+mod output;
 mod runner;
+mod select;
 mod state;
+mod template;
 
 use anyhow::{Context, Result};
-use clap::{Parser, Subcommand};
-use std::path::PathBuf;
+use clap::{CommandFactory, FromArgMatches, Parser, Subcommand};
+use std::path::{Path, PathBuf};
 
 #[derive(Parser)]
 #[command(name = "groo")]
@@ -17,6 +23,14 @@ struct Cli {
     #[arg(short = 'w', long = "workdir", global = true)]
     workdir: Option<PathBuf>,
 
+    /// Output style: human, plain or json (also GROO_OUTPUT; NO_COLOR implies plain)
+    #[arg(long, global = true, value_enum)]
+    output: Option<output::OutputMode>,
+
+    /// Only print errors and the command's primary output
+    #[arg(short = 'q', long, global = true)]
+    quiet: bool,
+
     #[command(subcommand)]
     command: Commands,
 }
@@ -24,25 +38,84 @@ struct Cli {
 #[derive(Subcommand)]
 enum Commands {
     /// Start dev servers interactively
-    Dev,
+    Dev {
+        /// Resolve ports by evaluating framework configs (slower, cached)
+        #[arg(long)]
+        deep: bool,
+        /// Run in a recent project: by name, or pick interactively when no name is given
+        #[arg(short = 'P', long = "project", num_args = 0..=1, default_missing_value = "")]
+        project: Option<String>,
+        /// Run an isolated copy of the stack on free ports with its own state and logs
+        #[arg(long)]
+        ephemeral: bool,
+        /// Pick services without prompting: name, last name segment, @tag, glob or fuzzy match
+        #[arg(short = 's', long = "service")]
+        services: Vec<String>,
+        /// Prefix each line of service output with the time it was printed
+        #[arg(long)]
+        timestamps: bool,
+        /// Set an environment variable for every started service (repeatable)
+        #[arg(short = 'e', long = "env", value_name = "KEY=VALUE")]
+        env: Vec<String>,
+        /// Only discover packages under these paths from the repository root, e.g. "apps/shop/**"
+        #[arg(long, value_name = "PATH")]
+        scope: Vec<String>,
+        /// Keep services running when the terminal closes instead of stopping them (not on Windows)
+        #[arg(long)]
+        keep_alive: bool,
+    },
     /// Restart running services
-    Restart,
+    Restart {
+        /// Restart only services that crashed in the last session
+        #[arg(long, conflicts_with = "services")]
+        crashed: bool,
+        /// Pick services without prompting: name, last name segment, @tag, glob or fuzzy match
+        #[arg(short = 's', long = "service")]
+        services: Vec<String>,
+    },
+    /// List recently used projects
+    Recent,
     /// List all projects with running services
-    List,
+    List {
+        /// Print each project using a template, e.g. "{name}\t{services}"
+        #[arg(long)]
+        format: Option<String>,
+    },
     /// Show status of services in a project
     Status {
         /// Project name (defaults to current directory)
         project: Option<String>,
+        /// Print each service using a template, e.g. "{name}\t{port}\t{status}"
+        #[arg(long)]
+        format: Option<String>,
+        /// Order services by a column (default: crashed and unhealthy first)
+        #[arg(long, value_enum)]
+        sort: Option<commands::status::SortKey>,
+        /// List each service's recent exits under it
+        #[arg(short = 'v', long)]
+        verbose: bool,
     },
     /// Open a service in the browser
     Open {
-        /// Service name to open
+        /// Service to open: name, last name segment, glob or fuzzy match
         service: String,
+        /// Open immediately instead of waiting for the service to accept connections
+        #[arg(long)]
+        no_wait: bool,
+        /// Seconds to wait for the service before giving up
+        #[arg(long, default_value_t = 60)]
+        timeout: u64,
     },
     /// Stop all services in a project
     Stop {
-        /// Project name (defaults to current directory)
+        /// Project name (defaults to current directory), or a service query if no project has that name
         project: Option<String>,
+        /// Pick services without prompting: name, last name segment, @tag, glob or fuzzy match
+        #[arg(short = 's', long = "service")]
+        services: Vec<String>,
+        /// Stop every running service without prompting
+        #[arg(long, conflicts_with = "services")]
+        all: bool,
     },
     /// View logs for running services
     Logs {
@@ -52,26 +125,295 @@ enum Commands {
         /// Follow log output
         #[arg(short = 'f', long)]
         follow: bool,
+        /// Show logs of another project by name, without cd'ing into it
+        #[arg(short = 'P', long = "project")]
+        project: Option<String>,
+        /// Start at the latest `groo mark` whose label contains this text instead of the last N lines
+        #[arg(long, value_name = "LABEL")]
+        since_mark: Option<String>,
+        /// Pick services without prompting: name, last name segment, @tag, glob or fuzzy match
+        #[arg(short = 's', long = "service")]
+        services: Vec<String>,
+    },
+    /// Write a timestamped marker into running services' logs and console
+    Mark {
+        /// What's about to happen, e.g. "before reproducing bug"
+        label: String,
+        /// Mark only these services: name, last name segment, @tag, glob or fuzzy match
+        #[arg(short = 's', long = "service")]
+        services: Vec<String>,
+    },
+    /// Write a local usage report from recorded sessions (no network)
+    Report {
+        /// Project name (defaults to current directory)
+        project: Option<String>,
+        /// Include all projects
+        #[arg(long, conflicts_with = "project")]
+        all: bool,
+        /// Write JSON instead of markdown
+        #[arg(long)]
+        json: bool,
+        /// File to write (defaults to groo-report.md or groo-report.json)
+        #[arg(short = 'o', long)]
+        file: Option<PathBuf>,
+    },
+    /// Show which packages discovery accepts or skips, and why
+    Discover {
+        /// Print machine-readable JSON
+        #[arg(long)]
+        json: bool,
+        /// Resolve ports by evaluating framework configs (slower, cached)
+        #[arg(long)]
+        deep: bool,
+        /// Only discover packages under these paths from the repository root, e.g. "apps/shop/**"
+        #[arg(long, value_name = "PATH")]
+        scope: Vec<String>,
+    },
+    /// Print a service's directory, or the project root
+    Path {
+        /// Service name (full name or its last segment, e.g. "web" for "apps:web")
+        service: Option<String>,
+        /// List all service names instead
+        #[arg(long, conflicts_with = "service")]
+        list: bool,
+    },
+    /// Print shell integration (adds `groo cd <service>` with completion)
+    ShellInit {
+        #[arg(value_enum)]
+        shell: commands::shell_init::Shell,
+    },
+    /// Change to a service directory (requires shell-init)
+    #[command(hide = true)]
+    Cd {
+        #[arg(trailing_var_arg = true, allow_hyphen_values = true)]
+        args: Vec<String>,
+    },
+    /// Diagnose the local groo installation
+    Doctor {
+        /// Print the end of groo's own diagnostic log
+        #[arg(long)]
+        show_log: bool,
+    },
+    /// Remove old groo data such as log files
+    Clean {
+        /// Remove log files of services that aren't running
+        #[arg(long)]
+        logs: bool,
+        /// Only remove files not modified within this long (e.g. 12h, 7d, 2w)
+        #[arg(long, value_name = "DURATION")]
+        older_than: Option<String>,
+        /// Show what would be removed without deleting anything
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Run a named task from groo.toml across services
+    Task {
+        /// Task name, as defined under [tasks] in groo.toml
+        name: String,
+    },
+    /// Project commands from [commands] in groo.toml, e.g. `groo db-reset`
+    #[command(external_subcommand)]
+    Custom(Vec<String>),
+    /// Compare service environments
+    Env {
+        #[command(subcommand)]
+        command: EnvCommand,
+    },
+    /// Save a session's startup as a named plan, or run a saved one
+    Plan {
+        #[command(subcommand)]
+        command: PlanCommand,
+    },
+    /// Add services from a Procfile or compose file to groo.toml
+    Import {
+        /// Format to convert from
+        #[arg(value_enum)]
+        source: commands::import::ImportSource,
+        /// File to read (defaults to Procfile.dev/Procfile or compose.yaml/docker-compose.yml in the repository root)
+        #[arg(long)]
+        file: Option<PathBuf>,
+    },
+    /// Write the services as a file for other tools, e.g. docker-compose
+    Export {
+        /// Format to write
+        #[arg(value_enum)]
+        format: commands::export::ExportFormat,
+        /// Write here instead of stdout
+        #[arg(long)]
+        file: Option<PathBuf>,
+    },
+    /// Scaffold a new app from a built-in template
+    New {
+        /// Template to start from
+        #[arg(value_enum)]
+        template: commands::new::AppTemplate,
+        /// Directory to create the app in, e.g. apps/spike
+        path: PathBuf,
+    },
+    /// Write a groo.toml for this repository, guided by what was discovered
+    Init,
+    /// Check service prerequisites declared in groo.toml and offer fixes
+    Setup {
+        /// Apply all available fixes without prompting
+        #[arg(short = 'y', long)]
+        yes: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum EnvCommand {
+    /// Show env vars that differ between two services, or between sessions of one
+    Diff {
+        /// Service to compare
+        service: String,
+        /// Service to compare against
+        #[arg(required_unless_present = "session")]
+        other: Option<String>,
+        /// Compare with an earlier session instead: "previous" or a number of sessions back
+        #[arg(long, conflicts_with = "other")]
+        session: Option<String>,
+    },
+}
+
+#[derive(Subcommand)]
+enum PlanCommand {
+    /// Save the services, env overrides and ports of the current or last session
+    Save {
+        /// Plan name, stored as .groo/plans/<name>.toml in the repository
+        name: String,
+    },
+    /// Start the services of a saved plan
+    Run {
+        /// Plan name
+        name: String,
     },
 }
 
 #[tokio::main]
 async fn main() -> Result<()> {
-    let cli = Cli::parse();
+    let cli = parse_cli();
+    output::init(cli.output, cli.quiet);
 
-    // Change working directory if specified
+    // Change working directory if specified. Everything else (project root, groo.toml,
+    // state and log paths) is resolved from the working directory afterwards.
     if let Some(workdir) = &cli.workdir {
-        std::env::set_current_dir(workdir)
+        let workdir = resolve_workdir(workdir)?;
+        std::env::set_current_dir(&workdir)
             .with_context(|| format!("Failed to change directory to: {}", workdir.display()))?;
     }
 
-    match cli.command {
-        Commands::Dev => commands::dev::run().await,
-        Commands::Restart => commands::restart::run().await,
-        Commands::List => commands::list::run(),
-        Commands::Status { project } => commands::status::run(project),
-        Commands::Open { service } => commands::open::run(&service),
-        Commands::Stop { project } => commands::stop::run(project),
-        Commands::Logs { lines, follow } => commands::logs::run(lines, follow).await,
+    // A groo that crashed may have left services running that state lost track of. Checked
+    // by the commands that start, inspect or stop services, as it reads every session.
+    if matches!(cli.command, Commands::Dev { .. } | Commands::Status { .. } | Commands::Stop { .. }) {
+        commands::reconcile::audit_orphans();
+    }
+
+    let result = match cli.command {
+        Commands::Dev {
+            deep,
+            project,
+            ephemeral,
+            services,
+            timestamps,
+            env,
+            scope,
+            keep_alive,
+        } => {
+            if let Some(query) = project {
+                let root = commands::recent::pick_project(&query)?;
+                std::env::set_current_dir(&root)
+                    .with_context(|| format!("Failed to change directory to: {}", root.display()))?;
+            }
+            commands::dev::run(deep, ephemeral, timestamps, env, services, scope, keep_alive).await
+        }
+        Commands::Restart { crashed, services } => commands::restart::run(crashed, services).await,
+        Commands::Recent => commands::recent::run(),
+        Commands::List { format } => commands::list::run(format),
+        Commands::Status {
+            project,
+            format,
+            sort,
+            verbose,
+        } => commands::status::run(project, format, sort, verbose).await,
+        Commands::Open {
+            service,
+            no_wait,
+            timeout,
+        } => commands::open::run(&service, no_wait, timeout).await,
+        Commands::Stop { project, services, all } => commands::stop::run(project, services, all),
+        Commands::Logs {
+            lines,
+            follow,
+            project,
+            since_mark,
+            services,
+        } => commands::logs::run(project, lines, follow, since_mark, services).await,
+        Commands::Mark { label, services } => commands::mark::run(&label, services),
+        Commands::Report {
+            project,
+            all,
+            json,
+            file,
+        } => commands::report::run(project, all, json, file),
+        Commands::Setup { yes } => commands::setup::run(yes),
+        Commands::Export { format, file } => commands::export::run(format, file),
+        Commands::Import { source, file } => commands::import::run(source, file),
+        Commands::New { template, path } => commands::new::run(template, &path),
+        Commands::Init => commands::init::run(),
+        Commands::Task { name } => commands::task::run(&name).await,
+        Commands::Custom(args) => commands::custom::run(&args[0], &args[1..]).await,
+        Commands::Env { command } => match command {
+            EnvCommand::Diff { service, other, session } => {
+                commands::env::diff(&service, other.as_deref(), session.as_deref())
+            }
+        },
+        Commands::Plan { command } => match command {
+            PlanCommand::Save { name } => commands::plan::save(&name),
+            PlanCommand::Run { name } => commands::plan::run(&name).await,
+        },
+        Commands::Discover { json, deep, scope } => commands::discover::run(json, deep, scope),
+        Commands::Path { service, list } => commands::path::run(service, list),
+        Commands::ShellInit { shell } => commands::shell_init::run(shell),
+        Commands::Cd { .. } => commands::shell_init::cd_without_integration(),
+        Commands::Doctor { show_log } => commands::doctor::run(show_log),
+        Commands::Clean {
+            logs,
+            older_than,
+            dry_run,
+        } => commands::clean::run(logs, older_than, dry_run),
+    };
+
+    // Scripts reading JSON get failures in the same shape as every other message
+    if let Err(e) = &result
+        && output::is_json()
+    {
+        output::error(format!("{:#}", e));
+        std::process::exit(1);
+    }
+    result
+}
+
+/// Parse the command line, listing the project's own commands in `--help`
+fn parse_cli() -> Cli {
+    let mut command = Cli::command();
+    // Finding them costs a git call, so only when help is asked for
+    if std::env::args().skip(1).any(|a| a == "-h" || a == "--help" || a == "help")
+        && let Some(help) = commands::custom::help_text()
+    {
+        command = command.after_help(help);
     }
+    Cli::from_arg_matches(&command.get_matches()).unwrap_or_else(|e| e.exit())
+}
+
+/// Expand a leading `~` (e.g. from a quoted argument) and make the path absolute
+fn resolve_workdir(workdir: &Path) -> Result<PathBuf> {
+    let expanded = match workdir.strip_prefix("~") {
+        Ok(rest) => dirs::home_dir()
+            .context("Could not determine home directory")?
+            .join(rest),
+        Err(_) => workdir.to_path_buf(),
+    };
+    expanded
+        .canonicalize()
+        .with_context(|| format!("Workdir not found: {}", workdir.display()))
 }
@@ -0,0 +1,273 @@
+use clap::ValueEnum;
+use console::style;
+use serde::Serialize;
+use std::fmt::Display;
+use std::sync::OnceLock;
+
+/// How commands render what they print
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+pub enum OutputMode {
+    /// Colors and symbols for people at a terminal
+    Human,
+    /// No colors, ASCII prefixes and stable wording for scripts and logs
+    Plain,
+    /// One JSON object per line
+    Json,
+}
+
+/// Severity of a status message
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Level {
+    Success,
+    Warning,
+    Error,
+    /// Progress, e.g. "Starting 2 service(s)..."
+    Step,
+}
+
+/// Where output goes. A command's results go to stdout; status messages go to stderr so
+/// they stay out of piped and redirected results.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Stream {
+    Stdout,
+    Stderr,
+}
+
+static MODE: OnceLock<OutputMode> = OnceLock::new();
+static QUIET: OnceLock<bool> = OnceLock::new();
+
+/// Pick the output mode for this run: the `--output` flag, then `GROO_OUTPUT`, then plain
+/// when `NO_COLOR` is set
+pub fn init(flag: Option<OutputMode>, quiet: bool) {
+    let mode = flag
+        .or_else(|| {
+            std::env::var("GROO_OUTPUT")
+                .ok()
+                .and_then(|value| OutputMode::from_str(&value, true).ok())
+        })
+        .unwrap_or_else(|| {
+            if std::env::var_os("NO_COLOR").is_some() {
+                OutputMode::Plain
+            } else {
+                OutputMode::Human
+            }
+        });
+    if mode != OutputMode::Human {
+        console::set_colors_enabled(false);
+        console::set_colors_enabled_stderr(false);
+    }
+    let _ = MODE.set(mode);
+    let _ = QUIET.set(quiet);
+}
+
+pub fn mode() -> OutputMode {
+    MODE.get().copied().unwrap_or(OutputMode::Human)
+}
+
+pub fn is_json() -> bool {
+    mode() == OutputMode::Json
+}
+
+fn is_quiet() -> bool {
+    QUIET.get().copied().unwrap_or(false)
+}
+
+pub fn success(message: impl Display) {
+    emit(Level::Success, 0, message);
+}
+
+pub fn warn(message: impl Display) {
+    emit(Level::Warning, 0, message);
+}
+
+pub fn error(message: impl Display) {
+    emit(Level::Error, 0, message);
+}
+
+pub fn step(message: impl Display) {
+    emit(Level::Step, 0, message);
+}
+
+/// A status message about one entry in a list, indented under its heading
+pub fn item(level: Level, message: impl Display) {
+    emit(level, 2, message);
+}
+
+/// A status message one level further in, e.g. a check under a service name
+pub fn subitem(level: Level, message: impl Display) {
+    emit(level, 4, message);
+}
+
+fn emit(level: Level, indent: usize, message: impl Display) {
+    if let Some(line) = render_message(mode(), is_quiet(), level, indent, &message.to_string()) {
+        write_line(Stream::Stderr, line);
+    }
+}
+
+/// A status message as printed in `mode`, or `None` when quiet hides it
+fn render_message(mode: OutputMode, quiet: bool, level: Level, indent: usize, message: &str) -> Option<String> {
+    // Quiet keeps errors only
+    if quiet && level != Level::Error {
+        return None;
+    }
+    let pad = " ".repeat(indent);
+    Some(match mode {
+        OutputMode::Human => {
+            let symbol = match level {
+                Level::Success => style("✓").green(),
+                Level::Warning => style("!").yellow(),
+                Level::Error => style("✗").red(),
+                Level::Step => style("→").cyan(),
+            };
+            let symbol = if indent == 0 { symbol.bold() } else { symbol };
+            format!("{}{} {}", pad, symbol, message)
+        }
+        OutputMode::Plain => {
+            let prefix = match level {
+                Level::Success => "ok: ",
+                Level::Warning => "warning: ",
+                Level::Error => "error: ",
+                Level::Step => "",
+            };
+            format!("{}{}{}", pad, prefix, console::strip_ansi_codes(message))
+        }
+        OutputMode::Json => {
+            #[derive(Serialize)]
+            struct Message<'a> {
+                level: Level,
+                message: &'a str,
+            }
+            let message = console::strip_ansi_codes(message);
+            serde_json::to_string(&Message {
+                level,
+                message: message.trim(),
+            })
+            .ok()?
+        }
+    })
+}
+
+fn write_line(stream: Stream, line: impl Display) {
+    match stream {
+        Stream::Stdout => println!("{}", line),
+        Stream::Stderr => eprintln!("{}", line),
+    }
+}
+
+/// A line of a command's primary output (tables, listings). Skipped in JSON mode,
+/// where commands emit [`data`] instead.
+pub fn line(text: impl Display) {
+    if shows_lines(mode()) {
+        write_line(Stream::Stdout, text);
+    }
+}
+
+/// Blank separator line, for human and plain output only
+pub fn blank() {
+    if shows_decoration(mode(), is_quiet()) {
+        write_line(Stream::Stdout, "");
+    }
+}
+
+/// A bold section title, for human and plain output only
+pub fn heading(text: impl Display) {
+    if shows_decoration(mode(), is_quiet()) {
+        write_line(Stream::Stdout, style(text).bold());
+    }
+}
+
+/// Output the caller has already rendered for its audience, printed as-is in every mode:
+/// `--format` lines, a service's own log lines, names for shell completion
+pub fn raw(stream: Stream, text: impl Display) {
+    write_line(stream, text);
+}
+
+/// A whole document a command produces, like a shell script or a compose file, printed
+/// as-is in every mode
+pub fn document(text: &str) {
+    print!("{}", text);
+}
+
+fn shows_lines(mode: OutputMode) -> bool {
+    mode != OutputMode::Json
+}
+
+fn shows_decoration(mode: OutputMode, quiet: bool) -> bool {
+    shows_lines(mode) && !quiet
+}
+
+/// Structured result of a command, printed as JSON in JSON mode
+pub fn data<T: Serialize>(value: &T) {
+    print_json(value);
+}
+
+fn print_json<T: Serialize>(value: &T) {
+    if let Ok(json) = serde_json::to_string(value) {
+        write_line(Stream::Stdout, json);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn render(mode: OutputMode, level: Level, message: &str) -> String {
+        render_message(mode, false, level, 0, message).unwrap()
+    }
+
+    #[test]
+    fn human_messages_lead_with_a_symbol() {
+        console::set_colors_enabled(false);
+        assert_eq!(render(OutputMode::Human, Level::Success, "Done"), "✓ Done");
+        assert_eq!(render(OutputMode::Human, Level::Warning, "Careful"), "! Careful");
+        assert_eq!(render(OutputMode::Human, Level::Error, "Failed"), "✗ Failed");
+        assert_eq!(render(OutputMode::Human, Level::Step, "Starting"), "→ Starting");
+    }
+
+    #[test]
+    fn plain_messages_use_ascii_prefixes_without_colors() {
+        assert_eq!(render(OutputMode::Plain, Level::Success, "Done"), "ok: Done");
+        assert_eq!(render(OutputMode::Plain, Level::Warning, "Careful"), "warning: Careful");
+        assert_eq!(render(OutputMode::Plain, Level::Error, "Failed"), "error: Failed");
+        assert_eq!(render(OutputMode::Plain, Level::Step, "Starting"), "Starting");
+        assert_eq!(render(OutputMode::Plain, Level::Warning, "\x1b[31mred\x1b[0m"), "warning: red");
+    }
+
+    #[test]
+    fn json_messages_are_objects_with_level_and_message() {
+        let line = render(OutputMode::Json, Level::Warning, "  \x1b[1mCareful\x1b[0m ");
+        let value: serde_json::Value = serde_json::from_str(&line).unwrap();
+        assert_eq!(value, serde_json::json!({ "level": "warning", "message": "Careful" }));
+        assert_eq!(
+            render(OutputMode::Json, Level::Step, "Starting"),
+            r#"{"level":"step","message":"Starting"}"#
+        );
+    }
+
+    #[test]
+    fn items_are_indented() {
+        assert_eq!(render_message(OutputMode::Plain, false, Level::Error, 2, "x").unwrap(), "  error: x");
+        assert_eq!(render_message(OutputMode::Plain, false, Level::Step, 4, "x").unwrap(), "    x");
+    }
+
+    #[test]
+    fn quiet_keeps_only_errors() {
+        for mode in [OutputMode::Human, OutputMode::Plain, OutputMode::Json] {
+            assert!(render_message(mode, true, Level::Success, 0, "x").is_none());
+            assert!(render_message(mode, true, Level::Warning, 0, "x").is_none());
+            assert!(render_message(mode, true, Level::Step, 0, "x").is_none());
+            assert!(render_message(mode, true, Level::Error, 0, "x").is_some());
+            assert!(!shows_decoration(mode, true));
+        }
+    }
+
+    #[test]
+    fn json_mode_leaves_out_lines_and_decoration() {
+        assert!(shows_lines(OutputMode::Human));
+        assert!(shows_lines(OutputMode::Plain));
+        assert!(!shows_lines(OutputMode::Json));
+        assert!(shows_decoration(OutputMode::Plain, false));
+        assert!(!shows_decoration(OutputMode::Json, false));
+    }
+}
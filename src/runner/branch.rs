@@ -0,0 +1,86 @@
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// A checkout that moved HEAD to a different branch or commit
+pub struct BranchSwitch {
+    pub from: String,
+    pub to: String,
+    /// Files that differ between the old and new commit, relative to the repository root
+    pub changed_files: Vec<PathBuf>,
+}
+
+/// Polls `.git/HEAD` for branch switches while services are running
+pub struct BranchWatcher {
+    git_root: PathBuf,
+    head_file: PathBuf,
+    head: String,
+    commit: Option<String>,
+}
+
+impl BranchWatcher {
+    pub fn new(git_root: &Path) -> Option<Self> {
+        let git_dir = git(git_root, &["rev-parse", "--absolute-git-dir"])?;
+        let head_file = PathBuf::from(git_dir).join("HEAD");
+        let head = std::fs::read_to_string(&head_file).ok()?;
+        Some(Self {
+            git_root: git_root.to_path_buf(),
+            head_file,
+            head,
+            commit: git(git_root, &["rev-parse", "HEAD"]),
+        })
+    }
+
+    /// Return the switch if HEAD changed since the last poll
+    pub fn poll(&mut self) -> Option<BranchSwitch> {
+        let head = std::fs::read_to_string(&self.head_file).ok()?;
+        if head == self.head {
+            return None;
+        }
+        let from = describe(&self.head);
+        self.head = head;
+
+        let commit = git(&self.git_root, &["rev-parse", "HEAD"]);
+        let changed_files = match (&self.commit, &commit) {
+            (Some(old), Some(new)) => git(&self.git_root, &["diff", "--name-only", old, new])
+                .map(|out| out.lines().map(PathBuf::from).collect())
+                .unwrap_or_default(),
+            _ => Vec::new(),
+        };
+        self.commit = commit;
+
+        Some(BranchSwitch {
+            from,
+            to: describe(&self.head),
+            changed_files,
+        })
+    }
+
+    /// Whether anything under `dir` differs between the two sides of a switch
+    pub fn touches(&self, switch: &BranchSwitch, dir: &Path) -> bool {
+        switch
+            .changed_files
+            .iter()
+            .any(|file| self.git_root.join(file).starts_with(dir))
+    }
+}
+
+/// Branch name from a HEAD file, or the abbreviated SHA on a detached HEAD
+fn describe(head: &str) -> String {
+    let head = head.trim();
+    match head.strip_prefix("ref: refs/heads/") {
+        Some(branch) => branch.to_string(),
+        None => head.chars().take(7).collect(),
+    }
+}
+
+fn git(git_root: &Path, args: &[&str]) -> Option<String> {
+    let output = Command::new("git")
+        .args(args)
+        .current_dir(git_root)
+        .output()
+        .ok()?;
+    output
+        .status
+        .success()
+        .then(|| String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
@@ -0,0 +1,59 @@
+use std::collections::{HashMap, VecDeque};
+use std::io::{Read, Seek, SeekFrom};
+use std::path::Path;
+use tokio::time::{Duration, Instant};
+
+use crate::state::parse_log_line;
+
+/// Lines of a crash-looping service's log kept to explain its last crash
+const CRASH_TAIL_LINES: usize = 15;
+
+/// How much of the end of the log is read to find them
+const CRASH_TAIL_BYTES: u64 = 16 * 1024;
+
+/// Recent crash restarts per service, for telling a service that crashed once from one
+/// that crashes again every time it comes up
+#[derive(Default)]
+pub struct CrashRestarts {
+    restarts: HashMap<String, VecDeque<Instant>>,
+}
+
+impl CrashRestarts {
+    /// Count another restart of a crashed service, or return `None` if it has already been
+    /// restarted `max` times within `window` and is crash-looping. Otherwise returns which
+    /// restart within the window this is.
+    pub fn allow(&mut self, service_name: &str, max: u32, window: Duration) -> Option<usize> {
+        let restarts = self.restarts.entry(service_name.to_string()).or_default();
+        while restarts.front().is_some_and(|at| at.elapsed() >= window) {
+            restarts.pop_front();
+        }
+        if restarts.len() >= max as usize {
+            return None;
+        }
+        restarts.push_back(Instant::now());
+        Some(restarts.len())
+    }
+}
+
+/// The last lines a service logged, without their sequence numbers
+pub fn crash_tail(log_file: &Path) -> Vec<String> {
+    let Ok(mut file) = std::fs::File::open(log_file) else {
+        return Vec::new();
+    };
+    let len = file.metadata().map(|m| m.len()).unwrap_or(0);
+    let start = len.saturating_sub(CRASH_TAIL_BYTES);
+    let mut bytes = Vec::new();
+    if file.seek(SeekFrom::Start(start)).is_err() || file.read_to_end(&mut bytes).is_err() {
+        return Vec::new();
+    }
+    let content = String::from_utf8_lossy(&bytes);
+    let mut lines: Vec<&str> = content.lines().collect();
+    // Reading from the middle of the file likely started mid-line
+    if start > 0 && !lines.is_empty() {
+        lines.remove(0);
+    }
+    lines[lines.len().saturating_sub(CRASH_TAIL_LINES)..]
+        .iter()
+        .map(|line| parse_log_line(line).1.to_string())
+        .collect()
+}
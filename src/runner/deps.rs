@@ -0,0 +1,168 @@
+use console::style;
+use std::collections::HashMap;
+use tokio::sync::mpsc;
+use tokio::task::JoinSet;
+use tokio::time::{Duration, Instant};
+
+use super::health::{is_port_ready, is_reachable};
+use crate::config::ExternalDependency;
+use crate::output;
+
+/// How long `groo dev` holds back a service for its external dependencies before starting it anyway
+const UPSTREAM_WAIT_TIMEOUT: Duration = Duration::from_secs(30);
+const UPSTREAM_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Where a dependency is probed
+#[derive(Debug, Clone)]
+pub enum Endpoint {
+    /// A port of a service in this project
+    Local(u16),
+    /// A `host:port` outside the repository
+    Remote(String),
+}
+
+impl Endpoint {
+    pub async fn is_up(&self) -> bool {
+        match self {
+            Endpoint::Local(port) => is_port_ready(*port).await,
+            Endpoint::Remote(address) => is_reachable(address).await,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Availability {
+    /// Not seen accepting connections yet, e.g. still starting
+    Waiting,
+    Up,
+    /// Was up, then stopped accepting connections
+    Down,
+}
+
+/// Watches the ports of services others depend on, to notice when one comes back after
+/// going down (crashed and restarted, or restarted by `groo restart` in another terminal).
+///
+/// Probes run in a background task so a slow or unreachable upstream never holds up the
+/// supervisor loop; their results are picked up on the next poll.
+pub struct DependencyWatcher {
+    dependencies: HashMap<String, Availability>,
+    results_tx: mpsc::UnboundedSender<Vec<(String, bool)>>,
+    results_rx: mpsc::UnboundedReceiver<Vec<(String, bool)>>,
+    probing: bool,
+}
+
+impl Default for DependencyWatcher {
+    fn default() -> Self {
+        let (results_tx, results_rx) = mpsc::unbounded_channel();
+        Self {
+            dependencies: HashMap::new(),
+            results_tx,
+            results_rx,
+            probing: false,
+        }
+    }
+}
+
+impl DependencyWatcher {
+    /// Start probing each `(name, endpoint)` in the background, unless the last round is
+    /// still running
+    pub fn probe(&mut self, dependencies: Vec<(String, Endpoint)>) {
+        if self.probing || dependencies.is_empty() {
+            return;
+        }
+        self.probing = true;
+        let results_tx = self.results_tx.clone();
+        tokio::spawn(async move {
+            let mut probes = JoinSet::new();
+            for (name, endpoint) in dependencies {
+                probes.spawn(async move { (name, endpoint.is_up().await) });
+            }
+            let results = probes.join_all().await;
+            let _ = results_tx.send(results);
+        });
+    }
+
+    /// Dependencies that are healthy again, according to the probes finished since the
+    /// last call
+    pub fn recovered(&mut self) -> Vec<String> {
+        let mut recovered = Vec::new();
+        while let Ok(results) = self.results_rx.try_recv() {
+            self.probing = false;
+            for (name, up) in results {
+                let previous = self
+                    .dependencies
+                    .get(&name)
+                    .copied()
+                    .unwrap_or(Availability::Waiting);
+                let next = match (previous, up) {
+                    (Availability::Down, true) => {
+                        recovered.push(name.clone());
+                        Availability::Up
+                    }
+                    (_, true) => Availability::Up,
+                    (Availability::Waiting, false) => Availability::Waiting,
+                    (_, false) => Availability::Down,
+                };
+                self.dependencies.insert(name, next);
+            }
+        }
+        recovered
+    }
+}
+
+/// Wait in the background for the external dependencies of held-back services, given by
+/// index. Each index is sent once all of its dependencies accept connections, or once
+/// the wait gives up so a flaky upstream doesn't keep the service from starting at all.
+pub fn release_when_up(waiting: Vec<Vec<ExternalDependency>>) -> mpsc::UnboundedReceiver<usize> {
+    let (tx, rx) = mpsc::unbounded_channel();
+    tokio::spawn(async move {
+        let mut unique: Vec<ExternalDependency> = Vec::new();
+        for dependency in waiting.iter().flatten() {
+            if !unique.contains(dependency) {
+                unique.push(dependency.clone());
+            }
+        }
+        let mut probes = JoinSet::new();
+        for dependency in unique {
+            probes.spawn(async move {
+                wait_for_upstream(&dependency).await;
+                dependency
+            });
+        }
+
+        let mut resolved: Vec<ExternalDependency> = Vec::new();
+        let mut released = vec![false; waiting.len()];
+        while let Some(Ok(dependency)) = probes.join_next().await {
+            resolved.push(dependency);
+            for (index, dependencies) in waiting.iter().enumerate() {
+                if !released[index] && dependencies.iter().all(|d| resolved.contains(d)) {
+                    released[index] = true;
+                    let _ = tx.send(index);
+                }
+            }
+        }
+    });
+    rx
+}
+
+/// Wait for an external dependency to accept connections, up to [`UPSTREAM_WAIT_TIMEOUT`]
+async fn wait_for_upstream(dependency: &ExternalDependency) {
+    let endpoint = Endpoint::Remote(dependency.address.clone());
+    if endpoint.is_up().await {
+        return;
+    }
+
+    output::step(format!("Waiting for {}...", dependency.label));
+    let deadline = Instant::now() + UPSTREAM_WAIT_TIMEOUT;
+    while Instant::now() < deadline {
+        tokio::time::sleep(UPSTREAM_POLL_INTERVAL).await;
+        if endpoint.is_up().await {
+            output::item(output::Level::Success, format!("{} is up", dependency.label));
+            return;
+        }
+    }
+    output::item(
+        output::Level::Warning,
+        format!("{} is unreachable, starting anyway", style(&dependency.label).bold()),
+    );
+}
@@ -0,0 +1,59 @@
+use tokio::net::TcpStream;
+use tokio::time::{timeout, Duration};
+
+use crate::discovery::FrameworkType;
+
+/// How long to wait on a remote address before counting it as down
+const REMOTE_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Check whether something accepts TCP connections on a local port.
+///
+/// Resolves `localhost` so servers bound only to `::1` or `127.0.0.1` are both found.
+pub async fn is_port_ready(port: u16) -> bool {
+    matches!(
+        timeout(Duration::from_millis(250), TcpStream::connect(("localhost", port))).await,
+        Ok(Ok(_))
+    )
+}
+
+/// Check whether a `host:port` address accepts TCP connections, e.g. an upstream API
+pub async fn is_reachable(address: &str) -> bool {
+    matches!(timeout(REMOTE_TIMEOUT, TcpStream::connect(address)).await, Ok(Ok(_)))
+}
+
+/// When to probe a starting service's port, and when to give up on it
+#[derive(Debug, Clone, Copy)]
+pub struct ProbeSchedule {
+    /// Wait before the first probe, for servers known to bind late
+    pub initial_delay: Duration,
+    /// Probes start at `FIRST_INTERVAL` and double up to this
+    pub max_interval: Duration,
+    /// Report the service as failed if it isn't listening by then
+    pub timeout: Duration,
+}
+
+const FIRST_INTERVAL: Duration = Duration::from_millis(250);
+
+impl ProbeSchedule {
+    /// Defaults by framework: Next.js compiles before it binds, Vite binds almost at once
+    pub fn for_framework(framework: &FrameworkType) -> Self {
+        let (initial_delay, max_interval, timeout) = match framework {
+            FrameworkType::NextJs => (1000, 5000, 120),
+            FrameworkType::Vite => (0, 2000, 30),
+            FrameworkType::Wrangler => (500, 5000, 60),
+            FrameworkType::Unknown => (0, 5000, 60),
+        };
+        Self {
+            initial_delay: Duration::from_millis(initial_delay),
+            max_interval: Duration::from_millis(max_interval),
+            timeout: Duration::from_secs(timeout),
+        }
+    }
+
+    /// Delay before the probe after `attempt` failed ones
+    pub fn interval(&self, attempt: u32) -> Duration {
+        FIRST_INTERVAL
+            .saturating_mul(1 << attempt.min(16))
+            .min(self.max_interval)
+    }
+}
@@ -0,0 +1,71 @@
+use std::process::Stdio;
+use tokio::process::Command;
+
+use crate::config::HooksConfig;
+use crate::output;
+use crate::state::Session;
+
+#[derive(Debug, Clone, Copy)]
+pub enum HookEvent {
+    Start,
+    Ready,
+    Crash,
+    Stall,
+    Stop,
+}
+
+impl HookEvent {
+    fn as_str(&self) -> &'static str {
+        match self {
+            HookEvent::Start => "start",
+            HookEvent::Ready => "ready",
+            HookEvent::Crash => "crash",
+            HookEvent::Stall => "stall",
+            HookEvent::Stop => "stop",
+        }
+    }
+
+    fn command<'a>(&self, hooks: &'a HooksConfig) -> Option<&'a str> {
+        match self {
+            HookEvent::Start => hooks.on_start.as_deref(),
+            HookEvent::Ready => hooks.on_ready.as_deref(),
+            HookEvent::Crash => hooks.on_crash.as_deref(),
+            HookEvent::Stall => hooks.on_stall.as_deref(),
+            HookEvent::Stop => hooks.on_stop.as_deref(),
+        }
+    }
+}
+
+/// Run the configured hook for an event in the background.
+///
+/// `vars` carries event-specific context (service name, port, exit code, ...) and is
+/// exported alongside `GROO_EVENT`, `GROO_PROJECT` and `GROO_PROJECT_PATH`.
+pub fn fire_hook(hooks: &HooksConfig, event: HookEvent, session: &Session, vars: &[(&str, String)]) {
+    let Some(command) = event.command(hooks) else {
+        return;
+    };
+
+    let mut cmd = Command::new("sh");
+    cmd.arg("-c")
+        .arg(command)
+        .current_dir(&session.path)
+        .env("GROO_EVENT", event.as_str())
+        .env("GROO_PROJECT", &session.project)
+        .env("GROO_PROJECT_PATH", &session.path)
+        .env("GROO_SESSION", &session.id)
+        .stdin(Stdio::null());
+    for (key, value) in vars {
+        cmd.env(key, value);
+    }
+
+    match cmd.spawn() {
+        Ok(mut child) => {
+            tokio::spawn(async move {
+                let _ = child.wait().await;
+            });
+        }
+        Err(e) => {
+            output::error(format!("Failed to run on_{} hook: {}", event.as_str(), e));
+        }
+    }
+}
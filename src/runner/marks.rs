@@ -0,0 +1,40 @@
+use std::io::{BufRead, BufReader, Seek, SeekFrom};
+use std::path::PathBuf;
+
+use crate::state::{marks_file, Mark};
+
+/// Picks up marks made with `groo mark` while services are running
+pub struct MarkWatcher {
+    path: PathBuf,
+    offset: u64,
+}
+
+impl MarkWatcher {
+    /// Start from the end of the project's marks file, so only new marks are seen
+    pub fn new(project_name: &str) -> Self {
+        let path = marks_file(project_name);
+        let offset = std::fs::metadata(&path).map(|m| m.len()).unwrap_or(0);
+        Self { path, offset }
+    }
+
+    /// Marks added since the last poll
+    pub fn poll(&mut self) -> Vec<Mark> {
+        let Ok(mut file) = std::fs::File::open(&self.path) else {
+            return Vec::new();
+        };
+        let len = file.metadata().map(|m| m.len()).unwrap_or(0);
+        if len < self.offset {
+            // Removed or truncated since; start over
+            self.offset = 0;
+        }
+        if len == self.offset || file.seek(SeekFrom::Start(self.offset)).is_err() {
+            return Vec::new();
+        }
+        self.offset = len;
+        BufReader::new(file)
+            .lines()
+            .map_while(Result::ok)
+            .filter_map(|line| serde_json::from_str(&line).ok())
+            .collect()
+    }
+}
@@ -1,5 +1,15 @@
+mod branch;
+mod crash;
+mod deps;
+mod health;
+mod hooks;
+mod marks;
 mod output;
+mod priority;
 mod process;
+mod signals;
 
+pub use health::{is_port_ready, is_reachable};
 pub use output::*;
 pub use process::*;
+pub use signals::{set_keep_alive, shutdown_requested};
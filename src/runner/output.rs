@@ -1,4 +1,9 @@
-use console::Style;
+use console::{style, Style};
+use serde::Serialize;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::output::{self, Stream};
 
 const COLORS: &[fn() -> Style] = &[
     || Style::new().cyan(),
@@ -17,15 +22,141 @@ pub fn get_color_for_index(index: usize) -> Style {
     COLORS[index % COLORS.len()]()
 }
 
+static TIMESTAMPS: AtomicBool = AtomicBool::new(false);
+
+/// Prefix service output on the console with the local time. Log files are unaffected.
+pub fn set_timestamps(enabled: bool) {
+    TIMESTAMPS.store(enabled, Ordering::Relaxed);
+}
+
 pub fn format_log_line(service_name: &str, line: &str, color: &Style) -> String {
     let prefix = color.apply_to(format!("[{}]", service_name));
-    format!("{} {}", prefix, line)
+    match timestamp() {
+        Some(time) => format!("{} {} {}", style(time).dim(), prefix, line),
+        None => format!("{} {}", prefix, line),
+    }
 }
 
 pub fn print_service_log(service_name: &str, line: &str, color: &Style) {
-    println!("{}", format_log_line(service_name, line, color));
+    if output::is_json() {
+        print_json_log(service_name, line, "stdout");
+    } else {
+        output::raw(Stream::Stdout, format_log_line(service_name, line, color));
+    }
 }
 
 pub fn print_service_error(service_name: &str, line: &str, color: &Style) {
-    eprintln!("{}", format_log_line(service_name, line, color));
+    if output::is_json() {
+        print_json_log(service_name, line, "stderr");
+    } else {
+        output::raw(Stream::Stderr, format_log_line(service_name, line, color));
+    }
+}
+
+fn print_json_log(service_name: &str, line: &str, stream: &str) {
+    #[derive(Serialize)]
+    struct LogLine<'a> {
+        #[serde(skip_serializing_if = "Option::is_none")]
+        time: Option<String>,
+        service: &'a str,
+        stream: &'a str,
+        line: &'a str,
+    }
+    output::data(&LogLine {
+        time: timestamp(),
+        service: service_name,
+        stream,
+        line,
+    });
+}
+
+/// Current local time as `HH:MM:SS`
+pub fn local_time() -> String {
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default();
+    let time = calendar(now.as_secs());
+    format!("{:02}:{:02}:{:02}", time.hour, time.minute, time.second)
+}
+
+/// Current local date and time as `YYYY-MM-DD HH:MM:SS.mmm`
+pub fn local_datetime() -> String {
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default();
+    let time = calendar(now.as_secs());
+    format!(
+        "{}-{:02}-{:02} {:02}:{:02}:{:02}.{:03}",
+        time.year,
+        time.month,
+        time.day,
+        time.hour,
+        time.minute,
+        time.second,
+        now.subsec_millis()
+    )
+}
+
+/// `HH:MM:SS.mmm` in local time, when timestamps are enabled
+fn timestamp() -> Option<String> {
+    if !TIMESTAMPS.load(Ordering::Relaxed) {
+        return None;
+    }
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default();
+    let time = calendar(now.as_secs());
+    Some(format!(
+        "{:02}:{:02}:{:02}.{:03}",
+        time.hour,
+        time.minute,
+        time.second,
+        now.subsec_millis()
+    ))
+}
+
+struct Calendar {
+    year: i64,
+    month: u64,
+    day: u64,
+    hour: u64,
+    minute: u64,
+    second: u64,
+}
+
+#[cfg(unix)]
+fn calendar(secs: u64) -> Calendar {
+    let time = secs as libc::time_t;
+    // SAFETY: localtime_r only writes into the tm we hand it
+    let mut tm: libc::tm = unsafe { std::mem::zeroed() };
+    if unsafe { libc::localtime_r(&time, &mut tm) }.is_null() {
+        return utc_calendar(secs);
+    }
+    Calendar {
+        year: tm.tm_year as i64 + 1900,
+        month: tm.tm_mon as u64 + 1,
+        day: tm.tm_mday as u64,
+        hour: tm.tm_hour as u64,
+        minute: tm.tm_min as u64,
+        second: tm.tm_sec as u64,
+    }
+}
+
+#[cfg(not(unix))]
+fn calendar(secs: u64) -> Calendar {
+    utc_calendar(secs)
+}
+
+fn utc_calendar(secs: u64) -> Calendar {
+    // Days since the epoch to a civil date (Howard Hinnant's algorithm)
+    let z = (secs / 86400) as i64 + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u64;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u64;
+    Calendar {
+        year: yoe + era * 400 + i64::from(month <= 2),
+        month,
+        day,
+        hour: (secs / 3600) % 24,
+        minute: (secs / 60) % 60,
+        second: secs % 60,
+    }
 }
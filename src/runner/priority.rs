@@ -0,0 +1,78 @@
+use tokio::process::Command;
+
+#[cfg(unix)]
+use crate::config::IoPriority;
+use crate::config::Priority;
+
+/// Have the process start with `priority`. It's set in the child before the dev command
+/// runs, so everything the command spawns inherits it.
+#[cfg(unix)]
+pub fn apply(cmd: &mut Command, priority: &Priority) {
+    if priority.is_default() {
+        return;
+    }
+    let nice = priority.nice();
+    let io = priority.io;
+    // SAFETY: only async-signal-safe syscalls run between fork and exec. Failures are
+    // ignored here and reported by `applied` once the process runs.
+    unsafe {
+        cmd.pre_exec(move || {
+            if let Some(nice) = nice {
+                libc::setpriority(libc::PRIO_PROCESS, 0, nice);
+            }
+            if let Some(io) = io {
+                set_io_priority(io);
+            }
+            Ok(())
+        });
+    }
+}
+
+#[cfg(not(unix))]
+pub fn apply(cmd: &mut Command, priority: &Priority) {
+    // Windows priority classes
+    const IDLE: u32 = 0x0040;
+    const BELOW_NORMAL: u32 = 0x4000;
+    const ABOVE_NORMAL: u32 = 0x8000;
+    const HIGH: u32 = 0x0080;
+    let class = match priority.nice() {
+        Some(nice) if nice >= 15 => IDLE,
+        Some(nice) if nice > 0 => BELOW_NORMAL,
+        Some(nice) if nice <= -15 => HIGH,
+        Some(nice) if nice < 0 => ABOVE_NORMAL,
+        _ => return,
+    };
+    cmd.creation_flags(class);
+}
+
+#[cfg(target_os = "linux")]
+fn set_io_priority(io: IoPriority) {
+    // ioprio_set(IOPRIO_WHO_PROCESS, self, class << 13 | level)
+    const IOPRIO_WHO_PROCESS: libc::c_int = 1;
+    let value: libc::c_int = match io {
+        IoPriority::Idle => 3 << 13,
+        IoPriority::Low => (2 << 13) | 7,
+        IoPriority::Normal => (2 << 13) | 4,
+    };
+    unsafe {
+        libc::syscall(libc::SYS_ioprio_set, IOPRIO_WHO_PROCESS, 0, value);
+    }
+}
+
+#[cfg(all(unix, not(target_os = "linux")))]
+fn set_io_priority(_io: IoPriority) {}
+
+/// Whether a running process got its `nice` value; raising priority usually needs root
+#[cfg(unix)]
+pub fn applied(pid: u32, priority: &Priority) -> bool {
+    let Some(nice) = priority.nice() else {
+        return true;
+    };
+    // SAFETY: getpriority only reads
+    unsafe { libc::getpriority(libc::PRIO_PROCESS, pid as libc::id_t) == nice }
+}
+
+#[cfg(not(unix))]
+pub fn applied(_pid: u32, _priority: &Priority) -> bool {
+    true
+}
@@ -1,55 +1,303 @@
 use anyhow::Result;
-use console::Style;
+use console::{style, Style, Term};
+use dialoguer::Confirm;
+use std::collections::BTreeMap;
 use std::path::{Path, PathBuf};
 use std::process::Stdio;
 use std::sync::Arc;
+use std::time::SystemTime;
 use tokio::fs::OpenOptions;
 use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
 use tokio::process::{Child, Command};
-use tokio::sync::{broadcast, Mutex};
+use tokio::sync::{broadcast, oneshot, Mutex};
+use tokio::time::{Duration, Instant};
 
+use super::branch::{BranchSwitch, BranchWatcher};
+use super::crash::{crash_tail, CrashRestarts};
+use super::deps::{release_when_up, DependencyWatcher, Endpoint};
+use super::health::{is_port_ready, ProbeSchedule};
+use super::hooks::{fire_hook, HookEvent};
+use super::marks::MarkWatcher;
 use super::output::{print_service_error, print_service_log};
+use super::priority;
+use super::signals::{continue_services, keep_alive, next_signal, resize, suspend, SessionSignal, SessionSignals};
+use crate::backend::backend;
+use crate::diag;
+use crate::format;
+use crate::config::{
+    self, BranchSwitchAction, CrashAction, DependencyAction, ExternalDependency, ProjectConfig, StallAction,
+};
+use crate::discovery::{service_url, Scheme, Service};
+use crate::output;
+use crate::select::theme;
+use crate::state::{encode_log_line, next_log_seq, now_millis, ExitEvent, ReadyEndpoint, ServiceState, Session, State};
+
+/// How often the ports of services others depend on are probed
+const DEPENDENCY_POLL_INTERVAL: tokio::time::Duration = tokio::time::Duration::from_millis(500);
+
+/// How often `.git/HEAD` is checked when `on_branch_switch` is enabled
+const BRANCH_POLL_INTERVAL: tokio::time::Duration = tokio::time::Duration::from_secs(1);
+
+/// How often the project's marks file is checked for `groo mark`
+const MARK_POLL_INTERVAL: tokio::time::Duration = tokio::time::Duration::from_millis(500);
+
+/// How often log files are checked for services with a `stall_timeout`
+const STALL_POLL_INTERVAL: tokio::time::Duration = tokio::time::Duration::from_secs(1);
+
+/// How long shutdown waits quietly before pointing out that Ctrl+C again forces it
+const SHUTDOWN_HINT_AFTER: Duration = Duration::from_secs(3);
+
+/// How long a restarted service's old process gets to free its port
+const PORT_RELEASE_TIMEOUT: tokio::time::Duration = tokio::time::Duration::from_secs(5);
+
+/// How long a crashed service's last output gets to reach its log before the tail is read
+const OUTPUT_SETTLE_TIME: Duration = Duration::from_millis(200);
+
+/// Wait before the first restart of a crashed service, doubled for each further restart
+/// within its `restart_window`
+const CRASH_RESTART_BACKOFF: Duration = Duration::from_millis(500);
+
+/// Longest wait before restarting a crashed service
+const MAX_CRASH_RESTART_BACKOFF: Duration = Duration::from_secs(8);
 
 pub struct ProcessHandle {
     pub name: String,
     pub child: Child,
     pub color: Style,
+    pub port: Option<u16>,
+    pub scheme: Scheme,
+    pub base_path: Option<String>,
+    pub started_at: Instant,
+    pub readiness: Readiness,
+    /// Failed readiness probes so far, for backing off
+    probe_attempts: u32,
+    next_probe: Instant,
+    /// What the process was spawned from, so it can be respawned in place
+    service: Service,
+    log_file: PathBuf,
+    overrides: Overrides,
+    /// When the log file was last seen changing, and its modification time then
+    output_at: Instant,
+    log_modified: Option<SystemTime>,
+    /// Reported as silent for longer than its `stall_timeout`
+    stalled: bool,
+}
+
+/// A service held back until the external dependencies it declares accept connections,
+/// started by [`wait_for_processes`] once they do
+pub struct Deferred {
+    pub service: Service,
+    pub color: Style,
+    pub log_file: PathBuf,
+    pub overrides: Overrides,
+    pub dependencies: Vec<ExternalDependency>,
+}
+
+/// What happens to a crashed service once its follow-up is due
+enum AfterCrash {
+    /// Start it again, once its old process lets go of its port or `port_deadline` passes
+    Restart {
+        service: Box<Deferred>,
+        port: Option<u16>,
+        port_deadline: Instant,
+    },
+    /// Report it as crash-looping, with the output that led up to the crash
+    ReportLoop {
+        name: String,
+        color: Style,
+        log_file: PathBuf,
+        max: u32,
+        window: Duration,
+    },
+}
+
+/// A crashed service's follow-up, taken by [`follow_up_crashes`] from `due` on so the
+/// supervisor loop never waits on it
+struct PendingCrash {
+    due: Instant,
+    after: AfterCrash,
+}
+
+/// A branch-switch question waiting on the user, answered on a thread of its own so the
+/// supervisor loop keeps running meanwhile
+struct BranchPrompt {
+    answer: oneshot::Receiver<bool>,
+    /// Services to restart if the answer is yes
+    services: Vec<String>,
+}
+
+/// Where a service is in coming up, as far as its port tells
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Readiness {
+    /// Spawned, port not accepting connections yet
+    Starting,
+    Ready,
+    /// Still not listening when its probe schedule ran out; probing continues in case it's
+    /// just slow
+    Failed,
+}
+
+/// Per-run changes to how a service starts, from `--ephemeral`, `--env` or a saved plan
+#[derive(Debug, Clone, Default)]
+pub struct Overrides {
+    /// Listen here instead of the detected port, via `PORT` and the framework's port flag
+    pub port: Option<u16>,
+    /// Extra environment variables for the dev script
+    pub env: BTreeMap<String, String>,
 }
 
 impl ProcessHandle {
     pub fn pid(&self) -> Option<u32> {
         self.child.id()
     }
+
+    /// Move the readiness and stall clocks forward, e.g. past time the session spent suspended
+    pub fn shift_clock(&mut self, by: Duration) {
+        self.started_at += by;
+        self.next_probe += by;
+        self.output_at += by;
+    }
 }
 
+/// Spawn a service's dev script with any per-run overrides applied
 pub async fn spawn_service(
-    name: &str,
-    path: &Path,
-    _command: &str,
+    service: &Service,
     color: Style,
     log_file: PathBuf,
+    overrides: Overrides,
 ) -> Result<ProcessHandle> {
-    // Ensure logs directory exists and truncate log file
-    if let Some(parent) = log_file.parent() {
-        tokio::fs::create_dir_all(parent).await?;
-    }
-    let file = OpenOptions::new()
-        .create(true)
-        .write(true)
-        .truncate(true)
-        .open(&log_file)
-        .await?;
-    let log_writer = Arc::new(Mutex::new(file));
+    let child = spawn_script(service, "dev", &color, Some(&log_file), &overrides).await?;
+    let started_at = Instant::now();
+
+    Ok(ProcessHandle {
+        name: service.name.clone(),
+        child,
+        color,
+        port: overrides.port.or(service.port),
+        scheme: service.scheme,
+        base_path: service.base_path.clone(),
+        started_at,
+        readiness: Readiness::Starting,
+        probe_attempts: 0,
+        next_probe: started_at + ProbeSchedule::for_framework(&service.framework).initial_delay,
+        service: service.clone(),
+        log_file,
+        overrides,
+        output_at: started_at,
+        log_modified: None,
+        stalled: false,
+    })
+}
+
+/// Run `npm run <script>` in a service's directory, or its groo.toml `command` for `dev`,
+/// printing its output with the service's prefix and, when `log_file` is given, writing it
+/// there (truncated first)
+pub async fn spawn_script(
+    service: &Service,
+    script_name: &str,
+    color: &Style,
+    log_file: Option<&Path>,
+    overrides: &Overrides,
+) -> Result<Child> {
+    let name = service.name.as_str();
+
+    let log_writer = match log_file {
+        Some(log_file) => {
+            // Ensure logs directory exists and truncate log file. Appending keeps lines
+            // written by other processes, like `groo mark`, from being overwritten.
+            if let Some(parent) = log_file.parent() {
+                config::create_private_dir(parent)?;
+            }
+            let file = OpenOptions::from(config::private_file())
+                .create(true)
+                .append(true)
+                .open(log_file)
+                .await?;
+            file.set_len(0).await?;
+            Some(Arc::new(Mutex::new(file)))
+        }
+        None => None,
+    };
 
+    // The directory is set on the process rather than `cd`-ed into in the script, which
+    // would break on paths with spaces, quotes or bytes that aren't UTF-8
     let mut cmd = Command::new("sh");
-    cmd.arg("-c")
-        .arg(format!("cd {} && npm run dev", path.display()))
-        .stdin(Stdio::inherit())
+    cmd.current_dir(&service.path);
+    match &service.command {
+        Some(command) if script_name == "dev" => {
+            cmd.arg("-c").arg(command);
+            // Procfile-style commands read their port from the environment
+            if let Some(port) = overrides.port.or(service.port) {
+                cmd.env("PORT", port.to_string());
+            }
+        }
+        _ => {
+            let mut script = format!("npm run {}", script_name);
+            if let Some(port) = overrides.port
+                && let Some(flag) = service.framework.port_flag()
+            {
+                script.push_str(&format!(" -- {} {}", flag, port));
+            }
+            cmd.arg("-c").arg(script);
+            if let Some(port) = overrides.port {
+                cmd.env("PORT", port.to_string());
+            }
+        }
+    }
+    for (key, value) in &service.framework_env {
+        if std::env::var_os(key).is_none() {
+            cmd.env(key, value);
+        }
+    }
+    cmd.envs(&service.env);
+    cmd.envs(&overrides.env);
+    priority::apply(&mut cmd, &service.priority);
+    // In a session of its own the service isn't hung up with the terminal
+    #[cfg(unix)]
+    if keep_alive() {
+        // SAFETY: setsid is async-signal-safe
+        unsafe {
+            cmd.pre_exec(|| {
+                libc::setsid();
+                Ok(())
+            });
+        }
+    }
+    cmd.stdin(if keep_alive() { Stdio::null() } else { Stdio::inherit() })
         .stdout(Stdio::piped())
         .stderr(Stdio::piped())
         .kill_on_drop(true);
 
-    let mut child = cmd.spawn()?;
+    let args: Vec<String> = cmd.as_std().get_args().map(|a| a.to_string_lossy().into_owned()).collect();
+    let mut child = match cmd.spawn() {
+        Ok(child) => child,
+        Err(e) => {
+            diag::record("spawn", format!("{}: sh {:?} in {} failed: {}", name, args, service.path.display(), e));
+            return Err(e.into());
+        }
+    };
+    diag::record(
+        "spawn",
+        format!(
+            "{}: sh {:?} in {} as pid {}",
+            name,
+            args,
+            service.path.display(),
+            child.id().map(|p| p.to_string()).unwrap_or_else(|| "?".to_string())
+        ),
+    );
+    if let Some(pid) = child.id()
+        && !priority::applied(pid, &service.priority)
+    {
+        print_service_error(
+            name,
+            &format!(
+                "Couldn't set priority nice {}; raising priority usually needs root",
+                service.priority.nice().unwrap_or_default()
+            ),
+            color,
+        );
+    }
 
     let stdout = child.stdout.take();
     let stderr = child.stderr.take();
@@ -60,16 +308,20 @@ pub async fn spawn_service(
     if let Some(stdout) = stdout {
         let name = name_clone.clone();
         let color = color_clone.clone();
-        let log_writer = Arc::clone(&log_writer);
+        let log_writer = log_writer.clone();
         tokio::spawn(async move {
             let reader = BufReader::new(stdout);
             let mut lines = reader.lines();
             while let Ok(Some(line)) = lines.next_line().await {
+                // Sequenced before waiting on the file, so the log keeps capture order
+                let seq = next_log_seq();
                 print_service_log(&name, &line, &color);
                 // Write to log file
-                let mut file = log_writer.lock().await;
-                let _ = file.write_all(format!("[{}] {}\n", name, line).as_bytes()).await;
-                let _ = file.flush().await;
+                if let Some(log_writer) = &log_writer {
+                    let mut file = log_writer.lock().await;
+                    let _ = file.write_all(encode_log_line(seq, &name, &line).as_bytes()).await;
+                    let _ = file.flush().await;
+                }
             }
         });
     }
@@ -78,41 +330,81 @@ pub async fn spawn_service(
     if let Some(stderr) = stderr {
         let name = name_clone.clone();
         let color = color_clone.clone();
-        let log_writer = Arc::clone(&log_writer);
         tokio::spawn(async move {
             let reader = BufReader::new(stderr);
             let mut lines = reader.lines();
             while let Ok(Some(line)) = lines.next_line().await {
+                let seq = next_log_seq();
                 print_service_error(&name, &line, &color);
                 // Write to log file
-                let mut file = log_writer.lock().await;
-                let _ = file.write_all(format!("[{}] {}\n", name, line).as_bytes()).await;
-                let _ = file.flush().await;
+                if let Some(log_writer) = &log_writer {
+                    let mut file = log_writer.lock().await;
+                    let _ = file.write_all(encode_log_line(seq, &name, &line).as_bytes()).await;
+                    let _ = file.flush().await;
+                }
             }
         });
     }
 
-    Ok(ProcessHandle {
-        name: name.to_string(),
-        child,
-        color,
-    })
+    Ok(child)
 }
 
+/// Supervise services until they have all exited or shutdown is requested. Returns whether
+/// shutdown had to be forced with a second Ctrl+C, which callers exit with status 130 for
+/// once they've cleaned up.
 pub async fn wait_for_processes(
     mut handles: Vec<ProcessHandle>,
+    deferred: Vec<Deferred>,
     mut shutdown_rx: broadcast::Receiver<()>,
-) {
+    session: &mut Session,
+    config: &ProjectConfig,
+) -> bool {
+    let hooks = &config.hooks;
+    let mut next_dependency_check = Instant::now();
+    let mut branch_watcher = match config.git.on_branch_switch {
+        BranchSwitchAction::Off => None,
+        _ => BranchWatcher::new(&session.path),
+    };
+    let mut next_branch_check = Instant::now() + BRANCH_POLL_INTERVAL;
+    let mut dependency_watcher = DependencyWatcher::default();
+    let mut mark_watcher = MarkWatcher::new(&session.project);
+    let mut next_mark_check = Instant::now() + MARK_POLL_INTERVAL;
+    let mut next_stall_check = Instant::now() + STALL_POLL_INTERVAL;
+    let mut signals = SessionSignals::new();
+    let mut crash_restarts = CrashRestarts::default();
+    let mut released = release_when_up(deferred.iter().map(|d| d.dependencies.clone()).collect());
+    let mut deferred: Vec<Option<Deferred>> = deferred.into_iter().map(Some).collect();
+    let mut pending_crashes: Vec<PendingCrash> = Vec::new();
+    let mut branch_prompt: Option<BranchPrompt> = None;
+    fire_hook(hooks, HookEvent::Start, session, &[]);
+    let mut forced = false;
+
     loop {
+        let held_back = deferred.iter().any(Option::is_some);
+        if handles.is_empty() && !held_back && pending_crashes.is_empty() {
+            break;
+        }
         tokio::select! {
-            _ = shutdown_rx.recv() => {
-                // Shutdown signal received, kill all processes and wait for them
-                for handle in &mut handles {
-                    let _ = handle.child.start_kill();
+            Some(index) = released.recv(), if held_back => {
+                if let Some(deferred) = deferred[index].take()
+                    && let Some(handle) = start_deferred(deferred, session).await
+                {
+                    handles.push(handle);
                 }
-                for handle in &mut handles {
-                    let _ = handle.child.wait().await;
+            }
+            answer = async { (&mut branch_prompt.as_mut().expect("guarded").answer).await }, if branch_prompt.is_some() => {
+                let prompt = branch_prompt.take().expect("guarded");
+                if answer.unwrap_or(false) {
+                    restart_named(&mut handles, &prompt.services, session).await;
                 }
+            }
+            signal = next_signal(&mut signals) => match signal {
+                SessionSignal::Suspend => suspend(&mut handles),
+                SessionSignal::Resume => continue_services(&handles),
+                SessionSignal::Resize => resize(&handles),
+            },
+            _ = shutdown_rx.recv() => {
+                forced = shutdown(&mut handles, session, config).await;
                 break;
             }
             // Check if any process has exited
@@ -137,13 +429,607 @@ pub async fn wait_for_processes(
                             color,
                         );
                     }
-                    handles.remove(index);
+                    session.mark_exited(&handle.name, status.code(), !status.success());
+                    let _ = session.save();
+                    let mut state = State::load().unwrap_or_default();
+                    state.record_exit(
+                        &session.project,
+                        &handle.name,
+                        ExitEvent {
+                            at: now_millis(),
+                            code: status.code(),
+                            signal: exit_signal(&status),
+                            uptime_ms: handle.started_at.elapsed().as_millis() as u64,
+                        },
+                    );
+                    let _ = state.save();
+                    if !status.success() {
+                        let exit_code = status.code().map(|c| c.to_string()).unwrap_or_default();
+                        let vars = service_vars(handle, &[("GROO_EXIT_CODE", exit_code)]);
+                        fire_hook(hooks, HookEvent::Crash, session, &vars);
+                    }
+
+                    let restart = !status.success()
+                        && config.service(&handle.name).is_some_and(|c| c.on_crash == CrashAction::Restart);
+                    let handle = handles.remove(index);
+                    if restart {
+                        pending_crashes.push(plan_restart(handle, &mut crash_restarts, config));
+                    }
+                }
+            }
+        }
+
+        follow_up_crashes(&mut pending_crashes, &mut handles, session).await;
+
+        check_ready(&mut handles, session, config).await;
+        if Instant::now() >= next_dependency_check {
+            check_dependencies(&mut handles, &mut dependency_watcher, session, config).await;
+            next_dependency_check = Instant::now() + DEPENDENCY_POLL_INTERVAL;
+        }
+
+        if Instant::now() >= next_mark_check {
+            for mark in mark_watcher.poll() {
+                if handles.iter().any(|h| mark.services.contains(&h.name)) {
+                    output::line(style(mark.line()).yellow().bold());
+                }
+            }
+            next_mark_check = Instant::now() + MARK_POLL_INTERVAL;
+        }
+
+        if Instant::now() >= next_stall_check {
+            check_stalls(&mut handles, session, config).await;
+            next_stall_check = Instant::now() + STALL_POLL_INTERVAL;
+        }
+
+        // A switch while the last one is still being asked about is picked up after the answer
+        if let Some(watcher) = branch_watcher.as_mut()
+            && branch_prompt.is_none()
+            && Instant::now() >= next_branch_check
+        {
+            if let Some(switch) = watcher.poll() {
+                let affected: Vec<usize> = handles
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, h)| watcher.touches(&switch, &h.service.path))
+                    .map(|(i, _)| i)
+                    .collect();
+                branch_prompt =
+                    on_branch_switch(&mut handles, &affected, &switch, session, config.git.on_branch_switch).await;
+            }
+            next_branch_check = Instant::now() + BRANCH_POLL_INTERVAL;
+        }
+    }
+
+    session.finish();
+    let _ = session.save();
+    fire_hook(hooks, HookEvent::Stop, session, &[]);
+    forced
+}
+
+/// Stop every service and wait for it to exit, dependents before their dependencies (see
+/// [`ProjectConfig::shutdown_layers`]). Dev servers that hang on shutdown keep this waiting
+/// until a second Ctrl+C, which kills whatever is left. Returns whether that happened.
+async fn shutdown(handles: &mut [ProcessHandle], session: &mut Session, config: &ProjectConfig) -> bool {
+    // Collected before anything exits: once a wrapper like `npm` is gone, the dev server
+    // it leaves behind can't be found through it
+    let trees: Vec<Vec<u32>> = handles
+        .iter()
+        .map(|h| h.pid().map(|pid| backend().process_tree(pid)).unwrap_or_default())
+        .collect();
+    let pids: Vec<u32> = trees.concat();
+    let names: Vec<String> = handles.iter().map(|h| h.name.clone()).collect();
+    let name_refs: Vec<&str> = names.iter().map(String::as_str).collect();
+    let layers: Vec<Vec<usize>> = config
+        .shutdown_layers(&name_refs)
+        .iter()
+        .map(|layer| layer.iter().filter_map(|name| names.iter().position(|n| n == name)).collect())
+        .collect();
+
+    // Exit codes of reaped services, by index
+    let mut exit_codes: Vec<Option<Option<i32>>> = vec![None; handles.len()];
+    let forced = {
+        let stop_all = async {
+            for (n, layer) in layers.iter().enumerate() {
+                if layers.len() > 1 {
+                    let layer_names: Vec<&str> = layer.iter().map(|&i| name_refs[i]).collect();
+                    output::step(format!("Stopping {}", layer_names.join(", ")));
+                }
+                for &i in layer {
+                    if trees[i].is_empty() {
+                        let _ = handles[i].child.start_kill();
+                    }
+                    for &pid in &trees[i] {
+                        backend().signal(pid, "TERM");
+                    }
+                }
+                // Let the layer exit before stopping what it depends on
+                if n + 1 < layers.len() {
+                    let grace = layer.iter().map(|&i| config.stop_timeout(&names[i])).max().unwrap_or_default();
+                    let deadline = Instant::now() + grace;
+                    for &i in layer {
+                        if let Ok(status) = tokio::time::timeout_at(deadline, handles[i].child.wait()).await {
+                            exit_codes[i] = Some(status.ok().and_then(|s| s.code()));
+                        }
+                    }
+                    let layer_pids: Vec<u32> = layer.iter().flat_map(|&i| trees[i].iter().copied()).collect();
+                    wait_for_exit(&layer_pids, Some(deadline)).await;
+                }
+            }
+            for (handle, exit_code) in handles.iter_mut().zip(exit_codes.iter_mut()) {
+                if exit_code.is_none() {
+                    let status = handle.child.wait().await.ok();
+                    *exit_code = Some(status.and_then(|s| s.code()));
+                }
+            }
+            // Wrappers can exit while the dev server they started is still shutting down
+            wait_for_exit(&pids, None).await;
+        };
+        tokio::pin!(stop_all);
+        let hint_at = tokio::time::sleep(SHUTDOWN_HINT_AFTER);
+        tokio::pin!(hint_at);
+        let mut hinted = false;
+        loop {
+            tokio::select! {
+                _ = &mut stop_all => break false,
+                _ = tokio::signal::ctrl_c() => break true,
+                _ = &mut hint_at, if !hinted => {
+                    hinted = true;
+                    output::warn("Still waiting for services to exit. Press Ctrl+C again to kill them.");
+                }
+            }
+        }
+    };
 
-                    if handles.is_empty() {
-                        break;
+    for (name, exit_code) in names.iter().zip(exit_codes) {
+        if let Some(code) = exit_code {
+            session.mark_stopped(name, code);
+        }
+    }
+    if forced {
+        for &pid in &pids {
+            // Windows has no SIGKILL; taskkill /F does the same
+            if !backend().signal(pid, "KILL") {
+                backend().kill_tree(pid);
+            }
+        }
+        output::error(format!("Forced shutdown: killed {} process(es)", pids.len()));
+    }
+    forced
+}
+
+/// Wait until none of `pids` is running, or `deadline` has passed
+async fn wait_for_exit(pids: &[u32], deadline: Option<Instant>) {
+    while pids.iter().any(|&pid| backend().is_pid_running(pid)) && deadline.is_none_or(|d| Instant::now() < d) {
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+}
+
+/// Restart services whose directories changed in a branch switch. When configured to ask
+/// first, the question is returned to be answered while services keep running; without a
+/// terminal to ask on, they're restarted as answering with the default would.
+async fn on_branch_switch(
+    handles: &mut [ProcessHandle],
+    affected: &[usize],
+    switch: &BranchSwitch,
+    session: &mut Session,
+    action: BranchSwitchAction,
+) -> Option<BranchPrompt> {
+    output::blank();
+    output::step(format!(
+        "Switched {} → {}",
+        style(&switch.from).bold(),
+        style(&switch.to).bold()
+    ));
+    if affected.is_empty() {
+        output::line(format!("  {}", style("No running services changed.").dim()));
+        output::blank();
+        return None;
+    }
+
+    let names: Vec<String> = affected.iter().map(|&i| handles[i].name.clone()).collect();
+    output::line(format!("  Changed: {}", names.join(", ")));
+    output::blank();
+
+    if action == BranchSwitchAction::Prompt && !keep_alive() && Term::stderr().is_term() {
+        let prompt = format!("Restart {} service(s) on {}?", names.len(), switch.to);
+        let (tx, answer) = oneshot::channel();
+        // Not a blocking task: the runtime would wait for it on shutdown
+        std::thread::spawn(move || {
+            let confirmed = Confirm::with_theme(&theme())
+                .with_prompt(prompt)
+                .default(true)
+                .interact_on(&Term::stderr())
+                .unwrap_or(false);
+            let _ = tx.send(confirmed);
+        });
+        return Some(BranchPrompt { answer, services: names });
+    }
+
+    restart_named(handles, &names, session).await;
+    None
+}
+
+/// Respawn the named services that are still running
+async fn restart_named(handles: &mut [ProcessHandle], names: &[String], session: &mut Session) {
+    for handle in handles.iter_mut().filter(|h| names.contains(&h.name)) {
+        if let Err(e) = respawn(handle, session).await {
+            print_service_error(&handle.name, &format!("Failed to restart: {}", e), &handle.color);
+        }
+    }
+}
+
+/// Kill a service and start it again from the same service definition
+async fn respawn(handle: &mut ProcessHandle, session: &mut Session) -> Result<()> {
+    print_service_log(&handle.name, "Restarting...", &handle.color);
+    if let Some(pid) = handle.pid() {
+        backend().kill_tree(pid);
+    }
+    let _ = handle.child.start_kill();
+    let status = handle.child.wait().await.ok();
+    session.mark_stopped(&handle.name, status.and_then(|s| s.code()));
+    relaunch(handle, session).await
+}
+
+/// Start a service again in place of its exited process
+async fn relaunch(handle: &mut ProcessHandle, session: &mut Session) -> Result<()> {
+    // The dev server may take a moment to let go of its port
+    if let Some(port) = handle.port {
+        let deadline = Instant::now() + PORT_RELEASE_TIMEOUT;
+        while backend().is_port_in_use(port) && Instant::now() < deadline {
+            tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+        }
+    }
+
+    let service = handle.service.clone();
+    *handle = spawn_service(&service, handle.color.clone(), handle.log_file.clone(), handle.overrides.clone()).await?;
+    track_started(handle, session);
+    Ok(())
+}
+
+/// Start a held-back service now that its dependencies are up (or were given up on)
+async fn start_deferred(deferred: Deferred, session: &mut Session) -> Option<ProcessHandle> {
+    let name = deferred.service.name.clone();
+    match spawn_service(&deferred.service, deferred.color, deferred.log_file, deferred.overrides).await {
+        Ok(handle) => {
+            track_started(&handle, session);
+            Some(handle)
+        }
+        Err(e) => {
+            output::error(format!("Failed to start {}: {}", name, e));
+            None
+        }
+    }
+}
+
+/// Record a process started during the session, keeping `groo status`/`groo stop` pointed at it
+fn track_started(handle: &ProcessHandle, session: &mut Session) {
+    session.add_service(&handle.name, &handle.service.path, handle.port, handle.pid(), &handle.overrides.env);
+    let _ = session.save();
+
+    if let Some(pid) = handle.pid() {
+        let mut state = State::load().unwrap_or_default();
+        state.add_service(
+            &session.project,
+            session.path.clone(),
+            &handle.name,
+            ServiceState {
+                pid,
+                port: handle.port,
+                scheme: handle.scheme,
+                base_path: handle.base_path.clone(),
+            },
+        );
+        let _ = state.save();
+    }
+}
+
+/// Plan the restart of a service that crashed with `on_crash = "restart"`, after a backoff
+/// that doubles with each restart within its `restart_window`. One that has been restarted
+/// `max_restarts` times within the window is crash-looping instead: it's reported with the
+/// output that led up to the crash and left stopped.
+fn plan_restart(handle: ProcessHandle, restarts: &mut CrashRestarts, config: &ProjectConfig) -> PendingCrash {
+    let (max, window) = config.restart_limit(&handle.name);
+    let Some(attempt) = restarts.allow(&handle.name, max, window) else {
+        return PendingCrash {
+            // Its last lines may still be on their way to the log
+            due: Instant::now() + OUTPUT_SETTLE_TIME,
+            after: AfterCrash::ReportLoop {
+                name: handle.name,
+                color: handle.color,
+                log_file: handle.log_file,
+                max,
+                window,
+            },
+        };
+    };
+
+    let doublings = (attempt - 1).min(16) as u32;
+    let delay = CRASH_RESTART_BACKOFF.saturating_mul(1 << doublings).min(MAX_CRASH_RESTART_BACKOFF);
+    print_service_log(
+        &handle.name,
+        &format!("Restarting after crash in {} ({}/{})...", format::precise(delay), attempt, max),
+        &handle.color,
+    );
+    let due = Instant::now() + delay;
+    PendingCrash {
+        due,
+        after: AfterCrash::Restart {
+            port: handle.port,
+            port_deadline: due + PORT_RELEASE_TIMEOUT,
+            service: Box::new(Deferred {
+                service: handle.service,
+                color: handle.color,
+                log_file: handle.log_file,
+                overrides: handle.overrides,
+                dependencies: Vec::new(),
+            }),
+        },
+    }
+}
+
+/// Take the crash follow-ups that are due: restart services whose old process has let go
+/// of its port (or held it too long to keep waiting), and report crash loops
+async fn follow_up_crashes(pending: &mut Vec<PendingCrash>, handles: &mut Vec<ProcessHandle>, session: &mut Session) {
+    let now = Instant::now();
+    let mut i = 0;
+    while i < pending.len() {
+        let ready = pending[i].due <= now
+            && match &pending[i].after {
+                AfterCrash::Restart { port, port_deadline, .. } => {
+                    now >= *port_deadline || !port.is_some_and(|p| backend().is_port_in_use(p))
+                }
+                AfterCrash::ReportLoop { .. } => true,
+            };
+        if !ready {
+            i += 1;
+            continue;
+        }
+
+        match pending.remove(i).after {
+            AfterCrash::Restart { service, .. } => {
+                if let Some(handle) = start_deferred(*service, session).await {
+                    handles.push(handle);
+                }
+            }
+            AfterCrash::ReportLoop {
+                name,
+                color,
+                log_file,
+                max,
+                window,
+            } => {
+                let tail = crash_tail(&log_file);
+                print_service_error(
+                    &name,
+                    &format!(
+                        "Crash-looping: restarted {} times within {}, not restarting again. Last output:",
+                        max,
+                        format::exact(window)
+                    ),
+                    &color,
+                );
+                for line in &tail {
+                    print_service_error(&name, &format!("  {}", line), &color);
+                }
+                session.mark_crash_looping(&name, tail);
+                let _ = session.save();
+            }
+        }
+    }
+}
+
+/// Probe ports of services that haven't come up yet, backing off per their probe schedule,
+/// and report each one as ready or, once the schedule runs out, as failed to start
+async fn check_ready(handles: &mut [ProcessHandle], session: &mut Session, config: &ProjectConfig) {
+    let hooks = &config.hooks;
+    for handle in handles.iter_mut().filter(|h| h.readiness != Readiness::Ready) {
+        let Some(port) = handle.port else {
+            continue;
+        };
+        if Instant::now() < handle.next_probe {
+            continue;
+        }
+
+        if is_port_ready(port).await {
+            let late = handle.readiness == Readiness::Failed;
+            handle.readiness = Readiness::Ready;
+            let elapsed = handle.started_at.elapsed();
+            print_service_log(
+                &handle.name,
+                &format!(
+                    "Ready on {} ({}{})",
+                    service_url(handle.scheme, port, handle.base_path.as_deref()),
+                    format::precise(elapsed),
+                    if late { ", later than expected" } else { "" }
+                ),
+                &handle.color,
+            );
+            session.mark_ready(&handle.name, elapsed.as_millis() as u64);
+            let _ = session.save();
+            let mut state = State::load().unwrap_or_default();
+            state.record_ready(
+                &session.project,
+                &handle.name,
+                ReadyEndpoint {
+                    port,
+                    scheme: handle.scheme,
+                    base_path: handle.base_path.clone(),
+                    at: now_millis(),
+                },
+            );
+            let _ = state.save();
+            let vars = service_vars(handle, &[("GROO_READY_MS", elapsed.as_millis().to_string())]);
+            fire_hook(hooks, HookEvent::Ready, session, &vars);
+            continue;
+        }
+
+        let mut schedule = ProbeSchedule::for_framework(&handle.service.framework);
+        if let Some(secs) = config.service(&handle.name).and_then(|c| c.ready_timeout) {
+            schedule.timeout = tokio::time::Duration::from_secs(secs);
+        }
+        if handle.probe_attempts == 0 {
+            print_service_log(&handle.name, &format!("Starting, waiting for port {}...", port), &handle.color);
+        }
+        handle.probe_attempts += 1;
+        handle.next_probe = Instant::now() + schedule.interval(handle.probe_attempts);
+
+        if handle.readiness == Readiness::Starting && handle.started_at.elapsed() >= schedule.timeout {
+            handle.readiness = Readiness::Failed;
+            print_service_error(
+                &handle.name,
+                &format!(
+                    "Failed to start: nothing listening on port {} after {} (still checking)",
+                    port,
+                    format::exact(schedule.timeout)
+                ),
+                &handle.color,
+            );
+            session.mark_not_ready(&handle.name);
+            let _ = session.save();
+        }
+    }
+}
+
+/// Report services with a `stall_timeout` that have written nothing to their log for that
+/// long, which for a watcher often means it hung while its port stays bound. Depending on
+/// `on_stall` the service is then restarted.
+async fn check_stalls(handles: &mut [ProcessHandle], session: &mut Session, config: &ProjectConfig) {
+    for handle in handles.iter_mut() {
+        let Some(timeout) = config.stall_timeout(&handle.name) else {
+            continue;
+        };
+        let modified = std::fs::metadata(&handle.log_file).and_then(|m| m.modified()).ok();
+        if modified != handle.log_modified {
+            handle.log_modified = modified;
+            handle.output_at = Instant::now();
+            if handle.stalled {
+                handle.stalled = false;
+                print_service_log(&handle.name, "Output resumed", &handle.color);
+            }
+            continue;
+        }
+        if handle.stalled || handle.output_at.elapsed() < timeout {
+            continue;
+        }
+
+        handle.stalled = true;
+        print_service_error(
+            &handle.name,
+            &format!("Stalled: no output for {}", format::exact(timeout)),
+            &handle.color,
+        );
+        let vars = service_vars(handle, &[("GROO_STALL_SECS", timeout.as_secs().to_string())]);
+        fire_hook(&config.hooks, HookEvent::Stall, session, &vars);
+
+        let action = config.service(&handle.name).map(|c| c.on_stall).unwrap_or_default();
+        if action == StallAction::Restart
+            && let Err(e) = respawn(handle, session).await
+        {
+            print_service_error(&handle.name, &format!("Failed to restart: {}", e), &handle.color);
+        }
+    }
+}
+
+/// Restart or signal services whose dependencies became healthy again after going down,
+/// per `depends_on` and `on_dependency_restart` in groo.toml
+async fn check_dependencies(
+    handles: &mut [ProcessHandle],
+    watcher: &mut DependencyWatcher,
+    session: &mut Session,
+    config: &ProjectConfig,
+) {
+    let mut state: Option<State> = None;
+    let mut dependencies: Vec<(String, Endpoint)> = Vec::new();
+    for handle in handles.iter() {
+        let Some(service_config) = config.service(&handle.name) else {
+            continue;
+        };
+        if service_config.on_dependency_restart == DependencyAction::Off {
+            continue;
+        }
+        for dependency in &service_config.depends_on {
+            if dependencies.iter().any(|(name, _)| name == dependency) {
+                continue;
+            }
+            if let Some(external) = ExternalDependency::parse(dependency) {
+                dependencies.push((dependency.clone(), Endpoint::Remote(external.address)));
+                continue;
+            }
+            // Dependencies outside this session are found through state
+            let port = handles
+                .iter()
+                .find(|h| &h.name == dependency)
+                .and_then(|h| h.port)
+                .or_else(|| {
+                    state
+                        .get_or_insert_with(|| State::load().unwrap_or_default())
+                        .get_project(&session.project)
+                        .and_then(|p| p.services.get(dependency))
+                        .and_then(|s| s.port)
+                });
+            if let Some(port) = port {
+                dependencies.push((dependency.clone(), Endpoint::Local(port)));
+            }
+        }
+    }
+    watcher.probe(dependencies);
+
+    for dependency in watcher.recovered() {
+        for handle in handles.iter_mut() {
+            let Some(service_config) = config.service(&handle.name) else {
+                continue;
+            };
+            if !service_config.depends_on.contains(&dependency) {
+                continue;
+            }
+            match service_config.on_dependency_restart {
+                DependencyAction::Off => {}
+                DependencyAction::Restart => {
+                    print_service_log(&handle.name, &format!("{} is back", dependency), &handle.color);
+                    if let Err(e) = respawn(handle, session).await {
+                        print_service_error(&handle.name, &format!("Failed to restart: {}", e), &handle.color);
+                    }
+                }
+                DependencyAction::Reload => {
+                    let signal = service_config.reload_signal.as_deref().unwrap_or("HUP");
+                    // Signal the dev server itself rather than its `sh`/`npm` wrappers
+                    let pid = handle
+                        .port
+                        .and_then(|port| backend().pids_on_port(port).first().copied())
+                        .or(handle.pid());
+                    if pid.is_some_and(|pid| backend().signal(pid, signal)) {
+                        print_service_log(
+                            &handle.name,
+                            &format!("{} is back, sent SIG{}", dependency, signal.trim_start_matches("SIG")),
+                            &handle.color,
+                        );
+                    } else {
+                        print_service_error(&handle.name, &format!("Failed to send {} to reload", signal), &handle.color);
                     }
                 }
             }
         }
     }
 }
+
+/// Signal that killed a process, if it didn't exit by itself
+#[cfg(unix)]
+fn exit_signal(status: &std::process::ExitStatus) -> Option<i32> {
+    use std::os::unix::process::ExitStatusExt;
+    status.signal()
+}
+
+#[cfg(not(unix))]
+fn exit_signal(_status: &std::process::ExitStatus) -> Option<i32> {
+    None
+}
+
+/// Hook env vars describing a service, plus any event-specific extras
+fn service_vars<'a>(handle: &ProcessHandle, extra: &[(&'a str, String)]) -> Vec<(&'a str, String)> {
+    let mut vars = vec![
+        ("GROO_SERVICE", handle.name.clone()),
+        ("GROO_PORT", handle.port.map(|p| p.to_string()).unwrap_or_default()),
+    ];
+    vars.extend_from_slice(extra);
+    vars
+}
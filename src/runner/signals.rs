@@ -0,0 +1,188 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use tokio::time::Instant;
+
+use super::process::ProcessHandle;
+use crate::backend::backend;
+use crate::diag;
+use crate::output;
+
+static KEEP_ALIVE: AtomicBool = AtomicBool::new(false);
+
+/// Keep services running when the terminal closes. They're started in their own session,
+/// without terminal input, and groo keeps capturing their logs in the background.
+pub fn set_keep_alive(enabled: bool) {
+    KEEP_ALIVE.store(enabled, Ordering::Relaxed);
+}
+
+pub fn keep_alive() -> bool {
+    KEEP_ALIVE.load(Ordering::Relaxed)
+}
+
+/// Wait until the session should shut down: on Ctrl+C, or when the terminal closes unless
+/// services are kept alive. After a hangup there's no terminal left to print to, so output
+/// is discarded from then on.
+pub async fn shutdown_requested() {
+    loop {
+        tokio::select! {
+            _ = tokio::signal::ctrl_c() => {
+                output::blank();
+                output::step("Shutting down... (Ctrl+C again to force)");
+                return;
+            }
+            _ = hangup() => {
+                detach_output();
+                if !keep_alive() {
+                    diag::record("session", "terminal closed, stopping services");
+                    return;
+                }
+                diag::record("session", "terminal closed, keeping services alive in the background");
+            }
+        }
+    }
+}
+
+#[cfg(unix)]
+async fn hangup() {
+    use tokio::signal::unix::{signal, SignalKind};
+    match signal(SignalKind::hangup()) {
+        Ok(mut hangup) => {
+            hangup.recv().await;
+        }
+        Err(_) => std::future::pending().await,
+    }
+}
+
+/// The console window closing; Windows gives the process a few seconds to clean up
+#[cfg(not(unix))]
+async fn hangup() {
+    match tokio::signal::windows::ctrl_close() {
+        Ok(mut close) => {
+            close.recv().await;
+        }
+        Err(_) => std::future::pending().await,
+    }
+}
+
+/// Point stdout and stderr at /dev/null, since writing to a hung-up terminal fails
+#[cfg(unix)]
+fn detach_output() {
+    // SAFETY: plain fd operations on descriptors this process owns
+    unsafe {
+        let null = libc::open(c"/dev/null".as_ptr(), libc::O_WRONLY);
+        if null >= 0 {
+            libc::dup2(null, libc::STDOUT_FILENO);
+            libc::dup2(null, libc::STDERR_FILENO);
+            libc::close(null);
+        }
+    }
+}
+
+#[cfg(not(unix))]
+fn detach_output() {}
+
+/// Job-control and terminal signals a dev session passes on to its services
+pub enum SessionSignal {
+    /// Ctrl+Z
+    Suspend,
+    /// `fg`/`bg` after a suspend
+    Resume,
+    /// The terminal was resized
+    Resize,
+}
+
+/// Listens for [`SessionSignal`]s. Installing the handlers stops Ctrl+Z from suspending
+/// groo on its own, so [`suspend`] does it once the services are stopped too.
+pub struct SessionSignals {
+    #[cfg(unix)]
+    suspend: tokio::signal::unix::Signal,
+    #[cfg(unix)]
+    resume: tokio::signal::unix::Signal,
+    #[cfg(unix)]
+    resize: tokio::signal::unix::Signal,
+}
+
+impl SessionSignals {
+    /// `None` if the handlers couldn't be installed, in which case signals keep their
+    /// default behavior
+    #[cfg(unix)]
+    pub fn new() -> Option<Self> {
+        use tokio::signal::unix::{signal, SignalKind};
+        Some(Self {
+            suspend: signal(SignalKind::from_raw(libc::SIGTSTP)).ok()?,
+            resume: signal(SignalKind::from_raw(libc::SIGCONT)).ok()?,
+            resize: signal(SignalKind::window_change()).ok()?,
+        })
+    }
+
+    #[cfg(not(unix))]
+    pub fn new() -> Option<Self> {
+        None
+    }
+
+    #[cfg(unix)]
+    pub async fn recv(&mut self) -> SessionSignal {
+        tokio::select! {
+            _ = self.suspend.recv() => SessionSignal::Suspend,
+            _ = self.resume.recv() => SessionSignal::Resume,
+            _ = self.resize.recv() => SessionSignal::Resize,
+        }
+    }
+
+    #[cfg(not(unix))]
+    pub async fn recv(&mut self) -> SessionSignal {
+        std::future::pending().await
+    }
+}
+
+/// Wait for the next signal, or forever without handlers
+pub async fn next_signal(signals: &mut Option<SessionSignals>) -> SessionSignal {
+    match signals {
+        Some(signals) => signals.recv().await,
+        None => std::future::pending().await,
+    }
+}
+
+/// Stop every service's process tree, then groo itself. The terminal only stops processes
+/// in its foreground group; dev servers that detach into their own group would otherwise
+/// keep running behind a suspended session.
+///
+/// Returns once the session is continued, with the services running again.
+pub fn suspend(handles: &mut [ProcessHandle]) {
+    for handle in handles.iter() {
+        if let Some(pid) = handle.pid() {
+            backend().signal_tree(pid, "STOP");
+        }
+    }
+    output::blank();
+    output::step(format!("Suspended {} service(s). Run `fg` to resume.", handles.len()));
+
+    let suspended_at = Instant::now();
+    backend().signal(std::process::id(), "STOP");
+    let suspended_for = suspended_at.elapsed();
+
+    continue_services(handles);
+    // Time spent suspended doesn't count against readiness
+    for handle in handles.iter_mut() {
+        handle.shift_clock(suspended_for);
+    }
+    output::step("Resumed");
+    output::blank();
+}
+
+/// Continue every service's process tree, including processes the shell's `fg` doesn't reach
+pub fn continue_services(handles: &[ProcessHandle]) {
+    for handle in handles {
+        if let Some(pid) = handle.pid() {
+            backend().signal_tree(pid, "CONT");
+        }
+    }
+}
+
+/// Pass a resize on to each dev server so terminal-aware output is laid out again
+pub fn resize(handles: &[ProcessHandle]) {
+    for handle in handles {
+        if let Some(pid) = handle.pid() {
+            backend().signal_tree(pid, "WINCH");
+        }
+    }
+}
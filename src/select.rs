@@ -0,0 +1,488 @@
+use anyhow::Result;
+use console::{style, Style, Term};
+use dialoguer::{theme::ColorfulTheme, MultiSelect};
+
+use crate::backend::backend;
+use crate::discovery::Service;
+use crate::output;
+
+/// Which services a selector offers
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Filter {
+    All,
+    Running,
+    Stopped,
+}
+
+/// Which offered services start out checked
+#[derive(Debug, Clone, PartialEq)]
+pub enum Defaults {
+    All,
+    None,
+    /// Services with a detected port that aren't running yet
+    NotRunning,
+    /// The named services, unless they're already running
+    Named(Vec<String>),
+}
+
+/// Service picker shared by `dev`, `restart`, `stop` and `logs`.
+///
+/// With queries (from `--service`) the selection is resolved without prompting: a query
+/// matches a full name, a last name segment (`web` → `apps:web`), `@tag` from groo.toml,
+/// a glob (`apps:*`), or fuzzily (`wb` → `apps:web`). Without queries the user picks from a checklist, or the
+/// defaults are taken when there is no terminal to prompt on.
+pub struct Selector<'a> {
+    services: &'a [Service],
+    running: Vec<bool>,
+    prompt: &'a str,
+    filter: Filter,
+    defaults: Defaults,
+    queries: &'a [String],
+    destructive: bool,
+}
+
+impl<'a> Selector<'a> {
+    pub fn new(services: &'a [Service], prompt: &'a str) -> Self {
+        let running = services
+            .iter()
+            .map(|s| s.port.is_some_and(|p| backend().is_port_in_use(p)))
+            .collect();
+        Self {
+            services,
+            running,
+            prompt,
+            filter: Filter::All,
+            defaults: Defaults::All,
+            queries: &[],
+            destructive: false,
+        }
+    }
+
+    pub fn filter(mut self, filter: Filter) -> Self {
+        self.filter = filter;
+        self
+    }
+
+    pub fn defaults(mut self, defaults: Defaults) -> Self {
+        self.defaults = defaults;
+        self
+    }
+
+    pub fn queries(mut self, queries: &'a [String]) -> Self {
+        self.queries = queries;
+        self
+    }
+
+    /// Use the warning-colored theme for actions like stopping services
+    pub fn destructive(mut self) -> Self {
+        self.destructive = true;
+        self
+    }
+
+    /// Services the filter lets through, in discovery order
+    pub fn candidates(&self) -> Vec<&'a Service> {
+        self.candidate_indices().into_iter().map(|i| &self.services[i]).collect()
+    }
+
+    /// Resolve queries or prompt. An empty result means the user deselected everything.
+    pub fn select(&self) -> Result<Vec<&'a Service>> {
+        let candidates = self.candidate_indices();
+        let term = Term::stderr();
+
+        let chosen = if !self.queries.is_empty() {
+            self.resolve_queries(&candidates, term.is_term())?
+        } else if !term.is_term() {
+            // Stopping every running service by default is too much to do unasked
+            if self.destructive {
+                anyhow::bail!("No terminal to choose services on: pass -s/--service, or --all for every one");
+            }
+            candidates
+                .iter()
+                .copied()
+                .filter(|&i| self.is_default(i))
+                .collect()
+        } else {
+            self.prompt_for(&candidates, |i| self.is_default(i))?
+        };
+
+        if chosen.is_empty() {
+            output::warn("No services selected.");
+        }
+        Ok(chosen.into_iter().map(|i| &self.services[i]).collect())
+    }
+
+    fn candidate_indices(&self) -> Vec<usize> {
+        (0..self.services.len())
+            .filter(|&i| match self.filter {
+                Filter::All => true,
+                Filter::Running => self.running[i],
+                Filter::Stopped => !self.running[i],
+            })
+            .collect()
+    }
+
+    fn is_default(&self, index: usize) -> bool {
+        match &self.defaults {
+            Defaults::All => true,
+            Defaults::None => false,
+            Defaults::NotRunning => self.services[index].port.is_some() && !self.running[index],
+            Defaults::Named(names) => names.contains(&self.services[index].name) && !self.running[index],
+        }
+    }
+
+    fn resolve_queries(&self, candidates: &[usize], interactive: bool) -> Result<Vec<usize>> {
+        let mut chosen: Vec<usize> = Vec::new();
+        for query in self.queries {
+            let matches = matching_services(self.services, query);
+            if matches.is_empty() {
+                let available: Vec<&str> = candidates.iter().map(|&i| self.services[i].name.as_str()).collect();
+                anyhow::bail!(
+                    "No service matches '{}'. Available services: {}",
+                    query,
+                    available.join(", ")
+                );
+            }
+
+            let offered: Vec<usize> = matches.iter().copied().filter(|i| candidates.contains(i)).collect();
+            if offered.is_empty() {
+                let names: Vec<&str> = matches.iter().map(|&i| self.services[i].name.as_str()).collect();
+                anyhow::bail!(
+                    "No {} service matches '{}' (found {})",
+                    self.filter_label(),
+                    query,
+                    names.join(", ")
+                );
+            }
+
+            // Tags and globs select every service they match; a name query has to settle on one
+            let resolved = if query.starts_with('@') || is_glob(query) || offered.len() == 1 {
+                offered
+            } else if interactive {
+                self.prompt_for(&offered, |_| false)?
+            } else {
+                let names: Vec<&str> = offered.iter().map(|&i| self.services[i].name.as_str()).collect();
+                anyhow::bail!("'{}' is ambiguous: {}", query, names.join(", "));
+            };
+
+            for index in resolved {
+                if !chosen.contains(&index) {
+                    chosen.push(index);
+                }
+            }
+        }
+        Ok(chosen)
+    }
+
+    fn filter_label(&self) -> &'static str {
+        match self.filter {
+            Filter::All => "discovered",
+            Filter::Running => "running",
+            Filter::Stopped => "stopped",
+        }
+    }
+
+    fn prompt_for(&self, indices: &[usize], checked: impl Fn(usize) -> bool) -> Result<Vec<usize>> {
+        let max_name_len = indices.iter().map(|&i| self.services[i].name.len()).max().unwrap_or(0);
+
+        let items: Vec<String> = indices
+            .iter()
+            .map(|&i| {
+                let service = &self.services[i];
+                let port_str = service
+                    .port
+                    .map(|p| format!("{}", p))
+                    .unwrap_or_else(|| "-".to_string());
+                let tags = if service.tags.is_empty() {
+                    String::new()
+                } else {
+                    format!(
+                        "  {}",
+                        style(service.tags.iter().map(|t| format!("@{}", t)).collect::<Vec<_>>().join(" ")).dim()
+                    )
+                };
+                // Only worth pointing out when running and stopped services are mixed
+                if self.filter == Filter::All && self.running[i] {
+                    format!(
+                        "{:<width$}  {}  {}{}",
+                        style(&service.name).dim(),
+                        style(port_str).dim(),
+                        style("(running)").dim().italic(),
+                        tags,
+                        width = max_name_len
+                    )
+                } else {
+                    format!(
+                        "{:<width$}  {}{}",
+                        service.name,
+                        style(port_str).dim(),
+                        tags,
+                        width = max_name_len
+                    )
+                }
+            })
+            .collect();
+
+        let defaults: Vec<bool> = indices.iter().map(|&i| checked(i)).collect();
+
+        let theme = if self.destructive { destructive_theme() } else { theme() };
+        let selections = MultiSelect::with_theme(&theme)
+            .with_prompt(self.prompt)
+            .items(&items)
+            .defaults(&defaults)
+            .interact_on(&Term::stderr())?;
+
+        Ok(selections.into_iter().map(|s| indices[s]).collect())
+    }
+}
+
+/// Indices of services matching a `--service` query, trying the most precise kind of match
+/// first: `@tag`, glob, full name, last name segment, substring, then fuzzy
+pub fn matching_services(services: &[Service], query: &str) -> Vec<usize> {
+    let all = 0..services.len();
+    if let Some(tag) = query.strip_prefix('@') {
+        return all.filter(|&i| services[i].tags.iter().any(|t| t == tag)).collect();
+    }
+
+    let name = |i: usize| services[i].name.as_str();
+    if is_glob(query) {
+        return all.filter(|&i| matches_glob(query, name(i))).collect();
+    }
+    let exact: Vec<usize> = all.clone().filter(|&i| name(i) == query).collect();
+    if !exact.is_empty() {
+        return exact;
+    }
+    let segment: Vec<usize> = all.clone().filter(|&i| name(i).rsplit(':').next() == Some(query)).collect();
+    if !segment.is_empty() {
+        return segment;
+    }
+    let substring: Vec<usize> = all.clone().filter(|&i| name(i).contains(query)).collect();
+    if !substring.is_empty() {
+        return substring;
+    }
+    all.filter(|&i| is_subsequence(query, name(i))).collect()
+}
+
+/// Whether a query uses glob syntax rather than naming a service
+pub fn is_glob(query: &str) -> bool {
+    query.contains(['*', '?', '['])
+}
+
+/// Whether a service name, or its last name segment, matches a glob: `*` matches any run of
+/// characters (`:` included), `?` any one character, and `[a-z]`/`[!x]` one from a set
+pub fn matches_glob(pattern: &str, name: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let matches = |text: &str| glob_match(&pattern, &text.chars().collect::<Vec<_>>());
+    matches(name) || name.rsplit_once(':').is_some_and(|(_, segment)| matches(segment))
+}
+
+/// Whether the whole of `text` matches a glob
+pub fn glob_matches(pattern: &str, text: &str) -> bool {
+    glob_match(&pattern.chars().collect::<Vec<_>>(), &text.chars().collect::<Vec<_>>())
+}
+
+fn glob_match(pattern: &[char], text: &[char]) -> bool {
+    // Where the last `*` was, and how much text it has swallowed so far, to backtrack to
+    let mut star: Option<(usize, usize)> = None;
+    let (mut p, mut t) = (0, 0);
+    while t < text.len() {
+        if p < pattern.len() && pattern[p] == '*' {
+            star = Some((p, t));
+            p += 1;
+            continue;
+        }
+        if p < pattern.len()
+            && let Some(len) = match_one(&pattern[p..], text[t])
+        {
+            p += len;
+            t += 1;
+            continue;
+        }
+        match star {
+            Some((star_p, star_t)) => {
+                star = Some((star_p, star_t + 1));
+                p = star_p + 1;
+                t = star_t + 1;
+            }
+            None => return false,
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+/// Whether the pattern element at the start of `pattern` matches `c`, and how many pattern
+/// characters it spans
+fn match_one(pattern: &[char], c: char) -> Option<usize> {
+    match pattern[0] {
+        '?' => Some(1),
+        '[' => {
+            // An unclosed `[` is just a bracket
+            let Some(end) = pattern.iter().skip(2).position(|&p| p == ']').map(|i| i + 2) else {
+                return (c == '[').then_some(1);
+            };
+            let (negated, set) = match pattern[1] {
+                '!' | '^' => (true, &pattern[2..end]),
+                _ => (false, &pattern[1..end]),
+            };
+            let mut found = false;
+            let mut i = 0;
+            while i < set.len() {
+                if i + 2 < set.len() && set[i + 1] == '-' {
+                    found |= (set[i]..=set[i + 2]).contains(&c);
+                    i += 3;
+                } else {
+                    found |= set[i] == c;
+                    i += 1;
+                }
+            }
+            (found != negated).then_some(end + 1)
+        }
+        p => (p == c).then_some(1),
+    }
+}
+
+/// Whether all characters of `query` appear in `name` in order
+fn is_subsequence(query: &str, name: &str) -> bool {
+    let mut chars = name.chars();
+    query.chars().all(|q| chars.any(|c| c == q))
+}
+
+pub fn theme() -> ColorfulTheme {
+    ColorfulTheme {
+        defaults_style: Style::new().dim(),
+        prompt_style: Style::new().bold(),
+        prompt_prefix: style("?".to_string()).green().bold(),
+        success_prefix: style("✓".to_string()).green().bold(),
+        error_prefix: style("✗".to_string()).red().bold(),
+        checked_item_prefix: style("  ◉".to_string()).green(),
+        unchecked_item_prefix: style("  ○".to_string()).dim(),
+        active_item_style: Style::new().cyan().bold(),
+        inactive_item_style: Style::new().dim(),
+        active_item_prefix: style("❯".to_string()).cyan().bold(),
+        ..ColorfulTheme::default()
+    }
+}
+
+fn destructive_theme() -> ColorfulTheme {
+    ColorfulTheme {
+        defaults_style: Style::new().dim(),
+        prompt_style: Style::new().bold(),
+        prompt_prefix: style("?".to_string()).yellow().bold(),
+        success_prefix: style("✓".to_string()).green().bold(),
+        error_prefix: style("✗".to_string()).red().bold(),
+        checked_item_prefix: style("  ◉".to_string()).red(),
+        unchecked_item_prefix: style("  ○".to_string()).dim(),
+        active_item_style: Style::new().yellow().bold(),
+        inactive_item_style: Style::new().dim(),
+        active_item_prefix: style("❯".to_string()).yellow().bold(),
+        ..ColorfulTheme::default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::discovery::FrameworkType;
+    use std::path::PathBuf;
+
+    fn service(name: &str, tags: &[&str]) -> Service {
+        Service {
+            name: name.to_string(),
+            path: PathBuf::from("/repo").join(name),
+            framework: FrameworkType::Unknown,
+            port: None,
+            scheme: Default::default(),
+            base_path: None,
+            tags: tags.iter().map(|t| t.to_string()).collect(),
+            command: None,
+            env: Default::default(),
+            framework_env: Default::default(),
+            package_name: None,
+            priority: Default::default(),
+        }
+    }
+
+    fn names<'a>(services: &'a [Service], query: &str) -> Vec<&'a str> {
+        matching_services(services, query)
+            .into_iter()
+            .map(|i| services[i].name.as_str())
+            .collect()
+    }
+
+    fn monorepo() -> Vec<Service> {
+        vec![
+            service("apps:web", &["frontend"]),
+            service("apps:admin", &["frontend"]),
+            service("services:api", &[]),
+            service("web", &[]),
+        ]
+    }
+
+    #[test]
+    fn star_matches_any_run_of_characters() {
+        assert!(glob_matches("apps:*", "apps:web"));
+        assert!(glob_matches("*:web", "apps:web"));
+        assert!(glob_matches("a*b*c", "aXXbYYc"));
+        assert!(glob_matches("*", ""));
+        assert!(!glob_matches("apps:*", "services:api"));
+    }
+
+    #[test]
+    fn question_mark_matches_exactly_one_character() {
+        assert!(glob_matches("we?", "web"));
+        assert!(!glob_matches("we?", "we"));
+        assert!(!glob_matches("we?", "webs"));
+    }
+
+    #[test]
+    fn brackets_match_sets_ranges_and_negations() {
+        assert!(glob_matches("[a-c]pi", "api"));
+        assert!(!glob_matches("[!a]pi", "api"));
+        assert!(glob_matches("[^x]pi", "api"));
+        assert!(glob_matches("v[12]", "v2"));
+        // An unclosed bracket is literal
+        assert!(glob_matches("a[b", "a[b"));
+    }
+
+    #[test]
+    fn globs_also_match_the_last_name_segment() {
+        assert!(matches_glob("w*", "apps:web"));
+        assert!(!matches_glob("a*s", "apps:web"));
+    }
+
+    #[test]
+    fn globs_select_every_match() {
+        let services = monorepo();
+        assert_eq!(names(&services, "apps:*"), ["apps:web", "apps:admin"]);
+        assert_eq!(names(&services, "*i"), ["services:api"]);
+    }
+
+    #[test]
+    fn tags_select_every_tagged_service() {
+        let services = monorepo();
+        assert_eq!(names(&services, "@frontend"), ["apps:web", "apps:admin"]);
+        assert!(names(&services, "@backend").is_empty());
+    }
+
+    #[test]
+    fn exact_names_win_over_segments() {
+        let services = monorepo();
+        assert_eq!(names(&services, "web"), ["web"]);
+        assert_eq!(names(&services, "apps:web"), ["apps:web"]);
+    }
+
+    #[test]
+    fn last_segment_matches_before_substrings() {
+        let services = monorepo();
+        assert_eq!(names(&services, "api"), ["services:api"]);
+        assert_eq!(names(&services, "admin"), ["apps:admin"]);
+    }
+
+    #[test]
+    fn substring_then_fuzzy_fallback() {
+        let services = monorepo();
+        assert_eq!(names(&services, "adm"), ["apps:admin"]);
+        assert_eq!(names(&services, "sapi"), ["services:api"]);
+        assert_eq!(names(&services, "wb"), ["apps:web", "web"]);
+        assert!(names(&services, "zzz").is_empty());
+    }
+}
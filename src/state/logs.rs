@@ -0,0 +1,237 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+use crate::config;
+use crate::discovery::Service;
+
+/// Maps services to log files under the logs directory, keyed by [`log_key`].
+///
+/// Log files used to be named after a hash of the service path, which orphaned them
+/// whenever a repo moved and made collisions impossible to trace back. The manifest
+/// keeps the mapping readable and records where each entry came from.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct LogManifest {
+    pub entries: BTreeMap<String, LogEntry>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LogEntry {
+    /// Path relative to the logs directory
+    pub file: PathBuf,
+    #[serde(with = "crate::state::os_path")]
+    pub project_path: PathBuf,
+    #[serde(with = "crate::state::os_path")]
+    pub service_path: PathBuf,
+    /// `name` in the service's package.json, to recognize it after a move
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub package_name: Option<String>,
+}
+
+impl LogManifest {
+    fn path() -> PathBuf {
+        config::get_log_manifest_file()
+    }
+
+    pub fn load() -> Self {
+        let mut manifest: Self = std::fs::read_to_string(Self::path())
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default();
+        manifest.rekey_legacy();
+        manifest
+    }
+
+    /// Entries used to be keyed `project/service` by directory name, which two checkouts
+    /// of the same repo shared
+    fn rekey_legacy(&mut self) {
+        let legacy: Vec<String> = self
+            .entries
+            .iter()
+            .filter(|(key, entry)| entry.service_name(key).is_none())
+            .map(|(key, _)| key.clone())
+            .collect();
+        for key in legacy {
+            let entry = self.entries.remove(&key).expect("key was just listed");
+            let Some((_, service_name)) = key.split_once('/') else {
+                continue;
+            };
+            self.entries.entry(log_key(&entry.project_path, service_name)).or_insert(entry);
+        }
+    }
+
+    pub fn save(&self) -> std::io::Result<()> {
+        if let Some(parent) = Self::path().parent() {
+            config::create_private_dir(parent)?;
+        }
+        let content = serde_json::to_string_pretty(self)?;
+        config::write_private(&Self::path(), content)
+    }
+
+    /// When a service's log file was last written, without registering a file for it
+    pub fn last_written(&self, project_path: &Path, service_name: &str) -> Option<SystemTime> {
+        let entry = self.entries.get(&log_key(project_path, service_name))?;
+        std::fs::metadata(config::get_logs_dir().join(&entry.file))
+            .and_then(|m| m.modified())
+            .ok()
+    }
+}
+
+impl LogEntry {
+    /// The service this entry is for, given its key in the manifest
+    pub fn service_name<'a>(&self, key: &'a str) -> Option<&'a str> {
+        key.strip_prefix(&log_key(&self.project_path, ""))
+    }
+}
+
+/// Manifest key for a service. Keyed on the project's canonical path rather than its name,
+/// so two checkouts of the same repo keep separate logs.
+pub fn log_key(project_path: &Path, service_name: &str) -> String {
+    format!("{}/{}", project_path.display(), service_name)
+}
+
+/// Total size in bytes of everything under the logs directory
+pub fn logs_disk_usage() -> u64 {
+    walkdir::WalkDir::new(config::get_logs_dir())
+        .into_iter()
+        .filter_map(|e| e.ok())
+        .filter_map(|e| e.metadata().ok())
+        .filter(|m| m.is_file())
+        .map(|m| m.len())
+        .sum()
+}
+
+/// Resolve (and register) the log file for a service.
+///
+/// Files live at `logs/<project>/<service>.log`. A legacy hash-named log for the same
+/// service path is moved into place the first time the service is seen.
+pub fn service_log_file(project_name: &str, project_path: &Path, service: &Service) -> PathBuf {
+    let logs_dir = config::get_logs_dir();
+    let service_name = service.name.as_str();
+    let service_path = service.path.as_path();
+    let key = log_key(project_path, service_name);
+    let mut manifest = LogManifest::load();
+
+    if let Some(entry) = manifest.entries.get_mut(&key) {
+        // Services resolved from state don't know their package name; keep the recorded one
+        let package_name = service.package_name.clone().or(entry.package_name.clone());
+        if entry.service_path != service_path || entry.project_path != project_path || entry.package_name != package_name {
+            entry.service_path = service_path.to_path_buf();
+            entry.project_path = project_path.to_path_buf();
+            entry.package_name = package_name;
+            let _ = manifest.save();
+        }
+        return logs_dir.join(&manifest.entries[&key].file);
+    }
+
+    let file = unique_file_name(&manifest, project_name, service_name);
+    let log_file = logs_dir.join(&file);
+
+    let legacy = legacy_log_file(service_path);
+    if legacy.exists() && !log_file.exists() {
+        if let Some(parent) = log_file.parent() {
+            let _ = config::create_private_dir(parent);
+        }
+        let _ = std::fs::rename(&legacy, &log_file);
+    }
+
+    manifest.entries.insert(
+        key,
+        LogEntry {
+            file,
+            project_path: project_path.to_path_buf(),
+            service_path: service_path.to_path_buf(),
+            package_name: service.package_name.clone(),
+        },
+    );
+    let _ = manifest.save();
+
+    log_file
+}
+
+/// Pick a file name for a new entry, avoiding files already claimed in the manifest
+fn unique_file_name(manifest: &LogManifest, project_name: &str, service_name: &str) -> PathBuf {
+    let project_dir = sanitize(project_name);
+    let stem = sanitize(service_name);
+    let taken = |candidate: &Path| manifest.entries.values().any(|e| e.file == candidate);
+
+    let mut file = Path::new(&project_dir).join(format!("{}.log", stem));
+    let mut suffix = 2;
+    while taken(&file) {
+        file = Path::new(&project_dir).join(format!("{}-{}.log", stem, suffix));
+        suffix += 1;
+    }
+    file
+}
+
+/// Make a name safe to use as a single path component on every platform
+fn sanitize(name: &str) -> String {
+    name.chars()
+        .map(|c| match c {
+            ':' | '/' | '\\' | '*' | '?' | '"' | '<' | '>' | '|' => '_',
+            c => c,
+        })
+        .collect()
+}
+
+/// Log file location used before the manifest existed
+fn legacy_log_file(service_path: &Path) -> PathBuf {
+    use std::collections::hash_map::DefaultHasher;
+    use std::hash::{Hash, Hasher};
+
+    let mut hasher = DefaultHasher::new();
+    service_path.hash(&mut hasher);
+    let hash = format!("{:x}", hasher.finish());
+    let short_hash = &hash[..8.min(hash.len())];
+
+    // Written before logs could move elsewhere, so always in the data dir
+    config::get_data_dir().join("logs").join(format!("{}.log", short_hash))
+}
+
+/// Last sequence number handed out by this process, see [`next_log_seq`]
+static LAST_LOG_SEQ: AtomicU64 = AtomicU64::new(0);
+static LOG_CLOCK: OnceLock<(Instant, u64)> = OnceLock::new();
+
+/// Sequence number for a log line captured now.
+///
+/// Microseconds since the epoch, read off a monotonic clock anchored to the wall clock once
+/// per process, and bumped past the previous number. Lines from one process therefore
+/// sort in capture order even within the same millisecond or across a wall clock change,
+/// while lines from different processes still interleave by time.
+pub fn next_log_seq() -> u64 {
+    let (anchor, epoch_micros) = LOG_CLOCK.get_or_init(|| {
+        let since_epoch = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default();
+        (Instant::now(), since_epoch.as_micros() as u64)
+    });
+    let now = epoch_micros + anchor.elapsed().as_micros() as u64;
+    let previous = LAST_LOG_SEQ
+        .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |last| Some(now.max(last + 1)))
+        .unwrap_or_default();
+    now.max(previous + 1)
+}
+
+/// A line as stored in a service log file: `<seq> [service] message`
+pub fn encode_log_line(seq: u64, service: &str, message: &str) -> String {
+    format!("{} [{}] {}\n", seq, service, message)
+}
+
+/// Split a stored log line into its sequence number and message. Lines written before
+/// sequencing have no number; their `[service]` prefix is dropped all the same.
+pub fn parse_log_line(line: &str) -> (Option<u64>, &str) {
+    let (seq, rest) = match line.split_once(' ') {
+        Some((seq, rest)) if rest.starts_with('[') => match seq.parse() {
+            Ok(seq) => (Some(seq), rest),
+            Err(_) => (None, line),
+        },
+        _ => (None, line),
+    };
+    if rest.starts_with('[')
+        && let Some(idx) = rest.find(']')
+    {
+        return (seq, rest[idx + 1..].trim_start());
+    }
+    (seq, rest)
+}
@@ -0,0 +1,77 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::io::Write;
+use std::path::PathBuf;
+
+use super::Rename;
+use crate::config;
+
+/// Surrounds a mark's label in log files and on the console
+const MARK_RULE: &str = "====";
+
+/// A bookmark dropped into running services' logs by `groo mark`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Mark {
+    pub label: String,
+    /// Local time the mark was made, `HH:MM:SS`
+    pub time: String,
+    /// Services whose logs got the marker line
+    pub services: Vec<String>,
+}
+
+impl Mark {
+    /// The line written into logs, e.g. `==== 14:03:22 before reproducing bug ====`
+    pub fn line(&self) -> String {
+        format!("{} {} {} {}", MARK_RULE, self.time, self.label, MARK_RULE)
+    }
+
+    /// Record the mark so running `groo dev` sessions of the project echo it
+    pub fn append(&self, project_name: &str) -> Result<()> {
+        let path = marks_file(project_name);
+        if let Some(parent) = path.parent() {
+            config::create_private_dir(parent)?;
+        }
+        let mut file = config::private_file().create(true).append(true).open(path)?;
+        writeln!(file, "{}", serde_json::to_string(self)?)?;
+        Ok(())
+    }
+}
+
+/// Point a project's recorded marks at services' new names after their directories moved
+pub fn rename_in_marks(project_name: &str, renames: &[Rename]) -> Result<()> {
+    let path = marks_file(project_name);
+    let Ok(content) = std::fs::read_to_string(&path) else {
+        return Ok(());
+    };
+    let mut touched = false;
+    let mut lines = Vec::new();
+    for line in content.lines() {
+        let Ok(mut mark) = serde_json::from_str::<Mark>(line) else {
+            lines.push(line.to_string());
+            continue;
+        };
+        for service in &mut mark.services {
+            if let Some(rename) = renames.iter().find(|r| r.from == *service) {
+                *service = rename.to.clone();
+                touched = true;
+            }
+        }
+        lines.push(serde_json::to_string(&mark)?);
+    }
+    if touched {
+        config::write_private(&path, lines.join("\n") + "\n")?;
+    }
+    Ok(())
+}
+
+pub fn marks_file(project_name: &str) -> PathBuf {
+    config::get_marks_dir().join(format!("{}.jsonl", project_name))
+}
+
+/// Whether a log message (without its `[service]` prefix) is a marker line, optionally
+/// one whose label contains `label`
+pub fn is_mark_line(message: &str, label: Option<&str>) -> bool {
+    message.starts_with(MARK_RULE)
+        && message.ends_with(MARK_RULE)
+        && label.is_none_or(|label| message.contains(label))
+}
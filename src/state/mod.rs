@@ -1,3 +1,14 @@
+mod logs;
+mod marks;
+pub mod os_path;
+mod reconcile;
+mod renames;
+mod session;
 mod tracker;
 
+pub use logs::*;
+pub use marks::*;
+pub use reconcile::*;
+pub use renames::*;
+pub use session::*;
 pub use tracker::*;
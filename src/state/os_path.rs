@@ -0,0 +1,51 @@
+//! Serde helpers for paths that may not be UTF-8.
+//!
+//! serde_json refuses to write such a `PathBuf`, which would fail saving state for a
+//! project checked out under one. UTF-8 paths are written as plain strings, as before;
+//! others as their raw bytes.
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::path::{Path, PathBuf};
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Stored {
+    Utf8(PathBuf),
+    Bytes(Vec<u8>),
+}
+
+pub fn serialize<S: Serializer>(path: &Path, serializer: S) -> Result<S::Ok, S::Error> {
+    match path.to_str() {
+        Some(path) => path.serialize(serializer),
+        None => bytes(path).serialize(serializer),
+    }
+}
+
+pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<PathBuf, D::Error> {
+    Ok(match Stored::deserialize(deserializer)? {
+        Stored::Utf8(path) => path,
+        Stored::Bytes(bytes) => from_bytes(bytes),
+    })
+}
+
+#[cfg(unix)]
+fn bytes(path: &Path) -> Vec<u8> {
+    use std::os::unix::ffi::OsStrExt;
+    path.as_os_str().as_bytes().to_vec()
+}
+
+#[cfg(not(unix))]
+fn bytes(path: &Path) -> Vec<u8> {
+    path.to_string_lossy().into_owned().into_bytes()
+}
+
+#[cfg(unix)]
+fn from_bytes(bytes: Vec<u8>) -> PathBuf {
+    use std::os::unix::ffi::OsStringExt;
+    PathBuf::from(std::ffi::OsString::from_vec(bytes))
+}
+
+#[cfg(not(unix))]
+fn from_bytes(bytes: Vec<u8>) -> PathBuf {
+    PathBuf::from(String::from_utf8_lossy(&bytes).into_owned())
+}
@@ -0,0 +1,287 @@
+use crate::backend::ProcessBackend;
+use crate::discovery::Service;
+
+use super::session::{now_millis, Session};
+use super::tracker::State;
+
+/// `ps` reports start times to the second, and a service is spawned just before its run
+/// is recorded
+const START_TIME_SLACK_MS: u64 = 2000;
+
+/// A service whose tracked state disagrees with what is actually running
+#[derive(Debug)]
+pub enum Mismatch {
+    /// State tracks the process, but it has exited and its port is free
+    Exited { service: String, pid: u32 },
+    /// The tracked process is alive, but nothing listens on its port
+    NotListening { service: String, pid: u32, port: u16 },
+    /// The port is in use by processes state doesn't know about
+    Untracked { service: String, port: u16, pids: Vec<u32> },
+}
+
+impl Mismatch {
+    pub fn service(&self) -> &str {
+        match self {
+            Mismatch::Exited { service, .. }
+            | Mismatch::NotListening { service, .. }
+            | Mismatch::Untracked { service, .. } => service,
+        }
+    }
+}
+
+/// Compare state for a project against the processes and ports on this machine.
+///
+/// Untracked listeners are only reported when `include_untracked` is set, since a service
+/// started outside groo is normal.
+pub fn find_mismatches(
+    state: &State,
+    project_name: &str,
+    services: &[Service],
+    include_untracked: bool,
+    backend: &dyn ProcessBackend,
+) -> Vec<Mismatch> {
+    let tracked = state.get_project(project_name).map(|p| &p.services);
+    let mut mismatches = Vec::new();
+
+    for service in services {
+        match tracked.and_then(|t| t.get(&service.name)) {
+            Some(entry) => {
+                let pid_alive = backend.is_pid_running(entry.pid);
+                let Some(port) = entry.port else {
+                    if !pid_alive {
+                        mismatches.push(Mismatch::Exited { service: service.name.clone(), pid: entry.pid });
+                    }
+                    continue;
+                };
+                match (pid_alive, backend.is_port_in_use(port)) {
+                    (true, true) => {}
+                    (false, false) => {
+                        mismatches.push(Mismatch::Exited { service: service.name.clone(), pid: entry.pid })
+                    }
+                    (true, false) => mismatches.push(Mismatch::NotListening {
+                        service: service.name.clone(),
+                        pid: entry.pid,
+                        port,
+                    }),
+                    // The tracked process is gone but someone else holds the port
+                    (false, true) => mismatches.push(Mismatch::Untracked {
+                        service: service.name.clone(),
+                        port,
+                        pids: backend.pids_on_port(port),
+                    }),
+                }
+            }
+            None if include_untracked => {
+                if let Some(port) = service.port
+                    && backend.is_port_in_use(port)
+                {
+                    mismatches.push(Mismatch::Untracked {
+                        service: service.name.clone(),
+                        port,
+                        pids: backend.pids_on_port(port),
+                    });
+                }
+            }
+            None => {}
+        }
+    }
+
+    mismatches
+}
+
+/// A service process left running by a `groo dev` or `groo restart` that crashed, and that
+/// state doesn't know about
+#[derive(Debug)]
+pub struct Orphan {
+    pub service: String,
+    pub pid: u32,
+    pub port: Option<u16>,
+    /// Whether the process is known to be the one the session started, rather than a
+    /// later one that reused its PID. Without a start time to compare (Windows) it isn't.
+    pub verified: bool,
+}
+
+/// A session whose groo process died before ending it
+#[derive(Debug)]
+pub struct CrashedSession {
+    pub session: Session,
+    pub orphans: Vec<Orphan>,
+}
+
+/// Find sessions whose groo process is gone but that never ended, and the services they
+/// left running. Crashed sessions with nothing left running come back with no orphans;
+/// nothing is written, so closing them is up to the caller.
+pub fn find_orphans(state: &State, backend: &dyn ProcessBackend) -> Vec<CrashedSession> {
+    let mut crashed = Vec::new();
+    for session in Session::load_all() {
+        // Sessions recorded before groo stored its pid can't be told apart from live ones
+        let Some(groo_pid) = session.pid else {
+            continue;
+        };
+        // A groo that may still be running isn't treated as crashed
+        if session.ended_at.is_some() || alive_since(backend, groo_pid, session.started_at) != Some(false) {
+            continue;
+        }
+
+        let tracked = state.get_project(&session.project).map(|p| &p.services);
+        let orphans: Vec<Orphan> = session
+            .services
+            .iter()
+            .filter(|run| run.exited_at.is_none())
+            .filter_map(|run| Some((run, run.pid?)))
+            .filter(|(run, pid)| tracked.and_then(|t| t.get(&run.name)).is_none_or(|s| s.pid != *pid))
+            .filter_map(|(run, pid)| {
+                let verified = match alive_since(backend, pid, run.started_at) {
+                    Some(false) => return None,
+                    Some(true) => true,
+                    None => false,
+                };
+                Some(Orphan {
+                    service: run.name.clone(),
+                    pid,
+                    port: run.port,
+                    verified,
+                })
+            })
+            .collect();
+
+        crashed.push(CrashedSession { session, orphans });
+    }
+    crashed
+}
+
+/// Whether `pid` is running and already was at `millis`, rather than being a later
+/// process that reused the PID. `None` when it's running but its start time is unknown
+/// (Windows).
+fn alive_since(backend: &dyn ProcessBackend, pid: u32, millis: u64) -> Option<bool> {
+    if !backend.is_pid_running(pid) {
+        return Some(false);
+    }
+    let uptime = backend.process_stats(pid).and_then(|stats| stats.uptime)?;
+    Some(now_millis().saturating_sub(uptime.as_millis() as u64) <= millis + START_TIME_SLACK_MS)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::MockBackend;
+    use crate::discovery::FrameworkType;
+    use crate::state::ServiceState;
+    use std::path::PathBuf;
+
+    fn service(name: &str, port: Option<u16>) -> Service {
+        Service {
+            name: name.to_string(),
+            path: PathBuf::from("/repo").join(name),
+            framework: FrameworkType::Unknown,
+            port,
+            scheme: Default::default(),
+            base_path: None,
+            tags: Vec::new(),
+            command: None,
+            env: Default::default(),
+            framework_env: Default::default(),
+            package_name: None,
+            priority: Default::default(),
+        }
+    }
+
+    fn track(state: &mut State, name: &str, pid: u32, port: Option<u16>) {
+        state.add_service(
+            "repo",
+            PathBuf::from("/repo"),
+            name,
+            ServiceState {
+                pid,
+                port,
+                scheme: Default::default(),
+                base_path: None,
+            },
+        );
+    }
+
+    #[test]
+    fn running_and_listening_is_no_mismatch() {
+        let mut state = State::default();
+        track(&mut state, "web", 10, Some(3000));
+        let backend = MockBackend::default().with_process(10, Some(3000));
+
+        let mismatches = find_mismatches(&state, "repo", &[service("web", Some(3000))], true, &backend);
+        assert!(mismatches.is_empty());
+    }
+
+    #[test]
+    fn exited_process_with_free_port() {
+        let mut state = State::default();
+        track(&mut state, "web", 10, Some(3000));
+        track(&mut state, "worker", 11, None);
+        let services = [service("web", Some(3000)), service("worker", None)];
+
+        let mismatches = find_mismatches(&state, "repo", &services, false, &MockBackend::default());
+        assert!(matches!(
+            mismatches.as_slice(),
+            [Mismatch::Exited { pid: 10, .. }, Mismatch::Exited { pid: 11, .. }]
+        ));
+    }
+
+    #[test]
+    fn alive_but_not_listening() {
+        let mut state = State::default();
+        track(&mut state, "web", 10, Some(3000));
+        let backend = MockBackend::default().with_process(10, None);
+
+        let mismatches = find_mismatches(&state, "repo", &[service("web", Some(3000))], false, &backend);
+        assert!(matches!(
+            mismatches.as_slice(),
+            [Mismatch::NotListening { pid: 10, port: 3000, .. }]
+        ));
+    }
+
+    #[test]
+    fn port_taken_over_by_another_process() {
+        let mut state = State::default();
+        track(&mut state, "web", 10, Some(3000));
+        let backend = MockBackend::default().with_process(20, Some(3000));
+
+        let mismatches = find_mismatches(&state, "repo", &[service("web", Some(3000))], false, &backend);
+        assert!(matches!(
+            mismatches.as_slice(),
+            [Mismatch::Untracked { port: 3000, pids, .. }] if pids == &[20]
+        ));
+    }
+
+    #[test]
+    fn untracked_listeners_only_when_asked() {
+        let state = State::default();
+        let backend = MockBackend::default().with_process(20, Some(3000));
+        let services = [service("web", Some(3000))];
+
+        assert!(find_mismatches(&state, "repo", &services, false, &backend).is_empty());
+        assert!(matches!(
+            find_mismatches(&state, "repo", &services, true, &backend).as_slice(),
+            [Mismatch::Untracked { port: 3000, .. }]
+        ));
+    }
+
+    #[test]
+    fn killed_process_shows_as_exited() {
+        let mut state = State::default();
+        track(&mut state, "web", 10, Some(3000));
+        let backend = MockBackend::default().with_process(10, Some(3000));
+        let services = [service("web", Some(3000))];
+
+        assert!(backend.kill(10));
+        assert!(matches!(
+            find_mismatches(&state, "repo", &services, false, &backend).as_slice(),
+            [Mismatch::Exited { pid: 10, .. }]
+        ));
+    }
+
+    #[test]
+    fn start_time_unknown_is_not_treated_as_dead() {
+        let backend = MockBackend::default().with_process(10, None);
+
+        assert_eq!(alive_since(&backend, 10, now_millis()), None);
+        assert_eq!(alive_since(&backend, 11, now_millis()), Some(false));
+    }
+}
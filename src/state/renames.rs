@@ -0,0 +1,105 @@
+use std::path::Path;
+
+use super::{log_key, rename_in_marks, LogManifest, Session, State};
+use crate::config;
+use crate::discovery::Service;
+
+/// A service whose directory was renamed or moved
+#[derive(Debug)]
+pub struct Rename {
+    pub from: String,
+    pub to: String,
+}
+
+/// Find services whose directory moved since groo last saw them and carry their log file,
+/// state entry, exit history and session history over to the new name.
+///
+/// Service names come from directories, so a move looks like one service vanishing and
+/// another appearing. The package.json `name` ties them together: a log entry for the same
+/// package whose old directory no longer holds a package.json was this service.
+pub fn migrate_renames(state: &mut State, project_name: &str, project_path: &Path, services: &[Service]) -> Vec<Rename> {
+    let logs_dir = config::get_logs_dir();
+    let mut manifest = LogManifest::load();
+    let mut changed = false;
+    let mut renames = Vec::new();
+
+    for service in services {
+        let Some(package_name) = &service.package_name else {
+            continue;
+        };
+        let key = log_key(project_path, &service.name);
+        let old = manifest
+            .entries
+            .iter()
+            .find(|(k, e)| {
+                **k != key
+                    && e.project_path == project_path
+                    && e.package_name.as_ref() == Some(package_name)
+                    && !e.service_path.join("package.json").exists()
+            })
+            .and_then(|(k, e)| Some((k.clone(), e.service_name(k)?.to_string())));
+        // `groo logs` and `groo mark` may have registered the new name before the move was
+        // noticed; an entry with nothing behind it yet gives way to the old one
+        let replaceable = manifest
+            .entries
+            .get(&key)
+            .is_none_or(|entry| is_unused(state, project_name, &service.name, &logs_dir.join(&entry.file)));
+
+        let Some((old_key, from)) = old.filter(|_| replaceable) else {
+            if let Some(entry) = manifest.entries.get_mut(&key)
+                && entry.package_name.as_ref() != Some(package_name)
+            {
+                // Remembered for when the directory moves
+                entry.package_name = Some(package_name.clone());
+                changed = true;
+            }
+            continue;
+        };
+        if let Some(unused) = manifest.entries.remove(&key) {
+            let _ = std::fs::remove_file(logs_dir.join(&unused.file));
+        }
+        let mut entry = manifest.entries.remove(&old_key).expect("key was just found");
+        entry.service_path = service.path.clone();
+        manifest.entries.insert(key, entry);
+        changed = true;
+        renames.push(Rename {
+            from,
+            to: service.name.clone(),
+        });
+    }
+    if changed {
+        let _ = manifest.save();
+    }
+    if renames.is_empty() {
+        return renames;
+    }
+
+    for rename in &renames {
+        state.rename_service(project_name, &rename.from, &rename.to);
+    }
+    // Keeps `preselect = "last_used"`, `groo report` and `groo env diff --session` working
+    for mut session in Session::load_all().into_iter().filter(|s| s.project == project_name) {
+        let mut touched = false;
+        for run in &mut session.services {
+            if let Some(rename) = renames.iter().find(|r| r.from == run.name) {
+                run.name = rename.to.clone();
+                touched = true;
+            }
+        }
+        if touched {
+            let _ = session.save();
+        }
+    }
+    // Marks list the services whose logs got them
+    let _ = rename_in_marks(project_name, &renames);
+    renames
+}
+
+/// Whether a service has nothing recorded under its name: no log output, exits, endpoint
+/// or tracked process
+fn is_unused(state: &State, project_name: &str, service_name: &str, log_file: &Path) -> bool {
+    std::fs::metadata(log_file).ok().is_none_or(|m| m.len() == 0)
+        && state.exit_history(project_name, service_name).is_empty()
+        && state.last_endpoint(project_name, service_name).is_none()
+        && state.get_project(project_name).is_none_or(|p| !p.services.contains_key(service_name))
+}
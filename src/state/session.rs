@@ -0,0 +1,319 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::config;
+use crate::diag;
+use crate::discovery::{get_git_branch, get_git_commit, load_env_files};
+
+/// Number of session files kept on disk; older ones are pruned when a new session starts.
+const MAX_SESSIONS: usize = 200;
+
+/// Format of session files. 0 is from before env values were replaced with fingerprints.
+const SESSION_VERSION: u32 = 1;
+
+/// A single run of a service within a session
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ServiceRun {
+    pub name: String,
+    /// Unix time in milliseconds when the process was spawned
+    pub started_at: u64,
+    /// Time from spawn until the service's port accepted connections
+    #[serde(default)]
+    pub ready_after_ms: Option<u64>,
+    /// True if the port wasn't accepting connections by the end of the readiness probe
+    #[serde(default)]
+    pub ready_timed_out: bool,
+    #[serde(default)]
+    pub exited_at: Option<u64>,
+    #[serde(default)]
+    pub exit_code: Option<i32>,
+    /// True if the process exited on its own with a failure status
+    #[serde(default)]
+    pub crashed: bool,
+    /// True if groo stopped the process (shutdown) rather than it exiting on its own
+    #[serde(default)]
+    pub stopped: bool,
+    /// True if this crash was one too many for `on_crash = "restart"` and the service
+    /// was left stopped
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub crash_looping: bool,
+    /// Last lines the service printed before it was given up on
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub crash_tail: Vec<String>,
+    /// Port the service was started on
+    #[serde(default)]
+    pub port: Option<u16>,
+    /// Process groo spawned for the run
+    #[serde(default)]
+    pub pid: Option<u32>,
+    /// Environment variables set on top of the shell's, e.g. from `groo dev --env`, as
+    /// [`env_fingerprint`]s. Values may be secrets and are never stored.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub env_hashes: BTreeMap<String, String>,
+    /// What the service's env files defined when it started, as fingerprints, for
+    /// `groo env diff --session`
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub env_file_hashes: BTreeMap<String, String>,
+}
+
+/// Metadata for one `groo dev`/`groo restart` invocation
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Session {
+    /// [`SESSION_VERSION`] when written; older files are rewritten by [`Session::start`]
+    #[serde(default)]
+    pub version: u32,
+    pub id: String,
+    pub project: String,
+    #[serde(with = "crate::state::os_path")]
+    pub path: PathBuf,
+    pub started_at: u64,
+    #[serde(default)]
+    pub ended_at: Option<u64>,
+    /// The groo process running the session. If it's gone while the session hasn't
+    /// ended, groo itself crashed and may have left services running.
+    #[serde(default)]
+    pub pid: Option<u32>,
+    /// Branch checked out when the session started; `None` on a detached HEAD
+    #[serde(default)]
+    pub branch: Option<String>,
+    /// Abbreviated SHA checked out when the session started
+    #[serde(default)]
+    pub commit: Option<String>,
+    #[serde(default)]
+    pub services: Vec<ServiceRun>,
+}
+
+impl Session {
+    pub fn new(project_name: &str, project_path: PathBuf) -> Self {
+        let started_at = now_millis();
+        Self {
+            version: SESSION_VERSION,
+            id: format!("{}-{}", project_name, started_at),
+            project: project_name.to_string(),
+            branch: get_git_branch(&project_path),
+            commit: get_git_commit(&project_path),
+            path: project_path,
+            started_at,
+            ended_at: None,
+            pid: Some(std::process::id()),
+            services: Vec::new(),
+        }
+    }
+
+    /// Load all recorded sessions, oldest first. Unreadable files are skipped.
+    pub fn load_all() -> Vec<Session> {
+        let mut sessions: Vec<Session> = std::fs::read_dir(config::get_sessions_dir())
+            .map(|entries| {
+                entries
+                    .filter_map(|e| e.ok())
+                    .filter(|e| e.path().extension().is_some_and(|ext| ext == "json"))
+                    .filter_map(|e| std::fs::read_to_string(e.path()).ok())
+                    .filter_map(|content| serde_json::from_str(&content).ok())
+                    .collect()
+            })
+            .unwrap_or_default();
+        sessions.sort_by_key(|s| s.started_at);
+        sessions
+    }
+
+    pub fn save(&self) -> Result<()> {
+        let sessions_dir = config::get_sessions_dir();
+        config::create_private_dir(&sessions_dir)?;
+        let content = serde_json::to_string_pretty(self)?;
+        config::write_private(&sessions_dir.join(format!("{}.json", self.id)), content)?;
+        Ok(())
+    }
+
+    /// Persist a freshly started session, bring older session files up to the current
+    /// format and prune the oldest ones past the limit.
+    pub fn start(&self) -> Result<()> {
+        self.save()?;
+        let sessions = Self::load_all();
+        // Saving writes only known fields, which drops the env values version 0 kept
+        for old in sessions.iter().filter(|s| s.version < SESSION_VERSION) {
+            let _ = Session { version: SESSION_VERSION, ..old.clone() }.save();
+        }
+        if sessions.len() > MAX_SESSIONS {
+            let sessions_dir = config::get_sessions_dir();
+            for old in &sessions[..sessions.len() - MAX_SESSIONS] {
+                let _ = std::fs::remove_file(sessions_dir.join(format!("{}.json", old.id)));
+            }
+        }
+        Ok(())
+    }
+
+    pub fn add_service(
+        &mut self,
+        service_name: &str,
+        service_dir: &Path,
+        port: Option<u16>,
+        pid: Option<u32>,
+        env: &BTreeMap<String, String>,
+    ) {
+        self.services.push(ServiceRun {
+            name: service_name.to_string(),
+            started_at: now_millis(),
+            ready_after_ms: None,
+            ready_timed_out: false,
+            exited_at: None,
+            exit_code: None,
+            crashed: false,
+            stopped: false,
+            crash_looping: false,
+            crash_tail: Vec::new(),
+            port,
+            pid,
+            env_hashes: env_fingerprints(env),
+            env_file_hashes: env_fingerprints(&load_env_files(service_dir)),
+        });
+    }
+
+    pub fn mark_ready(&mut self, service_name: &str, ready_after_ms: u64) {
+        diag::record("session", format!("{} ready after {}ms", service_name, ready_after_ms));
+        if let Some(run) = self.current_run_mut(service_name) {
+            run.ready_after_ms = Some(ready_after_ms);
+        }
+    }
+
+    /// Record a service that didn't come up within its readiness probe
+    pub fn mark_not_ready(&mut self, service_name: &str) {
+        if let Some(run) = self.current_run_mut(service_name) {
+            run.ready_timed_out = true;
+        }
+    }
+
+    /// Record a process exiting on its own
+    pub fn mark_exited(&mut self, service_name: &str, exit_code: Option<i32>, crashed: bool) {
+        diag::record("session", format!("{} exited with {:?}{}", service_name, exit_code, if crashed { " (crashed)" } else { "" }));
+        if let Some(run) = self.current_run_mut(service_name) {
+            run.exited_at = Some(now_millis());
+            run.exit_code = exit_code;
+            run.crashed = crashed;
+        }
+    }
+
+    /// Record a process that groo shut down
+    pub fn mark_stopped(&mut self, service_name: &str, exit_code: Option<i32>) {
+        diag::record("session", format!("{} stopped with {:?}", service_name, exit_code));
+        if let Some(run) = self.current_run_mut(service_name) {
+            run.exited_at = Some(now_millis());
+            run.exit_code = exit_code;
+            run.stopped = true;
+        }
+    }
+
+    /// Record a service that kept crashing and won't be restarted again, with the output
+    /// that led up to its last crash
+    pub fn mark_crash_looping(&mut self, service_name: &str, crash_tail: Vec<String>) {
+        diag::record("session", format!("{} is crash-looping, not restarting", service_name));
+        if let Some(run) = self.services.iter_mut().rev().find(|r| r.name == service_name) {
+            run.crash_looping = true;
+            run.crash_tail = crash_tail;
+        }
+    }
+
+    pub fn finish(&mut self) {
+        diag::record("session", format!("{} for {} finished", self.id, self.project));
+        let now = now_millis();
+        for run in &mut self.services {
+            if run.exited_at.is_none() {
+                run.exited_at = Some(now);
+                run.stopped = true;
+            }
+        }
+        self.ended_at = Some(now);
+    }
+
+    /// The most recent session recorded for a project, which may still be running
+    pub fn latest_for_project(project_name: &str) -> Option<Session> {
+        Self::load_all()
+            .into_iter()
+            .rev()
+            .find(|s| s.project == project_name)
+    }
+
+    /// Names of services whose last run in this session ended on its own
+    pub fn exited_services(&self) -> Vec<String> {
+        let mut seen: Vec<&str> = Vec::new();
+        let mut names = Vec::new();
+        // Walk newest first so only each service's latest run counts
+        for run in self.services.iter().rev() {
+            if seen.contains(&run.name.as_str()) {
+                continue;
+            }
+            seen.push(&run.name);
+            if run.exited_at.is_some() && !run.stopped {
+                names.push(run.name.clone());
+            }
+        }
+        names
+    }
+
+    /// Last runs of services given up on as crash-looping in this session
+    pub fn crash_looping_runs(&self) -> Vec<&ServiceRun> {
+        let mut seen: Vec<&str> = Vec::new();
+        let mut runs = Vec::new();
+        for run in self.services.iter().rev() {
+            if seen.contains(&run.name.as_str()) {
+                continue;
+            }
+            seen.push(&run.name);
+            if run.crash_looping {
+                runs.push(run);
+            }
+        }
+        runs
+    }
+
+    /// Names of services still being probed for readiness in this session
+    pub fn starting_services(&self) -> Vec<String> {
+        if self.ended_at.is_some() {
+            return Vec::new();
+        }
+        let mut seen: Vec<&str> = Vec::new();
+        let mut names = Vec::new();
+        for run in self.services.iter().rev() {
+            if seen.contains(&run.name.as_str()) {
+                continue;
+            }
+            seen.push(&run.name);
+            if run.exited_at.is_none() && run.ready_after_ms.is_none() && !run.ready_timed_out {
+                names.push(run.name.clone());
+            }
+        }
+        names
+    }
+
+    fn current_run_mut(&mut self, service_name: &str) -> Option<&mut ServiceRun> {
+        self.services
+            .iter_mut()
+            .rev()
+            .find(|r| r.name == service_name && r.exited_at.is_none())
+    }
+}
+
+/// Fingerprint of an env var's value, enough to tell whether it changed without keeping
+/// it. FNV-1a, which unlike `DefaultHasher` stays the same across Rust releases.
+pub fn env_fingerprint(value: &str) -> String {
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for byte in value.bytes() {
+        hash ^= byte as u64;
+        hash = hash.wrapping_mul(0x0000_0100_0000_01b3);
+    }
+    format!("{:016x}", hash)
+}
+
+fn env_fingerprints(env: &BTreeMap<String, String>) -> BTreeMap<String, String> {
+    env.iter().map(|(key, value)| (key.clone(), env_fingerprint(value))).collect()
+}
+
+/// Current Unix time in milliseconds
+pub fn now_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
@@ -3,23 +3,120 @@ use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::path::PathBuf;
 
+use crate::backend::backend;
 use crate::config;
+use crate::diag;
+use crate::discovery::{service_url, Scheme};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ServiceState {
     pub pid: u32,
     pub port: Option<u16>,
+    #[serde(default)]
+    pub scheme: Scheme,
+    #[serde(default)]
+    pub base_path: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ProjectState {
+    #[serde(with = "crate::state::os_path")]
     pub path: PathBuf,
     pub services: HashMap<String, ServiceState>,
 }
 
+/// A project root groo was recently used in
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecentProject {
+    pub name: String,
+    #[serde(with = "crate::state::os_path")]
+    pub path: PathBuf,
+    /// Unix time in milliseconds
+    pub last_used: u64,
+}
+
+/// Number of recent project roots remembered
+const MAX_RECENT_PROJECTS: usize = 20;
+
+/// How a service's process ended on its own, kept long after it's gone so intermittent
+/// crashes (say, overnight) leave a trace
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExitEvent {
+    /// Unix time in milliseconds
+    pub at: u64,
+    #[serde(default)]
+    pub code: Option<i32>,
+    /// Signal that killed the process (Unix only)
+    #[serde(default)]
+    pub signal: Option<i32>,
+    /// How long the process had been running
+    pub uptime_ms: u64,
+}
+
+impl ExitEvent {
+    /// `exit code 1`, `killed by SIGSEGV`, ...
+    pub fn describe(&self) -> String {
+        match (self.signal, self.code) {
+            (Some(signal), _) => format!("killed by {}", signal_name(signal)),
+            // Dev scripts run through `sh`, which reports a child killed by a signal as 128+n
+            (None, Some(code)) if (129..160).contains(&code) => {
+                format!("exit code {} ({})", code, signal_name(code - 128))
+            }
+            (None, Some(code)) => format!("exit code {}", code),
+            (None, None) => "exited".to_string(),
+        }
+    }
+}
+
+fn signal_name(signal: i32) -> String {
+    let name = match signal {
+        1 => "SIGHUP",
+        2 => "SIGINT",
+        3 => "SIGQUIT",
+        6 => "SIGABRT",
+        9 => "SIGKILL",
+        11 => "SIGSEGV",
+        13 => "SIGPIPE",
+        15 => "SIGTERM",
+        _ => return format!("signal {}", signal),
+    };
+    name.to_string()
+}
+
+/// Number of exit events remembered per service
+const MAX_EXIT_EVENTS: usize = 10;
+
+/// Where a service last accepted connections, remembered after it stops so its URL is
+/// known before it's detected again
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReadyEndpoint {
+    pub port: u16,
+    #[serde(default)]
+    pub scheme: Scheme,
+    #[serde(default)]
+    pub base_path: Option<String>,
+    /// Unix time in milliseconds
+    pub at: u64,
+}
+
+impl ReadyEndpoint {
+    pub fn url(&self) -> String {
+        service_url(self.scheme, self.port, self.base_path.as_deref())
+    }
+}
+
 #[derive(Debug, Default, Serialize, Deserialize)]
 pub struct State {
     pub projects: HashMap<String, ProjectState>,
+    /// Most recently used first
+    #[serde(default)]
+    pub recent: Vec<RecentProject>,
+    /// Exit history by project, then service, oldest first
+    #[serde(default)]
+    pub exits: HashMap<String, HashMap<String, Vec<ExitEvent>>>,
+    /// Last known good endpoint by project, then service
+    #[serde(default)]
+    pub endpoints: HashMap<String, HashMap<String, ReadyEndpoint>>,
 }
 
 impl State {
@@ -37,7 +134,7 @@ impl State {
         config::ensure_config_dir()?;
         let state_file = config::get_state_file();
         let content = serde_json::to_string_pretty(self)?;
-        std::fs::write(&state_file, content)?;
+        config::write_private(&state_file, content)?;
         Ok(())
     }
 
@@ -46,8 +143,7 @@ impl State {
         project_name: &str,
         project_path: PathBuf,
         service_name: &str,
-        pid: u32,
-        port: Option<u16>,
+        service: ServiceState,
     ) {
         let project = self
             .projects
@@ -57,10 +153,79 @@ impl State {
                 services: HashMap::new(),
             });
 
-        project.services.insert(
-            service_name.to_string(),
-            ServiceState { pid, port },
+        diag::record("state", format!("{}/{} running as pid {}", project_name, service_name, service.pid));
+        project.services.insert(service_name.to_string(), service);
+    }
+
+    /// Move a project root to the front of the recent list
+    pub fn touch_recent(&mut self, project_name: &str, project_path: PathBuf) {
+        self.recent.retain(|r| r.path != project_path);
+        self.recent.insert(
+            0,
+            RecentProject {
+                name: project_name.to_string(),
+                path: project_path,
+                last_used: super::now_millis(),
+            },
         );
+        self.recent.truncate(MAX_RECENT_PROJECTS);
+    }
+
+    /// Remember that a service exited on its own, forgetting its oldest exits past the limit
+    pub fn record_exit(&mut self, project_name: &str, service_name: &str, event: ExitEvent) {
+        let history = self
+            .exits
+            .entry(project_name.to_string())
+            .or_default()
+            .entry(service_name.to_string())
+            .or_default();
+        diag::record("state", format!("{}/{} exited: {}", project_name, service_name, event.describe()));
+        history.push(event);
+        if history.len() > MAX_EXIT_EVENTS {
+            history.drain(..history.len() - MAX_EXIT_EVENTS);
+        }
+    }
+
+    /// A service's remembered exits, oldest first
+    pub fn exit_history(&self, project_name: &str, service_name: &str) -> &[ExitEvent] {
+        self.exits
+            .get(project_name)
+            .and_then(|p| p.get(service_name))
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+    }
+
+    /// Remember where a service just came up
+    pub fn record_ready(&mut self, project_name: &str, service_name: &str, endpoint: ReadyEndpoint) {
+        self.endpoints
+            .entry(project_name.to_string())
+            .or_default()
+            .insert(service_name.to_string(), endpoint);
+    }
+
+    /// Where a service last accepted connections, whether or not it's running now
+    pub fn last_endpoint(&self, project_name: &str, service_name: &str) -> Option<&ReadyEndpoint> {
+        self.endpoints.get(project_name).and_then(|p| p.get(service_name))
+    }
+
+    /// Carry a service's entry, exit history and last endpoint over to a new name
+    pub fn rename_service(&mut self, project_name: &str, from: &str, to: &str) {
+        diag::record("state", format!("{}/{} renamed to {}", project_name, from, to));
+        if let Some(project) = self.projects.get_mut(project_name)
+            && let Some(service) = project.services.remove(from)
+        {
+            project.services.insert(to.to_string(), service);
+        }
+        if let Some(exits) = self.exits.get_mut(project_name)
+            && let Some(history) = exits.remove(from)
+        {
+            exits.insert(to.to_string(), history);
+        }
+        if let Some(endpoints) = self.endpoints.get_mut(project_name)
+            && let Some(endpoint) = endpoints.remove(from)
+        {
+            endpoints.insert(to.to_string(), endpoint);
+        }
     }
 
     pub fn remove_project(&mut self, project_name: &str) {
@@ -70,7 +235,9 @@ impl State {
     #[allow(dead_code)]
     pub fn remove_service(&mut self, project_name: &str, service_name: &str) {
         if let Some(project) = self.projects.get_mut(project_name) {
-            project.services.remove(service_name);
+            if project.services.remove(service_name).is_some() {
+                diag::record("state", format!("{}/{} removed", project_name, service_name));
+            }
             if project.services.is_empty() {
                 self.projects.remove(project_name);
             }
@@ -82,53 +249,15 @@ impl State {
     }
 
     pub fn clean_stale_pids(&mut self) {
-        for project in self.projects.values_mut() {
-            project.services.retain(|_, service| {
-                is_service_running(service.port, service.pid)
+        for (project_name, project) in self.projects.iter_mut() {
+            project.services.retain(|service_name, service| {
+                let running = backend().is_service_running(service.port, service.pid);
+                if !running {
+                    diag::record("state", format!("{}/{} pid {} no longer running", project_name, service_name, service.pid));
+                }
+                running
             });
         }
         self.projects.retain(|_, project| !project.services.is_empty());
     }
 }
-
-/// Check if a service is running by port (preferred) or PID fallback
-pub fn is_service_running(port: Option<u16>, pid: u32) -> bool {
-    // If we have a port, check if it's in use (more reliable)
-    if let Some(p) = port {
-        return is_port_in_use(p);
-    }
-    // Fall back to PID check
-    is_pid_running(pid)
-}
-
-/// Check if a port is in use (using lsof for reliability)
-#[cfg(unix)]
-pub fn is_port_in_use(port: u16) -> bool {
-    use std::process::Command;
-    Command::new("lsof")
-        .args(["-ti", &format!(":{}", port)])
-        .output()
-        .map(|o| o.status.success() && !o.stdout.is_empty())
-        .unwrap_or(false)
-}
-
-#[cfg(not(unix))]
-pub fn is_port_in_use(port: u16) -> bool {
-    use std::net::TcpListener;
-    TcpListener::bind(("127.0.0.1", port)).is_err()
-}
-
-#[cfg(unix)]
-fn is_pid_running(pid: u32) -> bool {
-    use std::process::Command;
-    Command::new("kill")
-        .args(["-0", &pid.to_string()])
-        .output()
-        .map(|o| o.status.success())
-        .unwrap_or(false)
-}
-
-#[cfg(not(unix))]
-fn is_pid_running(pid: u32) -> bool {
-    true
-}
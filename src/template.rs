@@ -0,0 +1,133 @@
+use anyhow::Result;
+
+/// A `--format` template such as `"{name}\t{port}\t{status}"`.
+///
+/// `{field}` is replaced per row, `{{`/`}}` produce literal braces and the escapes
+/// `\t`, `\n` and `\\` are expanded so shells don't need `$'...'` quoting.
+#[derive(Debug)]
+pub struct Template {
+    parts: Vec<Part>,
+}
+
+#[derive(Debug)]
+enum Part {
+    Literal(String),
+    Field(String),
+}
+
+impl Template {
+    /// Parse a template, rejecting fields not listed in `fields`
+    pub fn parse(source: &str, fields: &[&str]) -> Result<Self> {
+        let mut parts = Vec::new();
+        let mut literal = String::new();
+        let mut chars = source.chars().peekable();
+
+        while let Some(c) = chars.next() {
+            match c {
+                '\\' => match chars.next() {
+                    Some('t') => literal.push('\t'),
+                    Some('n') => literal.push('\n'),
+                    Some('\\') => literal.push('\\'),
+                    Some(other) => {
+                        literal.push('\\');
+                        literal.push(other);
+                    }
+                    None => literal.push('\\'),
+                },
+                '{' if chars.peek() == Some(&'{') => {
+                    chars.next();
+                    literal.push('{');
+                }
+                '}' if chars.peek() == Some(&'}') => {
+                    chars.next();
+                    literal.push('}');
+                }
+                '{' => {
+                    let mut name = String::new();
+                    loop {
+                        match chars.next() {
+                            Some('}') => break,
+                            Some(c) => name.push(c),
+                            None => anyhow::bail!("Unclosed '{{' in format string"),
+                        }
+                    }
+                    let name = name.trim().to_string();
+                    if !fields.contains(&name.as_str()) {
+                        anyhow::bail!(
+                            "Unknown field '{{{}}}'. Available fields: {}",
+                            name,
+                            fields.iter().map(|f| format!("{{{}}}", f)).collect::<Vec<_>>().join(", ")
+                        );
+                    }
+                    if !literal.is_empty() {
+                        parts.push(Part::Literal(std::mem::take(&mut literal)));
+                    }
+                    parts.push(Part::Field(name));
+                }
+                c => literal.push(c),
+            }
+        }
+        if !literal.is_empty() {
+            parts.push(Part::Literal(literal));
+        }
+
+        Ok(Self { parts })
+    }
+
+    /// Render one row, looking up each field's value with `value`
+    pub fn render(&self, value: impl Fn(&str) -> String) -> String {
+        self.parts
+            .iter()
+            .map(|part| match part {
+                Part::Literal(s) => s.clone(),
+                Part::Field(name) => value(name),
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FIELDS: &[&str] = &["name", "port"];
+
+    fn render(source: &str) -> String {
+        Template::parse(source, FIELDS)
+            .unwrap()
+            .render(|field| match field {
+                "name" => "apps:web".to_string(),
+                "port" => "3000".to_string(),
+                _ => unreachable!(),
+            })
+    }
+
+    #[test]
+    fn fields_are_replaced_per_row() {
+        assert_eq!(render("{name}:{port}"), "apps:web:3000");
+        assert_eq!(render("{ name } on { port }"), "apps:web on 3000");
+        assert_eq!(render("plain"), "plain");
+        assert_eq!(render(""), "");
+    }
+
+    #[test]
+    fn escapes_are_expanded() {
+        assert_eq!(render("{name}\\t{port}\\n"), "apps:web\t3000\n");
+        assert_eq!(render("a\\\\b"), "a\\b");
+        assert_eq!(render("\\x"), "\\x");
+        assert_eq!(render("end\\"), "end\\");
+    }
+
+    #[test]
+    fn doubled_braces_are_literal() {
+        assert_eq!(render("{{name}}"), "{name}");
+        assert_eq!(render("{{{name}}}"), "{apps:web}");
+    }
+
+    #[test]
+    fn unknown_and_unclosed_fields_are_rejected() {
+        let unknown = Template::parse("{status}", FIELDS).unwrap_err().to_string();
+        assert_eq!(unknown, "Unknown field '{status}'. Available fields: {name}, {port}");
+        assert!(Template::parse("{name", FIELDS).is_err());
+    }
+}